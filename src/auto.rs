@@ -0,0 +1,32 @@
+use crate::graph::BullDag;
+use crate::vertex::Vertex;
+use std::fmt::Debug;
+use uuid::Uuid;
+
+impl<T: Clone + Debug> BullDag<T, Uuid> {
+    /// Adds a vertex with no edges under a freshly generated random index,
+    /// and returns the index, for graphs whose vertices have no natural key
+    /// of their own.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use uuid::Uuid;
+    ///
+    /// let mut graph: BullDag<&str, Uuid> = BullDag::new();
+    /// let fetch = graph.add_vertex_auto("fetch");
+    /// let build = graph.add_vertex_auto("build");
+    /// graph.add_edge((fetch, build));
+    ///
+    /// assert!(fetch != build);
+    /// assert!(graph.has_edge(&fetch, &build));
+    /// ```
+    pub fn add_vertex_auto(&mut self, data: T) -> Uuid {
+        let mut ix = Uuid::new_v4();
+        while self.contains_vertex(&ix) {
+            ix = Uuid::new_v4();
+        }
+        self.add_vertex(&Vertex::new(data, ix));
+        ix
+    }
+}
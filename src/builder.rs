@@ -0,0 +1,169 @@
+use crate::edge::Edge;
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Vertex;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+/// The problems [`BullDagBuilder::build`] can find in a batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError<Ix: Index + Debug> {
+    /// The edge's source was never added to the builder
+    NonExistentSource(Edge<Ix>),
+    /// The edge's reference was never added to the builder
+    NonExistentReference(Edge<Ix>),
+    /// The edges contain at least one cycle, holds every vertex that is on
+    /// a cycle or that can only be reached through one
+    WouldCycle(Vec<Ix>),
+}
+
+/// Accumulates vertices and edges for bulk loading without checking for
+/// cycles on every insert the way [`BullDag::add_edge`] does. The whole
+/// batch is validated once, in [`BullDagBuilder::build`], which returns
+/// every problem found rather than silently dropping edges.
+///
+/// Example
+///
+/// ```
+/// use bulldag::builder::BullDagBuilder;
+/// use bulldag::vertex::Vertex;
+///
+/// let mut builder: BullDagBuilder<usize, &str> = BullDagBuilder::new();
+/// builder.add_vertex(Vertex::new(5, "source"));
+/// builder.add_vertex(Vertex::new(4, "reference"));
+/// builder.add_edge("source", "reference");
+///
+/// let graph = builder.build().unwrap();
+/// assert!(graph.len() == 2);
+/// assert!(graph.n_edges() == 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BullDagBuilder<T: Clone + Debug, Ix: Index + Debug> {
+    vertices: HashMap<Ix, Vertex<T, Ix>>,
+    edges: HashSet<Edge<Ix>>,
+    /// The order the vertices were first added in, see
+    /// [`BullDag::insertion_seq`]
+    sequence: HashMap<Ix, u64>,
+}
+
+impl<T, Ix> Default for BullDagBuilder<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, Ix> BullDagBuilder<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Creates a new, empty BullDagBuilder
+    pub fn new() -> BullDagBuilder<T, Ix> {
+        BullDagBuilder {
+            vertices: HashMap::new(),
+            edges: HashSet::new(),
+            sequence: HashMap::new(),
+        }
+    }
+
+    /// Adds a vertex, replacing any previously added vertex with the same
+    /// index but keeping its place in the insertion order. Only the data
+    /// and index are kept, edges are taken from
+    /// [`BullDagBuilder::add_edge`].
+    pub fn add_vertex(&mut self, vertex: Vertex<T, Ix>) -> &mut Self {
+        let index = vertex.get_index();
+        let next = self.sequence.len() as u64;
+        self.sequence.entry(index.clone()).or_insert(next);
+        self.vertices
+            .insert(index.clone(), Vertex::new(vertex.get_data(), index));
+        self
+    }
+
+    /// Adds an edge between two indices, no checks are made until
+    /// [`BullDagBuilder::build`]
+    pub fn add_edge(&mut self, source: Ix, reference: Ix) -> &mut Self {
+        self.edges.insert(Edge::new(source, reference));
+        self
+    }
+
+    /// Returns the number of vertices added so far
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Returns the number of distinct edges added so far
+    pub fn n_edges(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Validates the batch and builds the graph. Fails if any edge points at
+    /// a vertex that was never added, or if the edges contain a cycle. The
+    /// vertices are numbered in the order they were first added, as if they
+    /// had been added to the graph one by one.
+    pub fn build(self) -> Result<BullDag<T, Ix>, Vec<BuildError<Ix>>> {
+        let mut errors = vec![];
+        let mut graph = BullDag::new();
+        graph.vertices = self.vertices;
+        graph.next_sequence = self.sequence.len() as u64;
+        graph.sequence = self.sequence;
+        graph.reserve_edges(self.edges.len());
+
+        for edge in self.edges {
+            if !graph.vertices.contains_key(&edge.get_source()) {
+                errors.push(BuildError::NonExistentSource(edge));
+                continue;
+            }
+            if !graph.vertices.contains_key(&edge.get_reference()) {
+                errors.push(BuildError::NonExistentReference(edge));
+                continue;
+            }
+
+            if let Some(vtx) = graph.vertices.get_mut(&edge.get_source()) {
+                vtx.add_edge(&edge);
+            }
+            if let Some(vtx) = graph.vertices.get_mut(&edge.get_reference()) {
+                vtx.add_edge(&edge);
+            }
+            graph.edges.insert(edge);
+        }
+
+        let order = graph.topological_order();
+        if order.len() < graph.vertices.len() {
+            let ordered: HashSet<Ix> = order.into_iter().collect();
+            let cyclic = graph
+                .vertices
+                .keys()
+                .filter(|ix| !ordered.contains(ix))
+                .cloned()
+                .collect();
+            errors.push(BuildError::WouldCycle(cyclic));
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        graph.roots = graph
+            .vertices
+            .values()
+            .filter(|vtx| vtx.n_sources() == 0)
+            .map(|vtx| vtx.get_index())
+            .collect();
+        graph.leaves = graph
+            .vertices
+            .values()
+            .filter(|vtx| vtx.n_references() == 0)
+            .map(|vtx| vtx.get_index())
+            .collect();
+
+        Ok(graph)
+    }
+}
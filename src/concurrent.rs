@@ -0,0 +1,344 @@
+use crate::edge::{Edge, EdgeSpec, IntoEdgeSpec};
+use crate::graph::{BullDag, GraphError, GraphResult};
+use crate::index::Index;
+use crate::vertex::{Direction, Vertex};
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+const DEFAULT_SHARDS: usize = 16;
+
+type Shard<T, Ix> = RwLock<HashMap<Ix, Vertex<T, Ix>>>;
+
+/// A thread-safe graph for many concurrent readers and a stream of writes.
+///
+/// The vertices are spread over a number of shards, each behind its own
+/// `RwLock`, and readers only ever hold the lock of the shard they are
+/// looking at, and only for a single lookup. Writers are serialized by a
+/// separate mutex, so a cycle check sees a graph that can't change under
+/// it, and then write-lock just the shards of the two vertices they touch.
+/// A query therefore only waits on a write that is updating the very shard
+/// it needs, rather than on every write.
+///
+/// Reads are not transactional: a traversal that runs while edges are
+/// being added may see some of them and not others. Use
+/// [`SharedBullDag::to_dag`] for a consistent copy.
+///
+/// Example
+///
+/// ```
+/// use bulldag::concurrent::SharedBullDag;
+/// use bulldag::vertex::Vertex;
+/// use std::sync::Arc;
+///
+/// let graph: Arc<SharedBullDag<usize, u64>> = Arc::new(SharedBullDag::new());
+/// graph.add_vertex(&Vertex::new(0, 0));
+///
+/// let writer = {
+///     let graph = graph.clone();
+///     std::thread::spawn(move || {
+///         for i in 1..100 {
+///             graph.add_vertex(&Vertex::new(i as usize, i));
+///             graph.add_edge_ix(&(i - 1), &i).unwrap();
+///         }
+///     })
+/// };
+/// let _ = graph.get_roots();
+/// writer.join().unwrap();
+///
+/// assert!(graph.len() == 100);
+/// assert!(graph.n_edges() == 99);
+/// assert!(graph.trace(&0, bulldag::vertex::Direction::Reference).len() == 100);
+/// ```
+#[derive(Debug)]
+pub struct SharedBullDag<T: Clone + Debug, Ix: Index + Debug> {
+    shards: Vec<Shard<T, Ix>>,
+    roots: RwLock<HashSet<Ix>>,
+    leaves: RwLock<HashSet<Ix>>,
+    n_edges: AtomicUsize,
+    /// The insertion order, see [`BullDag::insertion_seq`]
+    sequence: RwLock<HashMap<Ix, u64>>,
+    next_sequence: AtomicU64,
+    writer: Mutex<()>,
+    hasher: RandomState,
+}
+
+impl<T, Ix> Default for SharedBullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A panic while holding one of the locks can't leave a shard half updated,
+// every write is a single insert, so poisoned locks are used as they are.
+fn read<L>(lock: &RwLock<L>) -> RwLockReadGuard<'_, L> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write<L>(lock: &RwLock<L>) -> RwLockWriteGuard<'_, L> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<T, Ix> SharedBullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Creates a new, empty SharedBullDag with the default number of shards
+    pub fn new() -> SharedBullDag<T, Ix> {
+        SharedBullDag::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Creates a new, empty SharedBullDag with `n` shards, at least one
+    pub fn with_shards(n: usize) -> SharedBullDag<T, Ix> {
+        SharedBullDag {
+            shards: (0..n.max(1)).map(|_| RwLock::new(HashMap::new())).collect(),
+            roots: RwLock::new(HashSet::new()),
+            leaves: RwLock::new(HashSet::new()),
+            n_edges: AtomicUsize::new(0),
+            sequence: RwLock::new(HashMap::new()),
+            next_sequence: AtomicU64::new(0),
+            writer: Mutex::new(()),
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn shard(&self, ix: &Ix) -> &Shard<T, Ix> {
+        let n = self.hasher.hash_one(ix) as usize % self.shards.len();
+        &self.shards[n]
+    }
+
+    fn lock_writer(&self) -> MutexGuard<'_, ()> {
+        self.writer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Adds a vertex as a root and a leaf. If a vertex with the same index
+    /// is already in the graph only its data is replaced, edges are only
+    /// ever added with [`SharedBullDag::add_edge_ix`] so they can't be
+    /// overwritten.
+    pub fn add_vertex(&self, vertex: &Vertex<T, Ix>) {
+        let _writer = self.lock_writer();
+        self.insert_vertex(vertex);
+    }
+
+    fn insert_vertex(&self, vertex: &Vertex<T, Ix>) {
+        let index = vertex.get_index();
+        let mut shard = write(self.shard(&index));
+        if let Some(vtx) = shard.get_mut(&index) {
+            vtx.set_data(vertex.get_data());
+            return;
+        }
+        shard.insert(index.clone(), Vertex::new(vertex.get_data(), index.clone()));
+        drop(shard);
+
+        write(&self.roots).insert(index.clone());
+        write(&self.leaves).insert(index.clone());
+        let seq = self.next_sequence.fetch_add(1, Ordering::SeqCst);
+        write(&self.sequence).insert(index, seq);
+    }
+
+    /// Adds an edge between two vertices that are already in the graph.
+    /// Fails with `NonExistentSource` or `NonExistentReference` if either
+    /// vertex is missing, and with `WouldCycle` if the edge would create a
+    /// cycle, see [`BullDag::add_edge_ix`].
+    pub fn add_edge_ix(&self, source: &Ix, reference: &Ix) -> GraphResult {
+        let _writer = self.lock_writer();
+        self.insert_edge(source, reference)
+    }
+
+    /// Adds an edge, given as anything [`BullDag::add_edge`] takes, along
+    /// with either of its vertices that is missing when it's given as a
+    /// pair of vertices. An edge that would create a cycle or that refers
+    /// to a missing index is silently dropped, as in [`BullDag::add_edge`].
+    pub fn add_edge(&self, edge: impl IntoEdgeSpec<T, Ix>) {
+        let _writer = self.lock_writer();
+        let (source, reference) = match edge.into_edge_spec() {
+            EdgeSpec::Vertices(source, reference) => {
+                let (s, r) = (source.get_index(), reference.get_index());
+                if s == r || self.reaches(&r, &s) {
+                    return;
+                }
+                for vtx in [&source, &reference] {
+                    if !self.contains_vertex(&vtx.get_index()) {
+                        self.insert_vertex(vtx);
+                    }
+                }
+                (s, r)
+            }
+            EdgeSpec::Indices(source, reference) => (source, reference),
+        };
+        let _ = self.insert_edge(&source, &reference);
+    }
+
+    /// Adds the edge, the caller must hold the writer lock
+    fn insert_edge(&self, source: &Ix, reference: &Ix) -> GraphResult {
+        if !self.contains_vertex(source) {
+            return Err(GraphError::NonExistentSource);
+        }
+        if !self.contains_vertex(reference) {
+            return Err(GraphError::NonExistentReference);
+        }
+        if source == reference || self.reaches(reference, source) {
+            return Err(GraphError::WouldCycle);
+        }
+
+        let edge = Edge::new(source.clone(), reference.clone());
+        let mut shard = write(self.shard(source));
+        let added = match shard.get_mut(source) {
+            Some(vtx) if !vtx.is_reference(reference) => {
+                vtx.add_edge(&edge);
+                true
+            }
+            _ => false,
+        };
+        drop(shard);
+        if !added {
+            return Ok(());
+        }
+        if let Some(vtx) = write(self.shard(reference)).get_mut(reference) {
+            vtx.add_edge(&edge);
+        }
+        self.n_edges.fetch_add(1, Ordering::SeqCst);
+        write(&self.leaves).remove(source);
+        write(&self.roots).remove(reference);
+
+        Ok(())
+    }
+
+    /// Whether `target` can be reached from `start` by following references
+    fn reaches(&self, start: &Ix, target: &Ix) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![start.clone()];
+        while let Some(ix) = stack.pop() {
+            if &ix == target {
+                return true;
+            }
+            if !visited.insert(ix.clone()) {
+                continue;
+            }
+            if let Some(vtx) = read(self.shard(&ix)).get(&ix) {
+                stack.extend(vtx.get_references().into_iter().cloned());
+            }
+        }
+        false
+    }
+
+    /// Returns a copy of the vertex, since a reference can't outlive the
+    /// shard's lock, see [`SharedBullDag::with_vertex`]
+    pub fn get_vertex(&self, target: &Ix) -> Option<Vertex<T, Ix>> {
+        read(self.shard(target)).get(target).cloned()
+    }
+
+    /// Calls `f` with a reference to the vertex while holding its shard's
+    /// read lock, avoiding the copy made by [`SharedBullDag::get_vertex`]
+    pub fn with_vertex<R>(&self, target: &Ix, f: impl FnOnce(&Vertex<T, Ix>) -> R) -> Option<R> {
+        read(self.shard(target)).get(target).map(f)
+    }
+
+    pub fn contains_vertex(&self, target: &Ix) -> bool {
+        read(self.shard(target)).contains_key(target)
+    }
+
+    /// Returns every vertex reachable from `start` in the given direction,
+    /// including `start` itself, or an empty `Vec` if it is missing
+    pub fn trace(&self, start: &Ix, direction: Direction) -> Vec<Ix> {
+        let mut visited: HashSet<Ix> = HashSet::new();
+        let mut order = vec![];
+        let mut queue = VecDeque::from([start.clone()]);
+        while let Some(ix) = queue.pop_front() {
+            if visited.contains(&ix) {
+                continue;
+            }
+            let neighbors = self.with_vertex(&ix, |vtx| {
+                vtx.neighbors(direction)
+                    .into_iter()
+                    .cloned()
+                    .collect::<Vec<Ix>>()
+            });
+            if let Some(neighbors) = neighbors {
+                visited.insert(ix.clone());
+                order.push(ix);
+                queue.extend(neighbors);
+            }
+        }
+        order
+    }
+
+    pub fn get_roots(&self) -> HashSet<Ix> {
+        read(&self.roots).clone()
+    }
+
+    pub fn n_roots(&self) -> usize {
+        read(&self.roots).len()
+    }
+
+    pub fn get_leaves(&self) -> HashSet<Ix> {
+        read(&self.leaves).clone()
+    }
+
+    pub fn n_leaves(&self) -> usize {
+        read(&self.leaves).len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| read(shard).len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| read(shard).is_empty())
+    }
+
+    pub fn n_edges(&self) -> usize {
+        self.n_edges.load(Ordering::SeqCst)
+    }
+
+    /// Returns a consistent copy of the graph as a [`BullDag`], blocking
+    /// writers, but not readers, while it is made
+    pub fn to_dag(&self) -> BullDag<T, Ix> {
+        let _writer = self.lock_writer();
+        let mut graph = BullDag::new();
+        for shard in self.shards.iter() {
+            for (ix, vtx) in read(shard).iter() {
+                graph.edges.extend(
+                    vtx.get_references()
+                        .into_iter()
+                        .map(|r| Edge::new(ix.clone(), r.clone())),
+                );
+                graph.vertices.insert(ix.clone(), vtx.clone());
+            }
+        }
+        graph.roots = self.get_roots();
+        graph.leaves = self.get_leaves();
+        graph.sequence = read(&self.sequence).clone();
+        graph.next_sequence = self.next_sequence.load(Ordering::SeqCst);
+        graph
+    }
+}
+
+impl<T, Ix> From<BullDag<T, Ix>> for SharedBullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn from(graph: BullDag<T, Ix>) -> Self {
+        let shared = SharedBullDag::new();
+        shared.n_edges.store(graph.edges.len(), Ordering::SeqCst);
+        *write(&shared.roots) = graph.roots;
+        *write(&shared.leaves) = graph.leaves;
+        *write(&shared.sequence) = graph.sequence;
+        shared
+            .next_sequence
+            .store(graph.next_sequence, Ordering::SeqCst);
+        for (ix, vtx) in graph.vertices {
+            write(shared.shard(&ix)).insert(ix, vtx);
+        }
+        shared
+    }
+}
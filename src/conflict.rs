@@ -0,0 +1,105 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::observe::Event;
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Declares that the vertices at `a` and `b` conflict, e.g. because
+    /// they spend the same output, so at most one of them can be accepted.
+    /// Conflicts are symmetric and independent of the edges. Returns false
+    /// if either vertex is missing or they are the same vertex. Conflicts
+    /// are dropped along with their vertices.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "spend_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "spend_2");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3)]);
+    ///
+    /// assert!(graph.add_conflict(&"spend_1", &"spend_2"));
+    /// assert!(graph.conflicts_of(&"spend_2") == ["spend_1"].into());
+    /// assert!(!graph.is_conflict_free(&["source", "spend_1", "spend_2"]));
+    /// assert!(graph.is_conflict_free(&["source", "spend_1"]));
+    /// ```
+    pub fn add_conflict(&mut self, a: &Ix, b: &Ix) -> bool {
+        if a == b || !self.contains_vertex(a) || !self.contains_vertex(b) {
+            return false;
+        }
+        self.conflicts
+            .entry(a.clone())
+            .or_default()
+            .insert(b.clone());
+        self.conflicts
+            .entry(b.clone())
+            .or_default()
+            .insert(a.clone());
+        self.notify(Event::ConflictAdded(a, b));
+        true
+    }
+
+    /// Withdraws a conflict, returning false if there was none
+    pub fn remove_conflict(&mut self, a: &Ix, b: &Ix) -> bool {
+        let removed = self.unlink_conflict(a, b);
+        self.unlink_conflict(b, a);
+        if removed {
+            self.notify(Event::ConflictRemoved(a, b));
+        }
+        removed
+    }
+
+    /// Removes `b` from the conflicts of `a`, dropping the entry of `a`
+    /// once it has none left
+    fn unlink_conflict(&mut self, a: &Ix, b: &Ix) -> bool {
+        let Some(conflicts) = self.conflicts.get_mut(a) else {
+            return false;
+        };
+        let removed = conflicts.remove(b);
+        if conflicts.is_empty() {
+            self.conflicts.remove(a);
+        }
+        removed
+    }
+
+    /// Drops every conflict of the vertex at `ix`
+    pub(crate) fn clear_conflicts(&mut self, ix: &Ix) {
+        if let Some(conflicts) = self.conflicts.remove(ix) {
+            conflicts.iter().for_each(|other| {
+                self.unlink_conflict(other, ix);
+            });
+        }
+    }
+
+    /// Returns the vertices that conflict with the vertex at `ix`
+    pub fn conflicts_of(&self, ix: &Ix) -> HashSet<Ix> {
+        self.conflicts.get(ix).cloned().unwrap_or_default()
+    }
+
+    pub fn has_conflict(&self, a: &Ix, b: &Ix) -> bool {
+        self.conflicts
+            .get(a)
+            .is_some_and(|conflicts| conflicts.contains(b))
+    }
+
+    /// Whether no two of the given vertices conflict
+    pub fn is_conflict_free<'a>(&self, subset: impl IntoIterator<Item = &'a Ix>) -> bool
+    where
+        Ix: 'a,
+    {
+        let subset: HashSet<&Ix> = subset.into_iter().collect();
+        subset.iter().all(|ix| {
+            self.conflicts
+                .get(*ix)
+                .is_none_or(|conflicts| conflicts.iter().all(|other| !subset.contains(other)))
+        })
+    }
+}
@@ -0,0 +1,341 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::ordering::OrderingRule;
+use crate::reachability::ReachabilityIndex;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+/// How GHOSTDAG colored the blocks a block merges, seen from that block.
+/// A block's sources are its parents, and its selected parent is the
+/// parent with the highest blue score, ties going to the greatest index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockColoring<Ix> {
+    /// `None` for a block without parents
+    pub selected_parent: Option<Ix>,
+    /// The number of blue blocks in the block's past
+    pub blue_score: u64,
+    /// The blocks in the past of the block but not of its selected parent,
+    /// other than the selected parent itself, that are blue, in the order
+    /// they were colored
+    pub mergeset_blues: Vec<Ix>,
+    /// The rest of the merged blocks, which are red
+    pub mergeset_reds: Vec<Ix>,
+}
+
+/// Coloring state kept for each block while the DAG is processed
+struct Colored<Ix> {
+    coloring: BlockColoring<Ix>,
+    /// The number of blues in the anticone of the selected parent and of
+    /// each merged blue, as seen from the block
+    blue_anticone_sizes: HashMap<Ix, usize>,
+}
+
+/// The GHOSTDAG coloring of a block DAG, as in the PHANTOM paper, and the
+/// total order over its blocks that follows from it. Built with
+/// [`BullDag::ghostdag`].
+///
+/// Every block is colored blue or red from the point of view of each later
+/// block, so that the blue blocks form a k-cluster: no blue block has more
+/// than `k` blue blocks in its anticone. The blocks are ordered along the
+/// chain of selected parents down from the virtual block that merges every
+/// tip, with each chain block preceded by the blocks it merges in
+/// topological order.
+///
+/// Reachability is looked up in a [`ReachabilityIndex`] built for the
+/// graph, which takes a bit per pair of blocks.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::vertex::Vertex;
+///
+/// // Two blocks mined in parallel on top of genesis, merged by a third
+/// let mut graph: BullDag<(), u32> = BullDag::new();
+/// let v: Vec<Vertex<(), u32>> = (0..4).map(|i| Vertex::new((), i)).collect();
+/// graph.extend_from_edges(&[(&v[0], &v[1]), (&v[0], &v[2]), (&v[1], &v[3]), (&v[2], &v[3])]);
+///
+/// // With k = 0 only one of the parallel blocks can be blue
+/// let strict = graph.ghostdag(0);
+/// assert!(strict.blue_set().len() == 3);
+/// assert!(strict.coloring(&3).unwrap().mergeset_reds == vec![1]);
+///
+/// // With k = 1 both can
+/// let lenient = graph.ghostdag(1);
+/// assert!(lenient.blue_set().len() == 4);
+/// assert!(lenient.order() == [0, 2, 1, 3]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Ghostdag<Ix: Index> {
+    k: usize,
+    blocks: HashMap<Ix, BlockColoring<Ix>>,
+    virtual_block: BlockColoring<Ix>,
+    blues: HashSet<Ix>,
+    order: Vec<Ix>,
+}
+
+/// Colors the blocks in topological order, so each block's parents are
+/// done before it
+struct Colorer<'a, T: Clone + Debug, Ix: Index + Debug + Ord> {
+    graph: &'a BullDag<T, Ix>,
+    k: usize,
+    reach: ReachabilityIndex<Ix>,
+    position: HashMap<Ix, usize>,
+    colored: HashMap<Ix, Colored<Ix>>,
+}
+
+impl<'a, T, Ix> Colorer<'a, T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    fn is_anticone(&self, a: &Ix, b: &Ix) -> bool {
+        !self.reach.is_reachable(a, b) && !self.reach.is_reachable(b, a)
+    }
+
+    /// The number of blues in the anticone of `blue`, seen from the block
+    /// being colored, whose own sizes so far are `local`
+    fn blue_anticone_size(
+        &self,
+        blue: &Ix,
+        local: &HashMap<Ix, usize>,
+        selected_parent: &Ix,
+    ) -> usize {
+        if let Some(size) = local.get(blue) {
+            return *size;
+        }
+        let mut block = Some(selected_parent);
+        while let Some(ix) = block {
+            let colored = &self.colored[ix];
+            if let Some(size) = colored.blue_anticone_sizes.get(blue) {
+                return *size;
+            }
+            block = colored.coloring.selected_parent.as_ref();
+        }
+        0
+    }
+
+    /// Colors a block with the given parents, which for the virtual block
+    /// are the tips of the graph
+    fn color(&self, parents: &[Ix]) -> Colored<Ix> {
+        let Some(selected_parent) = parents
+            .iter()
+            .max_by_key(|ix| (self.colored[*ix].coloring.blue_score, *ix))
+            .cloned()
+        else {
+            return Colored {
+                coloring: BlockColoring {
+                    selected_parent: None,
+                    blue_score: 0,
+                    mergeset_blues: vec![],
+                    mergeset_reds: vec![],
+                },
+                blue_anticone_sizes: HashMap::new(),
+            };
+        };
+
+        // Everything the other parents bring in that the selected parent
+        // doesn't already have in its past
+        let mut mergeset: Vec<Ix> = vec![];
+        let mut seen: HashSet<Ix> = HashSet::new();
+        let mut stack: Vec<Ix> = parents.to_vec();
+        while let Some(ix) = stack.pop() {
+            if self.reach.is_reachable(&ix, &selected_parent) || !seen.insert(ix.clone()) {
+                continue;
+            }
+            stack.extend(self.graph.vertices[&ix].get_sources().into_iter().cloned());
+            mergeset.push(ix);
+        }
+        mergeset.sort_by_key(|ix| self.position[ix]);
+
+        let mut blues: Vec<Ix> = vec![];
+        let mut reds: Vec<Ix> = vec![];
+        let mut sizes: HashMap<Ix, usize> = HashMap::from([(selected_parent.clone(), 0)]);
+        for candidate in mergeset {
+            // The blues seen from this block that are in the candidate's
+            // anticone. Below a chain block in the candidate's past
+            // everything is in its past too.
+            let mut anticone_blues: Vec<Ix> = blues
+                .iter()
+                .filter(|blue| self.is_anticone(blue, &candidate))
+                .cloned()
+                .collect();
+            let mut block = Some(&selected_parent);
+            while let Some(ix) = block {
+                if self.reach.is_reachable(ix, &candidate) || anticone_blues.len() > self.k {
+                    break;
+                }
+                let colored = &self.colored[ix];
+                anticone_blues.push(ix.clone());
+                anticone_blues.extend(
+                    colored
+                        .coloring
+                        .mergeset_blues
+                        .iter()
+                        .filter(|blue| self.is_anticone(blue, &candidate))
+                        .cloned(),
+                );
+                block = colored.coloring.selected_parent.as_ref();
+            }
+
+            let is_blue = anticone_blues.len() <= self.k
+                && anticone_blues
+                    .iter()
+                    .all(|blue| self.blue_anticone_size(blue, &sizes, &selected_parent) < self.k);
+            if !is_blue {
+                reds.push(candidate);
+                continue;
+            }
+            for blue in &anticone_blues {
+                let size = self.blue_anticone_size(blue, &sizes, &selected_parent);
+                sizes.insert(blue.clone(), size + 1);
+            }
+            sizes.insert(candidate.clone(), anticone_blues.len());
+            blues.push(candidate);
+        }
+
+        Colored {
+            coloring: BlockColoring {
+                blue_score: self.colored[&selected_parent].coloring.blue_score
+                    + 1
+                    + blues.len() as u64,
+                selected_parent: Some(selected_parent),
+                mergeset_blues: blues,
+                mergeset_reds: reds,
+            },
+            blue_anticone_sizes: sizes,
+        }
+    }
+}
+
+impl<Ix: Index + Debug + Ord> Ghostdag<Ix> {
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns how the block at `ix` colored the blocks it merges, or
+    /// `None` if there is no such block
+    pub fn coloring(&self, ix: &Ix) -> Option<&BlockColoring<Ix>> {
+        self.blocks.get(ix)
+    }
+
+    pub fn blue_score(&self, ix: &Ix) -> Option<u64> {
+        self.blocks.get(ix).map(|block| block.blue_score)
+    }
+
+    /// Returns the tip the virtual block selects, the head of the chain
+    /// the order follows, or `None` for an empty graph
+    pub fn selected_tip(&self) -> Option<&Ix> {
+        self.virtual_block.selected_parent.as_ref()
+    }
+
+    /// Returns the blue blocks, seen from the virtual block
+    pub fn blue_set(&self) -> &HashSet<Ix> {
+        &self.blues
+    }
+
+    pub fn is_blue(&self, ix: &Ix) -> bool {
+        self.blues.contains(ix)
+    }
+
+    /// Returns every block in the total order
+    pub fn order(&self) -> &[Ix] {
+        &self.order
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    /// Colors the graph with GHOSTDAG for anticone size `k` and orders its
+    /// blocks, see [`Ghostdag`]
+    pub fn ghostdag(&self, k: usize) -> Ghostdag<Ix> {
+        let order = self.sorted_topological_order();
+        let mut colorer = Colorer {
+            graph: self,
+            k,
+            reach: ReachabilityIndex::new(self),
+            position: order
+                .iter()
+                .enumerate()
+                .map(|(i, ix)| (ix.clone(), i))
+                .collect(),
+            colored: HashMap::with_capacity(order.len()),
+        };
+        for ix in &order {
+            let parents: Vec<Ix> = self.vertices[ix]
+                .get_sources()
+                .into_iter()
+                .cloned()
+                .collect();
+            let colored = colorer.color(&parents);
+            colorer.colored.insert(ix.clone(), colored);
+        }
+
+        let mut tips: Vec<Ix> = self.leaves.iter().cloned().collect();
+        tips.sort();
+        let virtual_block = colorer.color(&tips).coloring;
+
+        // Walk the chain down from the virtual block, then lay each chain
+        // block's merged blocks out before it on the way back up
+        let mut chain = vec![&virtual_block];
+        let mut blues = HashSet::new();
+        while let Some(parent) = chain
+            .last()
+            .and_then(|block| block.selected_parent.as_ref())
+        {
+            blues.insert(parent.clone());
+            chain.push(&colorer.colored[parent].coloring);
+        }
+        let mut total = vec![];
+        for (i, block) in chain.iter().enumerate().rev() {
+            let mut merged: Vec<&Ix> = block
+                .mergeset_blues
+                .iter()
+                .chain(block.mergeset_reds.iter())
+                .collect();
+            merged.sort_by_key(|ix| colorer.position[*ix]);
+            blues.extend(block.mergeset_blues.iter().cloned());
+            total.extend(merged.into_iter().cloned());
+            if i > 0 {
+                if let Some(ix) = chain[i - 1].selected_parent.as_ref() {
+                    total.push(ix.clone());
+                }
+            }
+        }
+
+        Ghostdag {
+            k,
+            blocks: colorer
+                .colored
+                .into_iter()
+                .map(|(ix, colored)| (ix, colored.coloring))
+                .collect(),
+            virtual_block,
+            blues,
+            order: total,
+        }
+    }
+}
+
+/// GHOSTDAG with anticone size `k` as an [`OrderingRule`]
+#[derive(Debug, Clone, Copy)]
+pub struct GhostdagRule {
+    pub k: usize,
+}
+
+impl<T, Ix> OrderingRule<T, Ix> for GhostdagRule
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    fn order(&self, graph: &BullDag<T, Ix>) -> Vec<Ix> {
+        graph.ghostdag(self.k).order
+    }
+
+    fn selected_tip(&self, graph: &BullDag<T, Ix>) -> Option<Ix> {
+        graph.ghostdag(self.k).selected_tip().cloned()
+    }
+}
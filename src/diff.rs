@@ -0,0 +1,140 @@
+use crate::edge::Edge;
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Vertex;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+/// The structural difference between two graphs, produced by
+/// [`BullDag::diff`] and replayed with [`BullDag::apply`]. Only the
+/// vertex data and the edge pairs are recorded, the sources, references,
+/// roots and leaves are rebuilt by the receiving graph as the edges are
+/// applied, which keeps the patch small enough to send over the network
+/// instead of the entire graph.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphPatch<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    pub added_vertices: Vec<(Ix, T)>,
+    pub removed_vertices: Vec<Ix>,
+    pub changed_vertices: Vec<(Ix, T)>,
+    pub added_edges: Vec<Edge<Ix>>,
+    pub removed_edges: Vec<Edge<Ix>>,
+}
+
+impl<T, Ix> GraphPatch<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns true if applying the patch would not change the graph
+    pub fn is_empty(&self) -> bool {
+        self.added_vertices.is_empty()
+            && self.removed_vertices.is_empty()
+            && self.changed_vertices.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + PartialEq,
+    Ix: Index + Debug,
+{
+    /// Computes the patch that turns `self` into `other`.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "new_reference");
+    ///
+    /// let mut local: BullDag<usize, &str> = BullDag::new();
+    /// local.add_edge(&(&v1, &v2));
+    ///
+    /// let mut remote = local.clone();
+    /// remote.add_edge(&(&v1, &v3));
+    ///
+    /// let patch = local.diff(&remote);
+    /// assert!(patch.added_vertices.len() == 1);
+    /// assert!(patch.added_edges.len() == 1);
+    ///
+    /// local.apply(patch);
+    /// assert!(local.len() == 3);
+    /// assert!(local.n_edges() == 2);
+    /// ```
+    pub fn diff(&self, other: &BullDag<T, Ix>) -> GraphPatch<T, Ix> {
+        let mut added_vertices = vec![];
+        let mut changed_vertices = vec![];
+        other
+            .vertices
+            .iter()
+            .for_each(|(ix, vtx)| match self.vertices.get(ix) {
+                Some(local) => {
+                    if local.get_data() != vtx.get_data() {
+                        changed_vertices.push((ix.clone(), vtx.get_data()));
+                    }
+                }
+                None => added_vertices.push((ix.clone(), vtx.get_data())),
+            });
+
+        let removed_vertices = self
+            .vertices
+            .keys()
+            .filter(|ix| !other.vertices.contains_key(ix))
+            .cloned()
+            .collect();
+
+        let added_edges = other.edges.difference(&self.edges).cloned().collect();
+        let removed_edges = self.edges.difference(&other.edges).cloned().collect();
+
+        GraphPatch {
+            added_vertices,
+            removed_vertices,
+            changed_vertices,
+            added_edges,
+            removed_edges,
+        }
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Replays a patch produced by [`BullDag::diff`]. Removals are applied
+    /// first so that the graph never holds more than the union of the
+    /// two graphs, then the new vertices, the data changes and finally the
+    /// new edges. As with [`BullDag::add_edge`] an edge that would create a
+    /// cycle is not added.
+    pub fn apply(&mut self, patch: GraphPatch<T, Ix>) {
+        patch.removed_edges.iter().for_each(|e| {
+            self.remove_edge(e);
+        });
+
+        patch.removed_vertices.into_iter().for_each(|ix| {
+            self.remove_vertex(ix);
+        });
+
+        patch.added_vertices.into_iter().for_each(|(ix, data)| {
+            if !self.contains_vertex(&ix) {
+                self.add_vertex(&Vertex::new(data, ix));
+            }
+        });
+
+        patch.changed_vertices.into_iter().for_each(|(ix, data)| {
+            self.set_vertex_data(&ix, data);
+        });
+
+        patch.added_edges.iter().for_each(|e| {
+            let _ = self.add_edge_ix(&e.get_source(), &e.get_reference());
+        });
+    }
+}
@@ -0,0 +1,78 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Direction;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// Walks two vertices up the dominator tree until they meet, by position in
+/// topological order
+fn intersect(idom: &[usize], mut a: usize, mut b: usize) -> usize {
+    while a != b {
+        while a > b {
+            a = idom[a];
+        }
+        while b > a {
+            b = idom[b];
+        }
+    }
+    a
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns the immediate dominator of every vertex reachable from
+    /// `root` by following references, other than `root` itself. A vertex
+    /// dominates another when every path from `root` to the other passes
+    /// through it, and the immediate dominator is the closest one. Returns
+    /// an empty map if `root` is not in the graph.
+    ///
+    /// This is the Cooper, Harvey and Kennedy algorithm, which needs just
+    /// one pass when the vertices are visited in topological order, as
+    /// each vertex's sources are then all settled before it.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let entry: Vertex<usize, &str> = Vertex::new(0, "entry");
+    /// let then: Vertex<usize, &str> = Vertex::new(1, "then");
+    /// let other: Vertex<usize, &str> = Vertex::new(2, "else");
+    /// let join: Vertex<usize, &str> = Vertex::new(3, "join");
+    /// graph.extend_from_edges(&[(&entry, &then), (&entry, &other), (&then, &join), (&other, &join)]);
+    ///
+    /// let idom = graph.dominators(&"entry");
+    /// assert!(idom[&"then"] == "entry");
+    /// assert!(idom[&"join"] == "entry");
+    /// assert!(!idom.contains_key(&"entry"));
+    /// ```
+    pub fn dominators(&self, root: &Ix) -> HashMap<Ix, Ix> {
+        let reachable = self.reachable(std::slice::from_ref(root), Direction::Reference, |_| false);
+        // Every vertex in the set is reachable from the root, so the root
+        // comes first.
+        let order = self.topological_order_of(&reachable);
+        let position: HashMap<&Ix, usize> =
+            order.iter().enumerate().map(|(i, ix)| (ix, i)).collect();
+
+        let mut idom = vec![0; order.len()];
+        for (i, ix) in order.iter().enumerate().skip(1) {
+            idom[i] = self.vertices[ix]
+                .get_sources()
+                .into_iter()
+                .filter_map(|source| position.get(source).copied())
+                .reduce(|a, b| intersect(&idom, a, b))
+                .unwrap_or(0);
+        }
+
+        order
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, ix)| (ix.clone(), order[idom[i]].clone()))
+            .collect()
+    }
+}
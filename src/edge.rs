@@ -1,5 +1,8 @@
 use crate::index::Index;
+use crate::memory::table_bytes;
+use crate::vertex::Vertex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Debug;
 
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, PartialEq, Eq)]
@@ -21,3 +24,187 @@ impl<Ix: Index + Debug> Edge<Ix> {
         self.source.clone()
     }
 }
+
+/// A value for some of the edges of a graph, kept by source and then by
+/// reference so that, unlike a map keyed by [`Edge`], it serializes to
+/// JSON
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct EdgeMap<Ix: Index, V>(HashMap<Ix, HashMap<Ix, V>>);
+
+impl<Ix: Index + Debug, V> Default for EdgeMap<Ix, V> {
+    fn default() -> Self {
+        EdgeMap(HashMap::new())
+    }
+}
+
+impl<Ix: Index + Debug, V> EdgeMap<Ix, V> {
+    pub(crate) fn get(&self, edge: &Edge<Ix>) -> Option<&V> {
+        self.between(&edge.source, &edge.reference)
+    }
+
+    pub(crate) fn between(&self, source: &Ix, reference: &Ix) -> Option<&V> {
+        self.0.get(source)?.get(reference)
+    }
+
+    pub(crate) fn get_mut(&mut self, edge: &Edge<Ix>) -> Option<&mut V> {
+        self.0.get_mut(&edge.source)?.get_mut(&edge.reference)
+    }
+
+    pub(crate) fn insert(&mut self, edge: &Edge<Ix>, value: V) -> Option<V> {
+        self.0
+            .entry(edge.get_source())
+            .or_default()
+            .insert(edge.get_reference(), value)
+    }
+
+    pub(crate) fn remove(&mut self, edge: &Edge<Ix>) -> Option<V> {
+        let references = self.0.get_mut(&edge.source)?;
+        let removed = references.remove(&edge.reference);
+        if references.is_empty() {
+            self.0.remove(&edge.source);
+        }
+        removed
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Edge<Ix>, &V)> {
+        self.0.iter().flat_map(|(source, references)| {
+            references
+                .iter()
+                .map(|(reference, value)| (Edge::new(source.clone(), reference.clone()), value))
+        })
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &V> {
+        self.0.values().flat_map(|references| references.values())
+    }
+
+    /// The same values for the edges flipped around
+    pub(crate) fn reversed(self) -> EdgeMap<Ix, V> {
+        let mut reversed = EdgeMap::default();
+        for (source, references) in self.0 {
+            for (reference, value) in references {
+                reversed.insert(&Edge::new(reference, source.clone()), value);
+            }
+        }
+        reversed
+    }
+
+    /// The bytes allocated for the tables, not counting anything the values
+    /// own
+    pub(crate) fn heap_bytes(&self) -> usize {
+        table_bytes::<(Ix, HashMap<Ix, V>)>(self.0.capacity())
+            + self
+                .0
+                .values()
+                .map(|references| table_bytes::<(Ix, V)>(references.capacity()))
+                .sum::<usize>()
+    }
+}
+
+/// The two endpoints of an edge to add to a graph, see [`IntoEdgeSpec`]
+#[derive(Clone, Debug)]
+pub enum EdgeSpec<T: Clone + Debug, Ix: Index + Debug> {
+    /// Vertices that are added to the graph along with the edge if there
+    /// are no vertices at their indices yet
+    Vertices(Vertex<T, Ix>, Vertex<T, Ix>),
+    /// The indices of vertices that must already be in the graph
+    Indices(Ix, Ix),
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> EdgeSpec<T, Ix> {
+    pub fn edge(&self) -> Edge<Ix> {
+        match self {
+            EdgeSpec::Vertices(source, reference) => {
+                Edge::new(source.get_index(), reference.get_index())
+            }
+            EdgeSpec::Indices(source, reference) => Edge::new(source.clone(), reference.clone()),
+        }
+    }
+}
+
+/// Anything that names the source and the reference of an edge, so it can
+/// be passed to [`BullDag::add_edge`](crate::graph::BullDag::add_edge)
+/// and the other methods that add
+/// edges: a pair of vertices, by value or by reference, a pair of indices,
+/// or an [`Edge`]. The first of a pair is the source.
+///
+/// Example
+///
+/// ```
+/// use bulldag::edge::Edge;
+/// use bulldag::graph::BullDag;
+/// use bulldag::vertex::Vertex;
+///
+/// let mut graph: BullDag<usize, &str> = BullDag::new();
+/// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+/// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+/// let v3: Vertex<usize, &str> = Vertex::new(3, "ref_reference");
+/// graph.add_edge(&(&v1, &v2));
+/// graph.add_edge((v2, v3));
+/// graph.add_edge(("source", "ref_reference"));
+/// graph.add_edge(Edge::new("ref_reference", "missing"));
+///
+/// assert!(graph.n_edges() == 3);
+/// ```
+pub trait IntoEdgeSpec<T: Clone + Debug, Ix: Index + Debug> {
+    fn into_edge_spec(self) -> EdgeSpec<T, Ix>;
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> IntoEdgeSpec<T, Ix> for EdgeSpec<T, Ix> {
+    fn into_edge_spec(self) -> EdgeSpec<T, Ix> {
+        self
+    }
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> IntoEdgeSpec<T, Ix> for (Vertex<T, Ix>, Vertex<T, Ix>) {
+    fn into_edge_spec(self) -> EdgeSpec<T, Ix> {
+        EdgeSpec::Vertices(self.0, self.1)
+    }
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> IntoEdgeSpec<T, Ix> for (&Vertex<T, Ix>, &Vertex<T, Ix>) {
+    fn into_edge_spec(self) -> EdgeSpec<T, Ix> {
+        EdgeSpec::Vertices(self.0.clone(), self.1.clone())
+    }
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> IntoEdgeSpec<T, Ix>
+    for &(&Vertex<T, Ix>, &Vertex<T, Ix>)
+{
+    fn into_edge_spec(self) -> EdgeSpec<T, Ix> {
+        EdgeSpec::Vertices(self.0.clone(), self.1.clone())
+    }
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> IntoEdgeSpec<T, Ix> for (Ix, Ix) {
+    fn into_edge_spec(self) -> EdgeSpec<T, Ix> {
+        EdgeSpec::Indices(self.0, self.1)
+    }
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> IntoEdgeSpec<T, Ix> for (&Ix, &Ix) {
+    fn into_edge_spec(self) -> EdgeSpec<T, Ix> {
+        EdgeSpec::Indices(self.0.clone(), self.1.clone())
+    }
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> IntoEdgeSpec<T, Ix> for Edge<Ix> {
+    fn into_edge_spec(self) -> EdgeSpec<T, Ix> {
+        EdgeSpec::Indices(self.source, self.reference)
+    }
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> IntoEdgeSpec<T, Ix> for &Edge<Ix> {
+    fn into_edge_spec(self) -> EdgeSpec<T, Ix> {
+        EdgeSpec::Indices(self.get_source(), self.get_reference())
+    }
+}
@@ -0,0 +1,315 @@
+use crate::edge::Edge;
+use crate::graph::{BullDag, GraphError, GraphResult};
+use crate::index::Index;
+use crate::vertex::{Direction, Vertex};
+use std::fmt::Debug;
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Contracts the edge from `source` to `reference`, merging the two
+    /// vertices into one that keeps the index of `source` and the data
+    /// `merge` makes of the data of both. Every other edge of `reference`
+    /// is moved over to the merged vertex with its kind, time, weight and
+    /// keys, unless the merged vertex already has an edge to or from the
+    /// same vertex, which is kept as it is. Fails with `NonExistentSource`
+    /// or `NonExistentReference` if either vertex is missing, with
+    /// `NoEdges` if there is no such edge, and with `WouldCycle` if
+    /// `reference` can also be reached from `source` some other way. Every
+    /// check is made before anything changes, so a failed contraction
+    /// leaves the graph untouched. The epoch and conflicts of `reference`
+    /// are dropped with it.
+    ///
+    /// The contraction is made of the removal of `reference`, the update of
+    /// the data of `source` and the addition of each moved edge, which
+    /// observers see, and the journal records, one at a time.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::{BullDag, GraphError};
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4), (&v3, &v4)]);
+    ///
+    /// assert!(graph.contract_edge(&"source", &"reference_1", |a, b| a + b).is_ok());
+    /// assert!(graph.len() == 3);
+    /// assert!(graph.get_vertex("source").unwrap().get_data() == 9);
+    /// assert!(graph.has_edge(&"source", &"ref_reference"));
+    ///
+    /// // "ref_reference" can also be reached through "reference_2"
+    /// assert!(matches!(
+    ///     graph.contract_edge(&"source", &"ref_reference", |a, b| a + b),
+    ///     Err(GraphError::WouldCycle)
+    /// ));
+    /// assert!(matches!(
+    ///     graph.contract_edge(&"reference_2", &"source", |a, b| a + b),
+    ///     Err(GraphError::NoEdges)
+    /// ));
+    /// ```
+    pub fn contract_edge(
+        &mut self,
+        source: &Ix,
+        reference: &Ix,
+        merge: impl Fn(T, T) -> T,
+    ) -> GraphResult {
+        let Some(source_vtx) = self.vertices.get(source) else {
+            return Err(GraphError::NonExistentSource);
+        };
+        if !self.contains_vertex(reference) {
+            return Err(GraphError::NonExistentReference);
+        }
+        if !source_vtx.is_reference(reference) {
+            return Err(GraphError::NoEdges);
+        }
+
+        // Any other path would become a cycle through the merged vertex
+        let others: Vec<Ix> = source_vtx
+            .get_references()
+            .into_iter()
+            .filter(|r| *r != reference)
+            .cloned()
+            .collect();
+        if self
+            .reachable(&others, Direction::Reference, |_| false)
+            .contains(reference)
+        {
+            return Err(GraphError::WouldCycle);
+        }
+
+        // Each edge that will be moved, as it will be once moved, with its
+        // kind, time, weight and keys
+        let vtx = &self.vertices[reference];
+        let moved: Vec<(Edge<Ix>, Edge<Ix>)> = vtx
+            .get_sources()
+            .into_iter()
+            .filter(|s| *s != source)
+            .map(|s| {
+                (
+                    Edge::new(s.clone(), reference.clone()),
+                    Edge::new(s.clone(), source.clone()),
+                )
+            })
+            .chain(vtx.get_references().into_iter().map(|r| {
+                (
+                    Edge::new(reference.clone(), r.clone()),
+                    Edge::new(source.clone(), r.clone()),
+                )
+            }))
+            .filter(|(_, after)| !self.edges.contains(after))
+            .collect();
+        let tags: Vec<_> = moved
+            .into_iter()
+            .map(|(before, after)| {
+                (
+                    after,
+                    self.edge_kind(&before),
+                    self.edge_time(&before),
+                    self.edge_weights.get(&before).copied(),
+                    self.edge_keys(&before),
+                )
+            })
+            .collect();
+
+        let removed = self
+            .remove_vertex(reference.clone())
+            .ok_or(GraphError::NonExistentReference)?;
+        if let Some(vtx) = self.vertices.get(source) {
+            let data = merge(vtx.get_data(), removed.get_data());
+            self.set_vertex_data(source, data);
+        }
+        // None of the moved edges can close a cycle, that was ruled out
+        // above, so they're added without checking again and the
+        // contraction can't fail half way through
+        for s in removed.get_sources().into_iter().filter(|s| *s != source) {
+            self.add_edge_unchecked((s, source));
+        }
+        for r in removed.get_references() {
+            self.add_edge_unchecked((source, r));
+        }
+        for (edge, kind, time, weight, keys) in tags {
+            self.set_edge_kind(&edge, kind.unwrap_or(0));
+            if let Some(time) = time {
+                self.set_edge_time(&edge, time);
+            }
+            if let Some(weight) = weight {
+                self.set_edge_weight(&edge, weight);
+            }
+            self.set_edge_keys(&edge, keys.into_iter().collect());
+        }
+
+        Ok(())
+    }
+
+    /// Splits the edge from `source` to `reference` in two by putting
+    /// `new` between them, so `source` references `new` and `new`
+    /// references `reference`. Any sources or references `new` already has
+    /// are ignored. Fails with `NonExistentSource` or
+    /// `NonExistentReference` if either vertex is missing, with `NoEdges`
+    /// if there is no such edge and with `VertexExists` if the index of
+    /// `new` is taken, in which case the graph is left untouched.
+    ///
+    /// Observers see, and the journal records, the new vertex, the two new
+    /// edges and the removal of the old one in that order.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::{BullDag, GraphError};
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// assert!(graph.insert_between(Vertex::new(3, "middle"), &"source", &"reference").is_ok());
+    /// assert!(graph.has_edge(&"source", &"middle") && graph.has_edge(&"middle", &"reference"));
+    /// assert!(!graph.has_edge(&"source", &"reference"));
+    /// assert!(matches!(
+    ///     graph.insert_between(Vertex::new(3, "middle"), &"source", &"middle"),
+    ///     Err(GraphError::VertexExists)
+    /// ));
+    /// ```
+    pub fn insert_between(
+        &mut self,
+        new: Vertex<T, Ix>,
+        source: &Ix,
+        reference: &Ix,
+    ) -> GraphResult {
+        let Some(source_vtx) = self.vertices.get(source) else {
+            return Err(GraphError::NonExistentSource);
+        };
+        if !self.contains_vertex(reference) {
+            return Err(GraphError::NonExistentReference);
+        }
+        if !source_vtx.is_reference(reference) {
+            return Err(GraphError::NoEdges);
+        }
+        let ix = new.get_index();
+        if self.contains_vertex(&ix) {
+            return Err(GraphError::VertexExists);
+        }
+
+        // Neither edge can close a cycle as `new` has no other edges
+        self.add_vertex(&Vertex::new(new.get_data(), ix.clone()));
+        self.add_edge_ix(source, &ix)?;
+        self.add_edge_ix(&ix, reference)?;
+        self.remove_edge(&Edge::new(source.clone(), reference.clone()));
+
+        Ok(())
+    }
+
+    /// Moves the vertex at `old` to the index `new`, rewriting every edge
+    /// it is part of and the sources and references of its neighbors. Its
+    /// epoch, conflicts, place in insertion order and the kinds, times,
+    /// weights and keys of its edges move with it. Fails with
+    /// `NonExistentVertex` if there is no vertex at `old` and with
+    /// `VertexExists` if `new` is taken, in which case the graph is left
+    /// untouched.
+    ///
+    /// Observers see, and the journal records, the removal of the vertex
+    /// followed by the addition of the renamed vertex and each of its
+    /// edges.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::{BullDag, GraphError};
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// assert!(graph.reindex(&"reference", "renamed").is_ok());
+    /// assert!(graph.has_edge(&"source", &"renamed"));
+    /// assert!(graph.get_leaves().contains("renamed"));
+    /// assert!(matches!(
+    ///     graph.reindex(&"renamed", "source"),
+    ///     Err(GraphError::VertexExists)
+    /// ));
+    /// ```
+    pub fn reindex(&mut self, old: &Ix, new: Ix) -> GraphResult {
+        if !self.contains_vertex(old) {
+            return Err(GraphError::NonExistentVertex);
+        }
+        if self.contains_vertex(&new) {
+            return Err(GraphError::VertexExists);
+        }
+
+        let epoch = self.epoch_of(old);
+        let conflicts = self.conflicts_of(old);
+        let seq = self.insertion_seq(old);
+        // Each edge as it will be once moved, with its kind, time, weight
+        // and keys
+        let vtx = &self.vertices[old];
+        let moved: Vec<(Edge<Ix>, Edge<Ix>)> = vtx
+            .get_sources()
+            .into_iter()
+            .map(|s| {
+                (
+                    Edge::new(s.clone(), old.clone()),
+                    Edge::new(s.clone(), new.clone()),
+                )
+            })
+            .chain(vtx.get_references().into_iter().map(|r| {
+                (
+                    Edge::new(old.clone(), r.clone()),
+                    Edge::new(new.clone(), r.clone()),
+                )
+            }))
+            .collect();
+        let tags: Vec<_> = moved
+            .into_iter()
+            .map(|(before, after)| {
+                (
+                    after,
+                    self.edge_kind(&before),
+                    self.edge_time(&before),
+                    self.edge_weights.get(&before).copied(),
+                    self.edge_keys(&before),
+                )
+            })
+            .collect();
+        let removed = self
+            .remove_vertex(old.clone())
+            .ok_or(GraphError::NonExistentVertex)?;
+        self.add_vertex(&Vertex::new(removed.get_data(), new.clone()));
+        // The edges are the ones just removed under a new name, so they
+        // can't close a cycle and the move can't fail half way through
+        for s in removed.get_sources() {
+            self.add_edge_unchecked((s, &new));
+        }
+        for r in removed.get_references() {
+            self.add_edge_unchecked((&new, r));
+        }
+        for (edge, kind, time, weight, keys) in tags {
+            self.set_edge_kind(&edge, kind.unwrap_or(0));
+            if let Some(time) = time {
+                self.set_edge_time(&edge, time);
+            }
+            if let Some(weight) = weight {
+                self.set_edge_weight(&edge, weight);
+            }
+            self.set_edge_keys(&edge, keys.into_iter().collect());
+        }
+
+        if let Some(epoch) = epoch {
+            self.set_epoch(&new, epoch);
+        }
+        if let Some(seq) = seq {
+            self.sequence.insert(new.clone(), seq);
+        }
+        conflicts.iter().for_each(|other| {
+            self.add_conflict(&new, other);
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Vertex;
+use std::fmt::Debug;
+
+/// A view into a single vertex slot of a graph, which may be occupied or
+/// vacant, returned by [`BullDag::entry`]. Mirrors the `HashMap` entry API
+/// so that "insert the vertex if it is missing, then use it" doesn't need
+/// a separate lookup, clone and `add_vertex`.
+pub enum Entry<'a, T: Clone + Debug, Ix: Index + Debug> {
+    Occupied(OccupiedEntry<'a, T, Ix>),
+    Vacant(VacantEntry<'a, T, Ix>),
+}
+
+/// An entry for a vertex that is in the graph
+pub struct OccupiedEntry<'a, T: Clone + Debug, Ix: Index + Debug> {
+    graph: &'a mut BullDag<T, Ix>,
+    index: Ix,
+}
+
+/// An entry for a vertex that is not in the graph
+pub struct VacantEntry<'a, T: Clone + Debug, Ix: Index + Debug> {
+    graph: &'a mut BullDag<T, Ix>,
+    index: Ix,
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Gets the entry for the vertex at `index` for in-place manipulation
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// graph.entry("source").or_insert(5);
+    /// graph.entry("reference").or_insert_with(|| 4);
+    /// graph.add_edge_ix(&"source", &"reference").unwrap();
+    ///
+    /// graph.entry("source").and_modify(|data| *data += 1).or_insert(0);
+    /// assert!(graph.get_vertex("source").unwrap().get_data() == 6);
+    /// assert!(graph.get_vertex("source").unwrap().n_references() == 1);
+    /// ```
+    pub fn entry(&mut self, index: Ix) -> Entry<'_, T, Ix> {
+        if self.vertices.contains_key(&index) {
+            Entry::Occupied(OccupiedEntry { graph: self, index })
+        } else {
+            Entry::Vacant(VacantEntry { graph: self, index })
+        }
+    }
+}
+
+impl<'a, T, Ix> Entry<'a, T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns the index of the entry
+    pub fn key(&self) -> &Ix {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Inserts a vertex holding `data` if the entry is vacant, and returns
+    /// a mutable reference to the vertex's data, see
+    /// [`OccupiedEntry::into_mut`]
+    pub fn or_insert(self, data: T) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(data),
+        }
+    }
+
+    /// Inserts a vertex holding the result of `f` if the entry is vacant,
+    /// and returns a mutable reference to the vertex's data, see
+    /// [`OccupiedEntry::into_mut`]
+    pub fn or_insert_with<F: FnOnce() -> T>(self, f: F) -> &'a mut T {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    /// Modifies the vertex's data if the entry is occupied. Observers see,
+    /// and the journal records, the vertex as updated.
+    pub fn and_modify<F: FnOnce(&mut T)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                let previous = entry.get().get_data();
+                f(entry.get_mut());
+                entry.graph.data_changed(&entry.index, previous);
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+impl<'a, T, Ix> Entry<'a, T, Ix>
+where
+    T: Clone + Debug + Default,
+    Ix: Index + Debug,
+{
+    /// Inserts a vertex holding `T::default()` if the entry is vacant, and
+    /// returns a mutable reference to the vertex's data, see
+    /// [`OccupiedEntry::into_mut`]
+    pub fn or_default(self) -> &'a mut T {
+        self.or_insert_with(T::default)
+    }
+}
+
+impl<'a, T, Ix> OccupiedEntry<'a, T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    pub fn key(&self) -> &Ix {
+        &self.index
+    }
+
+    pub fn get(&self) -> &Vertex<T, Ix> {
+        &self.graph.vertices[&self.index]
+    }
+
+    /// Borrows the vertex's data mutably. Like data changed through
+    /// [`BullDag::get_vertex_mut`], changes made through it are not seen by
+    /// observers or the journal, which [`Entry::and_modify`] and
+    /// [`OccupiedEntry::insert`] are.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.graph
+            .vertices
+            .get_mut(&self.index)
+            .expect("an occupied entry's vertex is in the graph")
+            .data_mut()
+    }
+
+    /// Converts the entry into a mutable reference to the vertex's data
+    /// with the lifetime of the graph borrow, see [`OccupiedEntry::get_mut`]
+    pub fn into_mut(self) -> &'a mut T {
+        self.graph
+            .vertices
+            .get_mut(&self.index)
+            .expect("an occupied entry's vertex is in the graph")
+            .data_mut()
+    }
+
+    /// Replaces the vertex's data, keeping its edges, and returns the data
+    /// it had. Observers see, and the journal records, the vertex as
+    /// updated.
+    pub fn insert(&mut self, data: T) -> T {
+        self.graph
+            .set_vertex_data(&self.index, data)
+            .expect("an occupied entry's vertex is in the graph")
+    }
+
+    /// Removes the vertex and all of its edges from the graph, see
+    /// [`BullDag::remove_vertex`]
+    pub fn remove(self) -> Vertex<T, Ix> {
+        self.graph
+            .remove_vertex(self.index)
+            .expect("an occupied entry's vertex is in the graph")
+    }
+}
+
+impl<'a, T, Ix> VacantEntry<'a, T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    pub fn key(&self) -> &Ix {
+        &self.index
+    }
+
+    /// Adds a vertex holding `data` at the entry's index, as a root and a
+    /// leaf since it has no edges yet, and returns a mutable reference to
+    /// its data, see [`OccupiedEntry::into_mut`]
+    pub fn insert(self, data: T) -> &'a mut T {
+        self.graph
+            .add_vertex(&Vertex::new(data, self.index.clone()));
+        self.graph
+            .vertices
+            .get_mut(&self.index)
+            .expect("the vertex was just inserted")
+            .data_mut()
+    }
+}
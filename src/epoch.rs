@@ -0,0 +1,124 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::observe::Event;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+/// What is left of the epochs pruned by [`BullDag::prune_epochs_before`]:
+/// the vertices the retained region starts from, and the graph's state
+/// root just before pruning, which commits to every pruned vertex.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochCheckpoint<Ix: Index> {
+    /// Every vertex tagged with an epoch before this one was pruned
+    pub epoch: u64,
+    /// The roots of the graph after pruning
+    pub roots: HashSet<Ix>,
+    /// See [`BullDag::state_root`]
+    pub state_root: [u8; 32],
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Tags the vertex at `ix` with an epoch, replacing any earlier tag.
+    /// Returns false if there is no such vertex. The tag is dropped along
+    /// with the vertex.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// graph.add_vertex(&Vertex::new(5, "source"));
+    /// assert!(graph.set_epoch(&"source", 3));
+    /// assert!(!graph.set_epoch(&"missing", 3));
+    /// assert!(graph.epoch_of(&"source") == Some(3));
+    /// ```
+    pub fn set_epoch(&mut self, ix: &Ix, epoch: u64) -> bool {
+        if !self.contains_vertex(ix) {
+            return false;
+        }
+        self.epochs.insert(ix.clone(), epoch);
+        self.notify(Event::EpochSet(ix, epoch));
+        true
+    }
+
+    /// Returns the epoch the vertex at `ix` is tagged with, if any
+    pub fn epoch_of(&self, ix: &Ix) -> Option<u64> {
+        self.epochs.get(ix).copied()
+    }
+
+    /// Returns the vertices tagged with `epoch`
+    pub fn vertices_in_epoch(&self, epoch: u64) -> Vec<Ix> {
+        self.epochs
+            .iter()
+            .filter(|(_, e)| **e == epoch)
+            .map(|(ix, _)| ix.clone())
+            .collect()
+    }
+
+    /// Returns the checkpoints recorded each time epochs were pruned, the
+    /// oldest first
+    pub fn epoch_checkpoints(&self) -> &[EpochCheckpoint<Ix>] {
+        &self.epoch_checkpoints
+    }
+}
+
+#[cfg(feature = "hash")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize,
+    Ix: Index + Debug + Serialize,
+{
+    /// Removes every vertex tagged with an epoch before `epoch`, along with
+    /// its edges, and records an [`EpochCheckpoint`] in their place so the
+    /// graph stays bounded while still committing to its history. Untagged
+    /// vertices are kept. Returns the checkpoint, which is also kept in
+    /// [`BullDag::epoch_checkpoints`].
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, usize> = BullDag::new();
+    /// let chain: Vec<Vertex<usize, usize>> = (0..6).map(|i| Vertex::new(i, i)).collect();
+    /// chain.windows(2).for_each(|w| graph.add_edge(&(&w[0], &w[1])));
+    /// (0..6).for_each(|i| {
+    ///     graph.set_epoch(&i, i as u64 / 2);
+    /// });
+    ///
+    /// let before = graph.state_root().unwrap();
+    /// let checkpoint = graph.prune_epochs_before(2).unwrap();
+    /// assert!(graph.len() == 2);
+    /// assert!(checkpoint.roots == [4].into());
+    /// assert!(checkpoint.state_root == before);
+    /// ```
+    pub fn prune_epochs_before(
+        &mut self,
+        epoch: u64,
+    ) -> Result<EpochCheckpoint<Ix>, serde_json::Error> {
+        let state_root = self.state_root()?;
+        let old: Vec<Ix> = self
+            .epochs
+            .iter()
+            .filter(|(_, e)| **e < epoch)
+            .map(|(ix, _)| ix.clone())
+            .collect();
+        old.into_iter().for_each(|ix| {
+            self.remove_vertex(ix);
+        });
+
+        let checkpoint = EpochCheckpoint {
+            epoch,
+            roots: self.get_roots(),
+            state_root,
+        };
+        self.epoch_checkpoints.push(checkpoint.clone());
+        Ok(checkpoint)
+    }
+}
@@ -0,0 +1,362 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::{Direction, Vertex};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::Debug;
+
+#[cfg(feature = "rayon")]
+use crate::graph::GraphError;
+#[cfg(feature = "async")]
+use futures::{stream::FuturesUnordered, Future, StreamExt};
+#[cfg(any(feature = "rayon", feature = "async"))]
+use std::sync::Arc;
+#[cfg(feature = "rayon")]
+use std::sync::{Mutex, PoisonError};
+
+/// The results of a vertex's sources, passed into the closure given to
+/// [`BullDag::execute`] alongside the vertex itself.
+pub type SourceResults<'a, Ix, R> = [(&'a Ix, &'a R)];
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Runs `f` once for every vertex in topological order, passing in the
+    /// vertex and the results of executing each of its sources, and returns
+    /// the result of every vertex keyed by its index. Roots are passed an
+    /// empty slice of source results.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(1, "fetch");
+    /// let v2: Vertex<usize, &str> = Vertex::new(2, "compile");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "assets");
+    /// let v4: Vertex<usize, &str> = Vertex::new(4, "package");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4), (&v3, &v4)]);
+    ///
+    /// // Each task's cost is its own data plus the cost of its inputs
+    /// let results = graph.execute(|vtx, sources| {
+    ///     vtx.get_data() + sources.iter().map(|(_, r)| **r).sum::<usize>()
+    /// });
+    /// assert!(results["package"] == 4 + (2 + 1) + (3 + 1));
+    /// ```
+    pub fn execute<F, R>(&self, mut f: F) -> HashMap<Ix, R>
+    where
+        F: FnMut(&Vertex<T, Ix>, &SourceResults<Ix, R>) -> R,
+    {
+        match self.try_execute(|vtx, sources| Ok::<R, Infallible>(f(vtx, sources))) {
+            Ok(results) => results,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Given the vertices that have changed, returns them along with every
+    /// vertex that references them directly or indirectly, in topological
+    /// order. These are exactly the vertices that need to be re-evaluated,
+    /// and in the order they need to be re-evaluated in. Indices that are
+    /// not in the graph are ignored.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(1, "fetch");
+    /// let v2: Vertex<usize, &str> = Vertex::new(2, "compile");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "assets");
+    /// let v4: Vertex<usize, &str> = Vertex::new(4, "package");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4), (&v3, &v4)]);
+    ///
+    /// assert!(graph.dirty_closure(&["compile"]) == vec!["compile", "package"]);
+    /// assert!(graph.dirty_closure(&["fetch"]).len() == 4);
+    /// ```
+    pub fn dirty_closure(&self, changed: &[Ix]) -> Vec<Ix> {
+        let dirty = self.reachable(changed, Direction::Reference, |_| false);
+        self.topological_order_of(&dirty)
+    }
+
+    /// Like [`BullDag::execute`] but for closures that can fail, stops at
+    /// the first error and returns it.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(1, "fetch");
+    /// let v2: Vertex<usize, &str> = Vertex::new(0, "compile");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// let results = graph.try_execute(|vtx, _| match vtx.get_data() {
+    ///     0 => Err(format!("{} failed", vtx.get_index())),
+    ///     n => Ok(n),
+    /// });
+    /// assert!(results == Err("compile failed".to_string()));
+    /// ```
+    pub fn try_execute<F, R, E>(&self, mut f: F) -> Result<HashMap<Ix, R>, E>
+    where
+        F: FnMut(&Vertex<T, Ix>, &SourceResults<Ix, R>) -> Result<R, E>,
+    {
+        let mut results: HashMap<Ix, R> = HashMap::with_capacity(self.vertices.len());
+        for ix in self.topological_order() {
+            let vtx = &self.vertices[&ix];
+            let result = {
+                let sources: Vec<(&Ix, &R)> = vtx
+                    .get_sources()
+                    .into_iter()
+                    .filter_map(|s| results.get(s).map(|r| (s, r)))
+                    .collect();
+                f(vtx, &sources)?
+            };
+            results.insert(ix, result);
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(feature = "rayon")]
+struct ParallelState<'a, T, Ix, R, F>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    graph: &'a BullDag<T, Ix>,
+    f: &'a F,
+    progress: Mutex<ParallelProgress<Ix, R>>,
+}
+
+/// The results so far and, for every vertex that hasn't run yet, the
+/// number of its sources that are still running or waiting to run.
+#[cfg(feature = "rayon")]
+struct ParallelProgress<Ix, R> {
+    results: HashMap<Ix, Arc<R>>,
+    waiting_on: HashMap<Ix, usize>,
+}
+
+#[cfg(feature = "rayon")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Sync,
+    Ix: Index + Debug + Send + Sync,
+{
+    /// A parallel version of [`BullDag::execute`] backed by a rayon thread
+    /// pool of `max_parallelism` threads (or rayon's default, one per core,
+    /// if `0`). Rather than working through the graph in topological order,
+    /// every vertex is spawned onto the pool as soon as the last of its
+    /// sources completes, so independent branches run concurrently.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(1, "fetch");
+    /// let v2: Vertex<usize, &str> = Vertex::new(2, "compile");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "assets");
+    /// let v4: Vertex<usize, &str> = Vertex::new(4, "package");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4), (&v3, &v4)]);
+    ///
+    /// let results = graph
+    ///     .par_execute(4, |vtx, sources| {
+    ///         vtx.get_data() + sources.iter().map(|(_, r)| **r).sum::<usize>()
+    ///     })
+    ///     .unwrap();
+    /// assert!(results["package"] == 4 + (2 + 1) + (3 + 1));
+    /// ```
+    pub fn par_execute<F, R>(
+        &self,
+        max_parallelism: usize,
+        f: F,
+    ) -> Result<HashMap<Ix, R>, GraphError>
+    where
+        F: Fn(&Vertex<T, Ix>, &SourceResults<Ix, R>) -> R + Sync,
+        R: Send + Sync,
+    {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(max_parallelism)
+            .build()
+            .map_err(GraphError::other)?;
+
+        let state = ParallelState {
+            graph: self,
+            f: &f,
+            progress: Mutex::new(ParallelProgress {
+                results: HashMap::with_capacity(self.vertices.len()),
+                waiting_on: self
+                    .vertices
+                    .iter()
+                    .map(|(ix, vtx)| (ix.clone(), vtx.n_sources()))
+                    .collect(),
+            }),
+        };
+
+        pool.install(|| {
+            rayon::scope(|scope| {
+                self.roots.iter().for_each(|root| {
+                    let state = &state;
+                    let root = root.clone();
+                    scope.spawn(move |scope| run_vertex(scope, state, root));
+                });
+            })
+        });
+
+        let progress = state
+            .progress
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Every task has finished and dropped its clones of the results
+        // it was given, so each Arc is now the only reference.
+        Ok(progress
+            .results
+            .into_iter()
+            .map(|(ix, r)| match Arc::try_unwrap(r) {
+                Ok(r) => (ix, r),
+                Err(_) => unreachable!("all vertex tasks have completed"),
+            })
+            .collect())
+    }
+}
+
+/// Runs a single vertex whose sources have all completed, records its
+/// result, and spawns any of its references that are now ready.
+#[cfg(feature = "rayon")]
+fn run_vertex<'s, T, Ix, R, F>(
+    scope: &rayon::Scope<'s>,
+    state: &'s ParallelState<'s, T, Ix, R, F>,
+    ix: Ix,
+) where
+    T: Clone + Debug + Sync,
+    Ix: Index + Debug + Send + Sync,
+    F: Fn(&Vertex<T, Ix>, &SourceResults<Ix, R>) -> R + Sync,
+    R: Send + Sync,
+{
+    let vtx = &state.graph.vertices[&ix];
+    let inputs: Vec<(Ix, Arc<R>)> = {
+        let progress = state
+            .progress
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        vtx.get_sources()
+            .into_iter()
+            .filter_map(|s| progress.results.get(s).map(|r| (s.clone(), r.clone())))
+            .collect()
+    };
+
+    let result = {
+        let sources: Vec<(&Ix, &R)> = inputs.iter().map(|(s, r)| (s, r.as_ref())).collect();
+        (state.f)(vtx, &sources)
+    };
+    drop(inputs);
+
+    let ready: Vec<Ix> = {
+        let mut progress = state
+            .progress
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        progress.results.insert(ix, Arc::new(result));
+        vtx.get_references()
+            .into_iter()
+            .filter(|r| {
+                progress.waiting_on.get_mut(*r).is_some_and(|n| {
+                    *n -= 1;
+                    *n == 0
+                })
+            })
+            .cloned()
+            .collect()
+    };
+
+    ready.into_iter().for_each(|r| {
+        scope.spawn(move |scope| run_vertex(scope, state, r));
+    });
+}
+
+#[cfg(feature = "async")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// An async version of [`BullDag::execute`]. `f` is called for a vertex
+    /// as soon as all of its sources have completed and returns a future,
+    /// every future whose sources are done is polled concurrently, so
+    /// independent vertices make progress at the same time and each edge
+    /// is an await point. Source results are shared with `Arc` since the
+    /// returned future cannot borrow from the executor, and are returned
+    /// that way too as a future may keep hold of the results it was given.
+    /// Not tied to a particular runtime, the returned future can be awaited
+    /// on any of them.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(1, "fetch");
+    /// let v2: Vertex<usize, &str> = Vertex::new(2, "compile");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "assets");
+    /// let v4: Vertex<usize, &str> = Vertex::new(4, "package");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4), (&v3, &v4)]);
+    ///
+    /// let results = futures::executor::block_on(graph.execute_async(|vtx, sources| {
+    ///     let data = vtx.get_data();
+    ///     async move { data + sources.iter().map(|(_, r)| **r).sum::<usize>() }
+    /// }));
+    /// assert!(*results["package"] == 4 + (2 + 1) + (3 + 1));
+    /// ```
+    pub async fn execute_async<F, Fut, R>(&self, mut f: F) -> HashMap<Ix, Arc<R>>
+    where
+        F: FnMut(&Vertex<T, Ix>, Vec<(Ix, Arc<R>)>) -> Fut,
+        Fut: Future<Output = R>,
+    {
+        let mut results: HashMap<Ix, Arc<R>> = HashMap::with_capacity(self.vertices.len());
+        let mut waiting_on: HashMap<Ix, usize> = self
+            .vertices
+            .iter()
+            .map(|(ix, vtx)| (ix.clone(), vtx.n_sources()))
+            .collect();
+        let mut in_flight = FuturesUnordered::new();
+
+        let mut start = |ix: Ix, results: &HashMap<Ix, Arc<R>>| {
+            let vtx = &self.vertices[&ix];
+            let inputs: Vec<(Ix, Arc<R>)> = vtx
+                .get_sources()
+                .into_iter()
+                .filter_map(|s| results.get(s).map(|r| (s.clone(), r.clone())))
+                .collect();
+            let fut = f(vtx, inputs);
+            async move { (ix, fut.await) }
+        };
+
+        self.roots
+            .iter()
+            .for_each(|root| in_flight.push(start(root.clone(), &results)));
+
+        while let Some((ix, result)) = in_flight.next().await {
+            results.insert(ix.clone(), Arc::new(result));
+            for r in self.vertices[&ix].get_references() {
+                let ready = waiting_on.get_mut(r).is_some_and(|n| {
+                    *n -= 1;
+                    *n == 0
+                });
+                if ready {
+                    in_flight.push(start(r.clone(), &results));
+                }
+            }
+        }
+
+        results
+    }
+}
@@ -0,0 +1,369 @@
+use crate::graph::{BullDag, GraphError};
+use crate::index::Index;
+use crate::vertex::Direction;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+const FROZEN_MAGIC: &[u8; 8] = b"BDAGFRZ1";
+const HEADER_LEN: usize = 24;
+
+/// A read-only graph laid out so that it can be used straight from the
+/// bytes it was written as, e.g. a memory-mapped file, without decoding it
+/// first. Opening one only checks the header and section sizes, so it takes
+/// the same time for ten edges as for ten million.
+///
+/// The layout, with every integer little-endian, is:
+///
+/// - the magic `BDAGFRZ1`, the number of vertices `n` and of edges `m`
+/// - four offset tables of `n + 1` `u64`s, into the index blob, the data
+///   blob, the reference list and the source list
+/// - the reference and the source lists, `m` `u32` vertex positions each
+/// - the index blob and the data blob, each vertex's index and data as JSON
+///
+/// Vertices are sorted by their JSON encoded index, so an index is found by
+/// binary search, and neighbors are positions, so traversals only decode
+/// the indices they return. Vertex data is only decoded when asked for.
+///
+/// Example
+///
+/// ```
+/// use bulldag::frozen::FrozenBullDag;
+/// use bulldag::graph::BullDag;
+/// use bulldag::vertex::{Direction, Vertex};
+///
+/// let mut graph: BullDag<usize, String> = BullDag::new();
+/// let v1: Vertex<usize, String> = Vertex::new(5, "source".to_string());
+/// let v2: Vertex<usize, String> = Vertex::new(4, "reference".to_string());
+/// graph.add_edge(&(&v1, &v2));
+///
+/// let bytes = graph.freeze().unwrap();
+/// let frozen: FrozenBullDag<_, usize, String> = FrozenBullDag::new(bytes).unwrap();
+/// assert!(frozen.n_edges() == 1);
+/// assert!(frozen.get_data(&"reference".to_string()) == Some(4));
+/// assert!(frozen.trace(&"reference".to_string(), Direction::Source).len() == 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrozenBullDag<B, T, Ix> {
+    bytes: B,
+    n: usize,
+    m: usize,
+    _types: PhantomData<fn() -> (T, Ix)>,
+}
+
+fn malformed(what: &str) -> GraphError {
+    GraphError::other(format!("malformed frozen graph: {what}"))
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    bytes
+        .get(at..at + 8)
+        .map_or(0, |b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    bytes
+        .get(at..at + 4)
+        .map_or(u32::MAX, |b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+fn to_json<S: Serialize>(value: &S) -> Result<Vec<u8>, GraphError> {
+    serde_json::to_vec(value).map_err(GraphError::other)
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize,
+    Ix: Index + Debug + Serialize,
+{
+    /// Writes the graph in the layout read by [`FrozenBullDag`]. Fails if
+    /// an index or data can't be serialized, or if the graph has more than
+    /// `u32::MAX` vertices.
+    pub fn freeze(&self) -> Result<Vec<u8>, GraphError> {
+        let mut keyed = Vec::with_capacity(self.vertices.len());
+        for ix in self.vertices.keys() {
+            keyed.push((to_json(ix)?, ix));
+        }
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        if keyed.len() > u32::MAX as usize {
+            return Err(GraphError::other("too many vertices to freeze"));
+        }
+        let positions: std::collections::HashMap<&Ix, u32> = keyed
+            .iter()
+            .enumerate()
+            .map(|(pos, (_, ix))| (*ix, pos as u32))
+            .collect();
+
+        let n = keyed.len();
+        let mut tables = vec![vec![0u64]; 4];
+        let (mut keys, mut data, mut refs, mut srcs) = (vec![], vec![], vec![], vec![]);
+        for (key, ix) in keyed.iter() {
+            let vtx = &self.vertices[*ix];
+            keys.extend_from_slice(key);
+            data.extend(to_json(&vtx.get_data())?);
+            refs.extend(vtx.get_references().into_iter().map(|r| positions[r]));
+            srcs.extend(vtx.get_sources().into_iter().map(|s| positions[s]));
+            for (table, len) in
+                tables
+                    .iter_mut()
+                    .zip([keys.len(), data.len(), refs.len(), srcs.len()])
+            {
+                table.push(len as u64);
+            }
+        }
+
+        let mut out = Vec::with_capacity(HEADER_LEN + 32 * (n + 1) + 8 * refs.len());
+        out.extend_from_slice(FROZEN_MAGIC);
+        out.extend_from_slice(&(n as u64).to_le_bytes());
+        out.extend_from_slice(&(refs.len() as u64).to_le_bytes());
+        for table in tables {
+            table
+                .into_iter()
+                .for_each(|offset| out.extend_from_slice(&offset.to_le_bytes()));
+        }
+        for list in [refs, srcs] {
+            list.into_iter()
+                .for_each(|pos| out.extend_from_slice(&pos.to_le_bytes()));
+        }
+        out.extend(keys);
+        out.extend(data);
+        Ok(out)
+    }
+}
+
+impl<B, T, Ix> FrozenBullDag<B, T, Ix>
+where
+    B: AsRef<[u8]>,
+    T: DeserializeOwned,
+    Ix: Serialize + DeserializeOwned,
+{
+    /// Uses bytes written by [`BullDag::freeze`], only checking that the
+    /// header is valid and that every section fits
+    pub fn new(bytes: B) -> Result<FrozenBullDag<B, T, Ix>, GraphError> {
+        let raw = bytes.as_ref();
+        if raw.len() < HEADER_LEN || &raw[..8] != FROZEN_MAGIC {
+            return Err(malformed("bad header"));
+        }
+        let (n, m) = (read_u64(raw, 8), read_u64(raw, 16));
+        let fixed = (n as u128 + 1) * 32 + m as u128 * 8 + HEADER_LEN as u128;
+        if fixed > raw.len() as u128 {
+            return Err(malformed("truncated tables"));
+        }
+        let frozen = FrozenBullDag {
+            bytes,
+            n: n as usize,
+            m: m as usize,
+            _types: PhantomData,
+        };
+        let blobs = frozen.blob_start() as u128
+            + frozen.offset(0, frozen.n) as u128
+            + frozen.offset(1, frozen.n) as u128;
+        if blobs > frozen.bytes.as_ref().len() as u128 {
+            return Err(malformed("truncated blobs"));
+        }
+        Ok(frozen)
+    }
+
+    fn raw(&self) -> &[u8] {
+        self.bytes.as_ref()
+    }
+
+    /// The `pos`th entry of offset table `table`: 0 for indices, 1 for
+    /// data, 2 for references and 3 for sources
+    fn offset(&self, table: usize, pos: usize) -> usize {
+        let at = HEADER_LEN + (table * (self.n + 1) + pos) * 8;
+        read_u64(self.raw(), at) as usize
+    }
+
+    fn range(&self, table: usize, pos: usize) -> Range<usize> {
+        self.offset(table, pos)..self.offset(table, pos + 1)
+    }
+
+    fn list_start(&self, direction: Direction) -> usize {
+        let lists = HEADER_LEN + 4 * (self.n + 1) * 8;
+        match direction {
+            Direction::Reference => lists,
+            Direction::Source => lists + self.m * 4,
+        }
+    }
+
+    fn blob_start(&self) -> usize {
+        self.list_start(Direction::Source) + self.m * 4
+    }
+
+    fn blob(&self, table: usize, pos: usize) -> &[u8] {
+        let start = match table {
+            0 => self.blob_start(),
+            _ => self.blob_start() + self.offset(0, self.n),
+        };
+        let range = self.range(table, pos);
+        self.raw()
+            .get(start + range.start..start + range.end)
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    pub fn n_edges(&self) -> usize {
+        self.m
+    }
+
+    /// Finds the position of a vertex by binary search over the sorted
+    /// indices
+    pub fn position(&self, index: &Ix) -> Option<u32> {
+        let key = serde_json::to_vec(index).ok()?;
+        let (mut low, mut high) = (0, self.n);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.blob(0, mid).cmp(key.as_slice()) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Some(mid as u32),
+            }
+        }
+        None
+    }
+
+    /// Decodes the index of the vertex at `pos`
+    pub fn index_at(&self, pos: u32) -> Option<Ix> {
+        if pos as usize >= self.n {
+            return None;
+        }
+        serde_json::from_slice(self.blob(0, pos as usize)).ok()
+    }
+
+    /// Decodes the data of the vertex at `pos`
+    pub fn data_at(&self, pos: u32) -> Option<T> {
+        if pos as usize >= self.n {
+            return None;
+        }
+        serde_json::from_slice(self.blob(1, pos as usize)).ok()
+    }
+
+    /// The positions of a vertex's sources or references
+    pub fn neighbors_at(&self, pos: u32, direction: Direction) -> impl Iterator<Item = u32> + '_ {
+        let table = match direction {
+            Direction::Reference => 2,
+            Direction::Source => 3,
+        };
+        let range = match (pos as usize) < self.n {
+            true => self.range(table, pos as usize),
+            false => 0..0,
+        };
+        let start = self.list_start(direction);
+        range
+            .map(move |i| read_u32(self.raw(), start + i * 4))
+            .filter(|pos| (*pos as usize) < self.n)
+    }
+
+    pub fn contains_vertex(&self, index: &Ix) -> bool {
+        self.position(index).is_some()
+    }
+
+    pub fn get_data(&self, index: &Ix) -> Option<T> {
+        self.data_at(self.position(index)?)
+    }
+
+    /// The vertex's direct sources or references
+    pub fn neighbors(&self, index: &Ix, direction: Direction) -> Vec<Ix> {
+        self.position(index)
+            .map(|pos| {
+                self.neighbors_at(pos, direction)
+                    .filter_map(|n| self.index_at(n))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns every vertex reachable from `index` in the given direction,
+    /// including `index` itself, or an empty `Vec` if it is missing
+    pub fn trace(&self, index: &Ix, direction: Direction) -> Vec<Ix> {
+        let Some(start) = self.position(index) else {
+            return vec![];
+        };
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        let mut order = vec![];
+        while let Some(pos) = queue.pop_front() {
+            order.extend(self.index_at(pos));
+            for next in self.neighbors_at(pos, direction) {
+                if visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        order
+    }
+
+    /// Every vertex without sources
+    pub fn get_roots(&self) -> Vec<Ix> {
+        self.without(Direction::Source)
+    }
+
+    /// Every vertex without references
+    pub fn get_leaves(&self) -> Vec<Ix> {
+        self.without(Direction::Reference)
+    }
+
+    fn without(&self, direction: Direction) -> Vec<Ix> {
+        (0..self.n as u32)
+            .filter(|pos| self.neighbors_at(*pos, direction).next().is_none())
+            .filter_map(|pos| self.index_at(pos))
+            .collect()
+    }
+
+    pub fn into_bytes(self) -> B {
+        self.bytes
+    }
+}
+
+impl<B, T, Ix> FrozenBullDag<B, T, Ix>
+where
+    B: AsRef<[u8]>,
+    T: Clone + Debug + DeserializeOwned,
+    Ix: Index + Debug + Serialize + DeserializeOwned,
+{
+    /// Decodes the whole graph back into a [`BullDag`]
+    pub fn thaw(&self) -> Result<BullDag<T, Ix>, GraphError> {
+        let mut graph = BullDag::new();
+        let mut indices = Vec::with_capacity(self.n);
+        for pos in 0..self.n as u32 {
+            let index = self.index_at(pos).ok_or_else(|| malformed("bad index"))?;
+            let data = self.data_at(pos).ok_or_else(|| malformed("bad data"))?;
+            graph.add_vertex(&crate::vertex::Vertex::new(data, index.clone()));
+            indices.push(index);
+        }
+        for pos in 0..self.n as u32 {
+            for reference in self.neighbors_at(pos, Direction::Reference) {
+                graph.add_edge_ix(&indices[pos as usize], &indices[reference as usize])?;
+            }
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<T, Ix> FrozenBullDag<memmap2::Mmap, T, Ix>
+where
+    T: DeserializeOwned,
+    Ix: Serialize + DeserializeOwned,
+{
+    /// Memory-maps a file written with [`BullDag::freeze`]. The file must
+    /// not be modified while it is mapped.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, GraphError> {
+        let file = std::fs::File::open(path).map_err(GraphError::other)?;
+        // Safety: the caller guarantees the file isn't modified while
+        // mapped, as documented above.
+        let map = unsafe { memmap2::Mmap::map(&file) }.map_err(GraphError::other)?;
+        FrozenBullDag::new(map)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::builder::BullDagBuilder;
+use crate::graph::BullDag;
+use crate::vertex::Vertex;
+#[cfg(feature = "random")]
+use rand::{Rng, RngCore};
+use std::fmt::Debug;
+
+// Every generator indexes its vertices `0..n`, with `T::default()` data,
+// and only adds edges from a lower index to a higher one, so the graphs
+// are acyclic by construction and can be built in one pass rather than
+// checking each edge for cycles.
+fn build<T: Clone + Debug + Default>(
+    n: usize,
+    edges: impl IntoIterator<Item = (usize, usize)>,
+) -> BullDag<T, usize> {
+    let mut builder = BullDagBuilder::new();
+    (0..n).for_each(|ix| {
+        builder.add_vertex(Vertex::new(T::default(), ix));
+    });
+    edges.into_iter().for_each(|(source, reference)| {
+        builder.add_edge(source, reference);
+    });
+    builder
+        .build()
+        .expect("every edge goes from a lower to a higher index")
+}
+
+/// `n` vertices, each referencing the next
+///
+/// Example
+///
+/// ```
+/// use bulldag::generate;
+/// use bulldag::graph::BullDag;
+///
+/// let graph: BullDag<(), usize> = generate::chain(10);
+/// assert!(graph.n_edges() == 9);
+/// assert!(graph.get_roots().contains(&0));
+/// assert!(graph.get_leaves().contains(&9));
+/// ```
+pub fn chain<T: Clone + Debug + Default>(n: usize) -> BullDag<T, usize> {
+    build(n, (1..n).map(|ix| (ix - 1, ix)))
+}
+
+/// A complete binary tree with `depth` levels, `2^depth - 1` vertices,
+/// where vertex `i` references `2i + 1` and `2i + 2`
+pub fn binary_tree<T: Clone + Debug + Default>(depth: u32) -> BullDag<T, usize> {
+    let n = (1usize << depth) - 1;
+    build(n, (1..n).map(|child| ((child - 1) / 2, child)))
+}
+
+/// A `width` by `height` grid where every vertex references the one to
+/// its right and the one below it, so there are many paths between the
+/// top-left root and the bottom-right leaf. Vertex `(row, column)` has
+/// index `row * width + column`.
+pub fn diamond_lattice<T: Clone + Debug + Default>(
+    width: usize,
+    height: usize,
+) -> BullDag<T, usize> {
+    let index = |row: usize, column: usize| row * width + column;
+    let edges = (0..height).flat_map(|row| {
+        (0..width).flat_map(move |column| {
+            let right = (column + 1 < width).then(|| (index(row, column), index(row, column + 1)));
+            let down = (row + 1 < height).then(|| (index(row, column), index(row + 1, column)));
+            right.into_iter().chain(down)
+        })
+    });
+    build(width * height, edges)
+}
+
+/// `layers` layers of `width` vertices, where each vertex references each
+/// vertex of the next layer with probability `p`. Vertex `i` of layer `l`
+/// has index `l * width + i`.
+#[cfg(feature = "random")]
+pub fn layered<T: Clone + Debug + Default>(
+    layers: usize,
+    width: usize,
+    p: f64,
+    rng: &mut dyn RngCore,
+) -> BullDag<T, usize> {
+    let mut edges = vec![];
+    for layer in 1..layers {
+        for source in (layer - 1) * width..layer * width {
+            for reference in layer * width..(layer + 1) * width {
+                if rng.gen_bool(p) {
+                    edges.push((source, reference));
+                }
+            }
+        }
+    }
+    build(layers * width, edges)
+}
+
+/// `n` vertices where each vertex references each vertex with a higher
+/// index with probability `p`
+#[cfg(feature = "random")]
+pub fn random<T: Clone + Debug + Default>(
+    n: usize,
+    p: f64,
+    rng: &mut dyn RngCore,
+) -> BullDag<T, usize> {
+    let mut edges = vec![];
+    for source in 0..n {
+        for reference in source + 1..n {
+            if rng.gen_bool(p) {
+                edges.push((source, reference));
+            }
+        }
+    }
+    build(n, edges)
+}
@@ -1,30 +1,81 @@
-use crate::edge::Edge;
+use crate::edge::{Edge, EdgeMap, EdgeSpec, IntoEdgeSpec};
+use crate::epoch::EpochCheckpoint;
+use crate::history::History;
 use crate::index::Index;
+use crate::journal::{Change, Journal};
+use crate::memory::table_bytes;
+use crate::observe::{Event, Observers};
+use crate::reachability::ReachabilityIndex;
 use crate::vertex::{Direction, Vertex};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-use std::fmt::Debug;
+use std::collections::hash_map::Values;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock};
 
 /// A basic error enum with different potential error types and a tuple
 /// variant for one-off and less predicatble error types
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum GraphError {
     WouldCycle,
     NonExistentSource,
     NonExistentReference,
     NonExistentVertex,
+    VertexExists,
+    /// The edge to remove or split does not exist
     NoEdges,
-    Other(String),
+    /// Any other error, such as a failure to parse or read a graph. Both
+    /// `Display` and `source` pass through to the wrapped error.
+    Other(Arc<dyn Error + Send + Sync>),
 }
 
-#[derive(Debug)]
-pub enum GraphOk<Ix: Index + Debug> {
-    Ok,
-    VecRes(Vec<Ix>),
+impl GraphError {
+    /// Wraps an error, or a message, in `GraphError::Other`
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::GraphError;
+    ///
+    /// let e = GraphError::other("read only");
+    /// assert!(e.to_string() == "read only");
+    /// ```
+    pub fn other(error: impl Into<Box<dyn Error + Send + Sync>>) -> GraphError {
+        GraphError::Other(Arc::from(error.into()))
+    }
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::WouldCycle => write!(f, "the edge would create a cycle"),
+            GraphError::NonExistentSource => write!(f, "the source vertex does not exist"),
+            GraphError::NonExistentReference => write!(f, "the reference vertex does not exist"),
+            GraphError::NonExistentVertex => write!(f, "the vertex does not exist"),
+            GraphError::VertexExists => write!(f, "the vertex already exists"),
+            GraphError::NoEdges => write!(f, "the edge does not exist"),
+            GraphError::Other(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl Error for GraphError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GraphError::Other(e) => e.source(),
+            _ => None,
+        }
+    }
 }
 
-/// Custom Type representing a Result specific to the graph
-pub type GraphResult<Ix> = Result<GraphOk<Ix>, GraphError>;
+/// Custom Type representing a Result specific to the graph, by default
+/// of a change that has nothing to return
+pub type GraphResult<T = ()> = Result<T, GraphError>;
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
 
 /// The core DAG graph structure, contains a hashmap of vertices
 /// with the key being the vertex's index, and the value being the
@@ -41,10 +92,105 @@ pub type GraphResult<Ix> = Result<GraphOk<Ix>, GraphError>;
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BullDag<T: Clone + Debug, Ix: Index + Debug> {
-    roots: HashSet<Ix>,
-    leaves: HashSet<Ix>,
-    vertices: HashMap<Ix, Vertex<T, Ix>>,
-    edges: HashSet<Edge<Ix>>,
+    pub(crate) roots: HashSet<Ix>,
+    pub(crate) leaves: HashSet<Ix>,
+    pub(crate) vertices: HashMap<Ix, Vertex<T, Ix>>,
+    pub(crate) edges: HashSet<Edge<Ix>>,
+    #[serde(default = "HashMap::new", skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) epochs: HashMap<Ix, u64>,
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    pub(crate) epoch_checkpoints: Vec<EpochCheckpoint<Ix>>,
+    #[serde(default = "HashMap::new", skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) conflicts: HashMap<Ix, HashSet<Ix>>,
+    /// The order vertices were added in, see [`BullDag::insertion_seq`]
+    #[serde(default = "HashMap::new", skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) sequence: HashMap<Ix, u64>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub(crate) next_sequence: u64,
+    #[serde(default = "Option::default", skip_serializing_if = "Option::is_none")]
+    pub(crate) edge_keys: Option<EdgeMap<Ix, BTreeSet<u64>>>,
+    #[serde(
+        default = "EdgeMap::default",
+        skip_serializing_if = "EdgeMap::is_empty"
+    )]
+    pub(crate) edge_kinds: EdgeMap<Ix, u32>,
+    #[serde(
+        default = "EdgeMap::default",
+        skip_serializing_if = "EdgeMap::is_empty"
+    )]
+    pub(crate) edge_times: EdgeMap<Ix, u64>,
+    #[serde(
+        default = "EdgeMap::default",
+        skip_serializing_if = "EdgeMap::is_empty"
+    )]
+    pub(crate) edge_weights: EdgeMap<Ix, u64>,
+    #[serde(skip, default = "Observers::default")]
+    pub(crate) observers: Observers<T, Ix>,
+    #[serde(skip, default = "Option::default")]
+    pub(crate) journal: Option<Journal<T, Ix>>,
+    #[serde(skip, default = "HashMap::default")]
+    pub(crate) checkpoints: HashMap<String, BullDag<T, Ix>>,
+    #[serde(skip, default = "Option::default")]
+    pub(crate) history: Option<History<T, Ix>>,
+    #[serde(skip, default = "OnceLock::default")]
+    pub(crate) topo_order: OnceLock<TopoOrder<Ix>>,
+    #[serde(skip, default = "Option::default")]
+    pub(crate) reachability: Option<OnceLock<ReachabilityIndex<Ix>>>,
+}
+
+/// A topological order of every vertex, kept between calls to
+/// [`BullDag::topological_order`] and updated as the graph changes where
+/// that is cheap, or dropped to be recomputed on the next call otherwise.
+#[derive(Debug, Clone)]
+pub(crate) struct TopoOrder<Ix: Index> {
+    order: Vec<Ix>,
+    position: HashMap<Ix, usize>,
+}
+
+impl<Ix: Index> TopoOrder<Ix> {
+    fn new(order: Vec<Ix>) -> TopoOrder<Ix> {
+        let position = order
+            .iter()
+            .enumerate()
+            .map(|(i, ix)| (ix.clone(), i))
+            .collect();
+        TopoOrder { order, position }
+    }
+
+    /// The bytes allocated for the order and the positions
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.order.capacity() * std::mem::size_of::<Ix>()
+            + table_bytes::<(Ix, usize)>(self.position.capacity())
+    }
+
+    /// Applies a change to the order, returning false if the order no
+    /// longer holds and has to be recomputed. A new vertex has no edges so
+    /// it goes last, a removed edge can't invalidate the order, and a new
+    /// edge only does if its source comes after its reference.
+    fn update<T: Clone + Debug>(&mut self, event: &Event<'_, T, Ix>) -> bool
+    where
+        Ix: Debug,
+    {
+        match event {
+            Event::VertexAdded(vertex) => {
+                self.position.insert(vertex.get_index(), self.order.len());
+                self.order.push(vertex.get_index());
+                true
+            }
+            Event::EdgeAdded(edge) => {
+                match (
+                    self.position.get(&edge.get_source()),
+                    self.position.get(&edge.get_reference()),
+                ) {
+                    (Some(source), Some(reference)) => source < reference,
+                    _ => false,
+                }
+            }
+            Event::VertexRemoved(_) => false,
+            // Neither data nor tags move a vertex
+            _ => true,
+        }
+    }
 }
 
 impl<T, Ix> Default for BullDag<T, Ix>
@@ -57,6 +203,164 @@ where
     }
 }
 
+impl<'a, T, Ix> IntoIterator for &'a BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    type Item = &'a Vertex<T, Ix>;
+    type IntoIter = Values<'a, Ix, Vertex<T, Ix>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.vertices.values()
+    }
+}
+
+/// Builds a graph from `(source, reference)` index pairs, creating any
+/// vertex that doesn't exist yet with `T::default()` as its data. As with
+/// [`BullDag::add_edge`] a pair that would create a cycle is not added.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+///
+/// let graph: BullDag<usize, &str> = vec![("a", "b"), ("a", "c"), ("b", "c")]
+///     .into_iter()
+///     .collect();
+/// assert!(graph.len() == 3);
+/// assert!(graph.n_edges() == 3);
+/// ```
+impl<T, Ix> FromIterator<(Ix, Ix)> for BullDag<T, Ix>
+where
+    T: Clone + Debug + Default,
+    Ix: Index + Debug,
+{
+    fn from_iter<I: IntoIterator<Item = (Ix, Ix)>>(iter: I) -> Self {
+        let mut graph = BullDag::new();
+        graph.extend(iter.into_iter().map(|(s, r)| Edge::new(s, r)));
+        graph
+    }
+}
+
+/// Adds edges from an iterator, see [`BullDag::add_edge`]
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::vertex::Vertex;
+///
+/// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+/// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+/// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+///
+/// let mut graph: BullDag<usize, &str> = BullDag::new();
+/// graph.extend(vec![(v1.clone(), v2), (v1, v3)]);
+/// assert!(graph.len() == 3);
+/// assert!(graph.n_edges() == 2);
+/// ```
+impl<T, Ix> Extend<(Vertex<T, Ix>, Vertex<T, Ix>)> for BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn extend<I: IntoIterator<Item = (Vertex<T, Ix>, Vertex<T, Ix>)>>(&mut self, iter: I) {
+        iter.into_iter()
+            .for_each(|(source, reference)| self.add_edge((&source, &reference)));
+    }
+}
+
+/// Adds edges from an iterator, creating any vertex that doesn't exist yet
+/// with `T::default()` as its data.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::edge::Edge;
+///
+/// let mut graph: BullDag<usize, &str> = BullDag::new();
+/// graph.extend(vec![Edge::new("source", "reference"), Edge::new("reference", "source")]);
+/// assert!(graph.len() == 2);
+/// assert!(graph.n_edges() == 1);
+/// ```
+impl<T, Ix> Extend<Edge<Ix>> for BullDag<T, Ix>
+where
+    T: Clone + Debug + Default,
+    Ix: Index + Debug,
+{
+    fn extend<I: IntoIterator<Item = Edge<Ix>>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|e| {
+            let source = self
+                .get_vertex(e.get_source())
+                .cloned()
+                .unwrap_or_else(|| Vertex::new(T::default(), e.get_source()));
+            let reference = self
+                .get_vertex(e.get_reference())
+                .cloned()
+                .unwrap_or_else(|| Vertex::new(T::default(), e.get_reference()));
+            self.add_edge((&source, &reference));
+        });
+    }
+}
+
+/// Builds a graph from vertices, see [`BullDag::add_vertex`]
+impl<T, Ix> FromIterator<Vertex<T, Ix>> for BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn from_iter<I: IntoIterator<Item = Vertex<T, Ix>>>(iter: I) -> Self {
+        let mut graph = BullDag::new();
+        iter.into_iter().for_each(|v| graph.add_vertex(&v));
+        graph
+    }
+}
+
+/// Two graphs are equal when they contain the same vertex indices with
+/// equal data, and the same set of edges. Roots and leaves are derived
+/// from the edges so they are not compared separately.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::vertex::Vertex;
+///
+/// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+/// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+/// let mut g1: BullDag<usize, &str> = BullDag::new();
+/// let mut g2: BullDag<usize, &str> = BullDag::new();
+/// g1.add_edge(&(&v1, &v2));
+/// assert!(g1 != g2);
+/// g2.add_edge(&(&v1, &v2));
+/// assert!(g1 == g2);
+/// ```
+impl<T, Ix> PartialEq for BullDag<T, Ix>
+where
+    T: Clone + Debug + PartialEq,
+    Ix: Index + Debug,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.vertices.len() == other.vertices.len()
+            && self.edges == other.edges
+            && self.vertices.iter().all(|(ix, vtx)| {
+                other
+                    .vertices
+                    .get(ix)
+                    .is_some_and(|o| o.get_data() == vtx.get_data())
+            })
+    }
+}
+
+impl<T, Ix> Eq for BullDag<T, Ix>
+where
+    T: Clone + Debug + Eq,
+    Ix: Index + Debug,
+{
+}
+
 impl<T, Ix> BullDag<T, Ix>
 where
     T: Clone + Debug,
@@ -78,7 +382,70 @@ where
             leaves: HashSet::new(),
             vertices: HashMap::new(),
             edges: HashSet::new(),
+            epochs: HashMap::new(),
+            epoch_checkpoints: vec![],
+            conflicts: HashMap::new(),
+            sequence: HashMap::new(),
+            next_sequence: 0,
+            edge_keys: None,
+            edge_kinds: EdgeMap::default(),
+            edge_times: EdgeMap::default(),
+            edge_weights: EdgeMap::default(),
+            observers: Observers::default(),
+            journal: None,
+            checkpoints: HashMap::new(),
+            history: None,
+            topo_order: OnceLock::new(),
+            reachability: None,
+        }
+    }
+
+    /// Creates an empty graph with room for at least `n_vertices`
+    /// vertices and `n_edges` edges before it has to grow, for loading
+    /// graphs whose size is known up front
+    ///
+    /// Example
+    /// ```
+    /// use bulldag::graph::BullDag;
+    ///
+    /// let graph: BullDag<usize, usize> = BullDag::with_capacity(1000, 5000);
+    /// assert!(graph.is_empty());
+    /// ```
+    pub fn with_capacity(n_vertices: usize, n_edges: usize) -> BullDag<T, Ix> {
+        let mut graph = BullDag::new();
+        graph.reserve_vertices(n_vertices);
+        graph.reserve_edges(n_edges);
+        graph
+    }
+
+    /// Makes room for at least `additional` more vertices
+    pub fn reserve_vertices(&mut self, additional: usize) {
+        self.vertices.reserve(additional);
+    }
+
+    /// Makes room for at least `additional` more edges
+    pub fn reserve_edges(&mut self, additional: usize) {
+        self.edges.reserve(additional);
+    }
+
+    /// Passes a change on to the history, if enabled, and the observers
+    pub(crate) fn notify(&mut self, event: Event<'_, T, Ix>) {
+        if let Some(history) = self.history.as_mut() {
+            history.track(&event);
+        }
+        if let Some(order) = self.topo_order.get_mut() {
+            if !order.update(&event) {
+                self.topo_order.take();
+            }
         }
+        if let Some(index) = self.reachability.as_mut() {
+            if let Some(reach) = index.get_mut() {
+                if !reach.update(&event, &self.vertices) {
+                    index.take();
+                }
+            }
+        }
+        self.observers.notify(event);
     }
 
     /// Adds a root to the roots set, roots are vertices with no sources
@@ -148,53 +515,178 @@ where
     /// println!("{:?}", graph);
     /// assert!(graph.n_edges() == 1);
     /// ```
-    pub fn add_edge(&mut self, edge: &(&Vertex<T, Ix>, &Vertex<T, Ix>)) {
-        let mut source = edge.0.clone();
-        let mut reference = edge.1.clone();
-        let e: Edge<Ix> = edge.into();
+    pub fn add_edge(&mut self, edge: impl IntoEdgeSpec<T, Ix>) {
+        // A cyclic edge is silently dropped, use `add_edge_ix` to find out
+        // whether the edge was added.
+        let _ = self.try_add_edge(edge);
+    }
 
-        source.add_edge(&e);
-        reference.add_edge(&e);
+    /// Adds an edge if it would not create a cycle, otherwise returns
+    /// `GraphError::WouldCycle` and leaves the graph untouched
+    pub(crate) fn try_add_edge(&mut self, edge: impl IntoEdgeSpec<T, Ix>) -> GraphResult {
+        self.journaled_insert_edge(edge.into_edge_spec(), true)
+    }
+
+    /// Adds an edge (and its vertices) without checking whether it creates
+    /// a cycle, for input that is already known to be acyclic, such as a
+    /// replayed journal or a verified snapshot. Otherwise the same as
+    /// `add_edge`.
+    ///
+    /// The caller must make sure the edge doesn't close a cycle: the
+    /// traversals, orders and indices of a cyclic graph are meaningless.
+    /// Run [`BullDag::validate`] after loading untrusted input to find out.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge_unchecked(&(&v1, &v2));
+    /// assert!(graph.n_edges() == 1);
+    /// assert!(graph.validate().is_ok());
+    /// ```
+    pub fn add_edge_unchecked(&mut self, edge: impl IntoEdgeSpec<T, Ix>) {
+        let _ = self.journaled_insert_edge(edge.into_edge_spec(), false);
+    }
+
+    /// Batch add edges (and vertices) with `add_edge_unchecked`, under the
+    /// same contract
+    pub fn extend_unchecked(&mut self, edges: impl IntoIterator<Item = impl IntoEdgeSpec<T, Ix>>) {
+        edges.into_iter().for_each(|e| self.add_edge_unchecked(e));
+    }
+
+    /// Looks up the vertices of an edge given by index, failing with
+    /// `NonExistentSource` or `NonExistentReference` if either is missing
+    fn edge_vertices(&self, spec: EdgeSpec<T, Ix>) -> GraphResult<(Vertex<T, Ix>, Vertex<T, Ix>)> {
+        match spec {
+            EdgeSpec::Vertices(source, reference) => Ok((source, reference)),
+            EdgeSpec::Indices(source, reference) => {
+                let source = self
+                    .vertices
+                    .get(&source)
+                    .ok_or(GraphError::NonExistentSource)?;
+                let reference = self
+                    .vertices
+                    .get(&reference)
+                    .ok_or(GraphError::NonExistentReference)?;
+                Ok((source.clone(), reference.clone()))
+            }
+        }
+    }
 
-        if self.check_cycles(edge).is_ok() {
-            // Check if the vertex already exists, if so, get a mutable reference
-            // to it, so that you can add this new edge to its `references` store
-            // since we are adding a reference to this vertex, check if it was
-            // previously a `leaf`, i.e. a node with no references.
-            // if it was, remove it as it no longer is a `leaf`, it now contains
-            // a reference.
-            //
-            // If the vertex does not already exist, add the edge and add the
-            // vertex, the `add_vertex` method will handle the rest.
-            if let Some(vtx) = self.get_vertex(source.get_index()) {
-                let mut updated_vtx = vtx.clone();
-                updated_vtx.add_edge(&e);
-                self.add_vertex(&updated_vtx);
-                self.clean_leaf(updated_vtx.get_index());
-            } else {
-                self.add_vertex(&source);
+    fn journaled_insert_edge(&mut self, spec: EdgeSpec<T, Ix>, check_cycles: bool) -> GraphResult {
+        let edge = spec.edge();
+        let (source, reference) = match self.edge_vertices(spec) {
+            Ok(vertices) => vertices,
+            Err(e) => {
+                self.notify(Event::EdgeRejected(&edge, &e));
+                return Err(e);
             }
+        };
+
+        // The vertices the edge adds are journaled with it, as one step.
+        let journal = self.journal.take();
+        let new_vertices: Vec<Vertex<T, Ix>> = [&source, &reference]
+            .into_iter()
+            .filter(|vtx| !self.vertices.contains_key(&vtx.get_index()))
+            .map(|vtx| Vertex::new(vtx.get_data(), vtx.get_index()))
+            .collect();
+        let n_edges = self.edges.len();
+        let result = self.insert_edge(source, reference, check_cycles);
+        self.journal = journal;
+
+        if self.edges.len() > n_edges {
+            self.record(Change::AddEdge { edge, new_vertices });
+        }
+        result
+    }
 
-            // Check if the vertex already exists, if so, get a mutable reference
-            // to it, so that you can add this new edge to its `sources` store
-            // since we are adding a source to this vertex, check if it was
-            // previously a `root`, i.e. a node with no sources.
-            // if it was, remove it as it is no longer a `root`, it now contains
-            // a source.
-            //
-            // If the vertex does not already exist, add the edge and add the
-            // vertex, the `add_vertex` method will handle the rest.
-            if let Some(vtx) = self.get_vertex(reference.get_index()) {
-                let mut updated_vtx = vtx.clone();
-                updated_vtx.add_edge(&e);
-                self.add_vertex(&updated_vtx);
-                self.clean_root(updated_vtx.get_index());
-            } else {
-                self.add_vertex(&reference);
+    fn insert_edge(
+        &mut self,
+        mut source: Vertex<T, Ix>,
+        mut reference: Vertex<T, Ix>,
+        check_cycles: bool,
+    ) -> GraphResult {
+        let e: Edge<Ix> = Edge::new(source.get_index(), reference.get_index());
+        if check_cycles {
+            if let Err(err) = self.check_cycles(&source, &reference) {
+                self.notify(Event::EdgeRejected(&e, &err));
+                return Err(err);
             }
+        }
+
+        source.add_edge(&e);
+        reference.add_edge(&e);
+
+        // Check if the vertex already exists, if so, get a mutable reference
+        // to it, so that you can add this new edge to its `references` store
+        // since we are adding a reference to this vertex, check if it was
+        // previously a `leaf`, i.e. a node with no references.
+        // if it was, remove it as it no longer is a `leaf`, it now contains
+        // a reference.
+        //
+        // If the vertex does not already exist, add the edge and add the
+        // vertex, the `add_vertex` method will handle the rest.
+        if let Some(vtx) = self.vertices.get_mut(&source.get_index()) {
+            vtx.add_edge(&e);
+            self.clean_leaf(source.get_index());
+        } else {
+            self.add_vertex(&source);
+        }
+
+        // Check if the vertex already exists, if so, get a mutable reference
+        // to it, so that you can add this new edge to its `sources` store
+        // since we are adding a source to this vertex, check if it was
+        // previously a `root`, i.e. a node with no sources.
+        // if it was, remove it as it is no longer a `root`, it now contains
+        // a source.
+        //
+        // If the vertex does not already exist, add the edge and add the
+        // vertex, the `add_vertex` method will handle the rest.
+        if let Some(vtx) = self.vertices.get_mut(&reference.get_index()) {
+            vtx.add_edge(&e);
+            self.clean_root(reference.get_index());
+        } else {
+            self.add_vertex(&reference);
+        }
 
-            self.edges.insert(e.clone());
+        if self.edges.insert(e.clone()) {
+            self.add_default_edge_key(&e);
+            self.notify(Event::EdgeAdded(&e));
         }
+
+        Ok(())
+    }
+
+    /// Adds an edge between two vertices that are already in the graph,
+    /// without having to pass the vertices themselves. Unlike `add_edge`
+    /// the outcome is reported: fails with `NonExistentSource` or
+    /// `NonExistentReference` if either vertex is missing, and with
+    /// `WouldCycle` if the edge would create a cycle.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::{BullDag, GraphError};
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// graph.add_vertices(&[Vertex::new(5, "source"), Vertex::new(4, "reference")]);
+    /// assert!(graph.add_edge_ix(&"source", &"reference").is_ok());
+    /// assert!(matches!(
+    ///     graph.add_edge_ix(&"reference", &"source"),
+    ///     Err(GraphError::WouldCycle)
+    /// ));
+    /// assert!(matches!(
+    ///     graph.add_edge_ix(&"source", &"missing"),
+    ///     Err(GraphError::NonExistentReference)
+    /// ));
+    /// assert!(graph.n_edges() == 1);
+    /// ```
+    pub fn add_edge_ix(&mut self, source: &Ix, reference: &Ix) -> GraphResult {
+        self.try_add_edge((source, reference))
     }
 
     /// Batch add edges (and vertices)
@@ -217,36 +709,49 @@ where
     /// assert!(graph.n_roots() == 1);
     /// assert!(graph.n_leaves() == 2);
     /// ```
-    #[allow(clippy::type_complexity)]
-    pub fn extend_from_edges(&mut self, edges: &[(&Vertex<T, Ix>, &Vertex<T, Ix>)]) {
-        edges.iter().for_each(|e| {
-            let source = e.0.get_index();
-            let reference = e.1.get_index();
-            if let Some(src) = self.get_vertex(source) {
-                let edge: Edge<Ix> = e.into();
-                let mut src = src.clone();
-                src.add_edge(&edge);
-            } else {
-                let edge: Edge<Ix> = e.into();
-                let mut src = e.0.clone();
-                src.add_edge(&edge);
-            }
+    pub fn extend_from_edges(&mut self, edges: impl IntoIterator<Item = impl IntoEdgeSpec<T, Ix>>) {
+        // Cyclic edges are silently dropped, use `try_extend_from_edges` to
+        // find out which edges were added.
+        let _ = self.try_extend_from_edges(edges);
+    }
 
-            if let Some(r) = self.get_vertex(reference) {
-                let edge: Edge<Ix> = e.into();
-                let mut r = r.clone();
-                r.add_edge(&edge);
-            } else {
-                let edge = e.into();
-                let mut r = e.1.clone();
-                r.add_edge(&edge);
-            }
-            self.add_edge(e);
-        });
+    /// Batch add edges (and vertices) like `extend_from_edges`, returning
+    /// the outcome of adding each edge, in the same order as the edges.
+    /// Edges that would create a cycle are skipped with
+    /// `GraphError::WouldCycle` and the rest are still added.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use bulldag::graph::{BullDag, GraphError};
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    ///
+    /// let results = graph.try_extend_from_edges(&[(&v1, &v2), (&v2, &v1), (&v1, &v3)]);
+    /// assert!(results[0].is_ok() && results[2].is_ok());
+    /// assert!(matches!(results[1], Err(GraphError::WouldCycle)));
+    /// assert!(graph.n_edges() == 2);
+    /// ```
+    pub fn try_extend_from_edges(
+        &mut self,
+        edges: impl IntoIterator<Item = impl IntoEdgeSpec<T, Ix>>,
+    ) -> Vec<GraphResult> {
+        edges.into_iter().map(|e| self.try_add_edge(e)).collect()
     }
 
-    /// Adds a single vertex to the graph
+    /// Adds a single vertex to the graph. If a vertex with the same index
+    /// is already in the graph only its data is replaced, its edges are
+    /// kept, and observers see the vertex as updated rather than added.
     pub fn add_vertex(&mut self, vertex: &Vertex<T, Ix>) {
+        if self.contains_vertex(&vertex.get_index()) {
+            self.set_vertex_data(&vertex.get_index(), vertex.get_data());
+            return;
+        }
+
         if vertex.get_sources().is_empty() {
             self.add_root(vertex.get_index());
         }
@@ -256,116 +761,996 @@ where
         }
 
         self.vertices.insert(vertex.get_index(), vertex.clone());
+        self.sequence.insert(vertex.get_index(), self.next_sequence);
+        self.next_sequence += 1;
+        self.record(Change::AddVertex(vertex.clone()));
+        self.notify(Event::VertexAdded(vertex));
     }
 
-    /// Gets the vertex at key `target`
-    pub fn get_vertex(&self, target: Ix) -> Option<&Vertex<T, Ix>> {
-        self.vertices.get(&target)
-    }
-
-    pub fn get_vertex_mut(&mut self, target: Ix) -> Option<&mut Vertex<T, Ix>> {
-        self.vertices.get_mut(&target)
+    /// Replaces the data of the vertex at `ix`, returning the data it had,
+    /// or `None` if there is no such vertex
+    pub(crate) fn set_vertex_data(&mut self, ix: &Ix, data: T) -> Option<T> {
+        let vtx = self.vertices.get_mut(ix)?;
+        let previous = std::mem::replace(vtx.data_mut(), data);
+        self.data_changed(ix, previous.clone());
+        Some(previous)
     }
 
-    pub fn add_vertices(&mut self, vertices: &[Vertex<T, Ix>]) {
-        vertices.iter().for_each(|v| {
-            self.add_vertex(v);
+    /// Passes a change to the data of the vertex at `ix`, which had the
+    /// data `previous` before, on to the history and the observers, and
+    /// journals it
+    pub(crate) fn data_changed(&mut self, ix: &Ix, previous: T) {
+        let Some(data) = self.vertices.get(ix).map(Vertex::get_data) else {
+            return;
+        };
+        self.notify(Event::VertexUpdated {
+            index: ix,
+            data: &data,
+            previous: &previous,
+        });
+        self.record(Change::SetData {
+            index: ix.clone(),
+            data,
+            previous,
         });
     }
 
-    /// Returns the number of vertices in the graph as usize
-    pub fn len(&self) -> usize {
-        self.vertices.len()
-    }
-
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
-    }
-
-    /// Returns the number of edges in the graph as a usize
-    pub fn n_edges(&self) -> usize {
-        self.edges.len()
-    }
-
-    pub fn trace(&self, target: &Vertex<T, Ix>, direction: Direction) -> Vec<Ix> {
-        let mut stack = vec![];
-        match direction {
-            Direction::Source => {
-                self.get_sources(target, &mut stack);
-            }
-            Direction::Reference => {
-                self.get_references(target, &mut stack);
+    /// Adds a vertex at `ix` with the data `insert` returns if there is
+    /// none, otherwise lets `update` change the data of the vertex in
+    /// place, keeping its edges. Observers see, and the journal records,
+    /// either the new vertex or the update.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// graph.upsert("source", || 0, |data| *data += 1);
+    /// graph.upsert("new", || 0, |data| *data += 1);
+    /// assert!(graph.get_vertex("source").unwrap().get_data() == 6);
+    /// assert!(graph.get_vertex("source").unwrap().n_references() == 1);
+    /// assert!(graph.get_vertex("new").unwrap().get_data() == 0);
+    /// ```
+    pub fn upsert(&mut self, ix: Ix, insert: impl FnOnce() -> T, update: impl FnOnce(&mut T)) {
+        match self.vertices.get_mut(&ix) {
+            Some(vtx) => {
+                let previous = vtx.get_data();
+                update(vtx.data_mut());
+                self.data_changed(&ix, previous);
             }
+            None => self.add_vertex(&Vertex::new(insert(), ix)),
         }
-
-        stack
     }
 
-    fn get_sources(&self, target: &Vertex<T, Ix>, stack: &mut Vec<Ix>) {
-        let mut edges = self.edges.clone();
-        edges.retain(|e| e.get_reference() == target.get_index());
-        let sources: Vec<Ix> = edges.iter().map(|e| e.get_source()).collect();
-
-        if !sources.is_empty() {
-            for source in sources {
-                if let Some(vtx) = self.get_vertex(source.clone()) {
-                    self.get_sources(vtx, stack);
-                }
-            }
+    /// Removes an edge from the graph and from both of its vertices.
+    /// A source left without references becomes a leaf, and a reference
+    /// left without sources becomes a root. Returns `false` if the edge
+    /// was not in the graph.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    /// use bulldag::edge::Edge;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    /// assert!(graph.remove_edge(&Edge::new("source", "reference")));
+    /// assert!(graph.n_edges() == 0);
+    /// assert!(graph.n_roots() == 2);
+    /// assert!(graph.n_leaves() == 2);
+    /// ```
+    pub fn remove_edge(&mut self, edge: &Edge<Ix>) -> bool {
+        if !self.edges.remove(edge) {
+            return false;
         }
+        let tags = self.take_edge_tags(edge);
 
-        if !stack.contains(&target.get_index()) {
-            stack.push(target.get_index());
-        }
-    }
+        let source = edge.get_source();
+        let reference = edge.get_reference();
 
-    fn get_references(&self, target: &Vertex<T, Ix>, stack: &mut Vec<Ix>) {
-        let mut edges = self.edges.clone();
-        edges.retain(|e| e.get_source() == target.get_index());
-        let references: Vec<Ix> = edges.iter().map(|e| e.get_reference()).collect();
-
-        if !references.is_empty() {
-            for reference in references {
-                if let Some(vtx) = self.get_vertex(reference.clone()) {
-                    self.get_references(vtx, stack);
-                }
+        if let Some(vtx) = self.vertices.get_mut(&source) {
+            vtx.remove_reference(&reference);
+            if vtx.n_references() == 0 {
+                self.add_leaf(source);
             }
         }
 
-        if !stack.contains(&target.get_index()) {
-            stack.push(target.get_index());
+        if let Some(vtx) = self.vertices.get_mut(&reference) {
+            vtx.remove_source(&edge.get_source());
+            if vtx.n_sources() == 0 {
+                self.add_root(reference);
+            }
         }
-    }
 
-    fn auto_source_cycle(&self) -> bool {
-        self.n_roots() == 0 && !self.is_empty()
-    }
+        // Only once the removal is complete, as in `insert_edge`
+        self.record(Change::RemoveEdge {
+            edge: edge.clone(),
+            tags,
+        });
+        self.notify(Event::EdgeRemoved(edge));
 
-    fn auto_ref_cycle(&self) -> bool {
-        self.n_leaves() == 0 && !self.is_empty()
+        true
     }
 
-    /// Checks whether the given edge would cause a cycle
-    fn check_cycles(&self, edge: &(&Vertex<T, Ix>, &Vertex<T, Ix>)) -> GraphResult<Ix> {
-        if self.auto_source_cycle() || self.auto_ref_cycle() {
+    /// Removes a vertex and every edge it is part of from the graph,
+    /// returning the removed vertex if it existed.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    /// let removed = graph.remove_vertex("source");
+    /// assert!(removed.is_some());
+    /// assert!(graph.len() == 1);
+    /// assert!(graph.n_edges() == 0);
+    /// assert!(graph.get_roots().contains("reference"));
+    /// ```
+    pub fn remove_vertex(&mut self, target: Ix) -> Option<Vertex<T, Ix>> {
+        // The edges removed with the vertex are journaled with it, as one
+        // step.
+        let change = self
+            .journal
+            .as_ref()
+            .and_then(|_| self.vertex_removal(&target));
+        let journal = self.journal.take();
+        let removed = self.detach_vertex(target);
+        self.journal = journal;
+
+        if let Some(change) = change {
+            self.record(change);
+        }
+        removed
+    }
+
+    fn detach_vertex(&mut self, target: Ix) -> Option<Vertex<T, Ix>> {
+        // Take the vertex out first so it is returned with its sources and
+        // references intact, `remove_edge` only updates the other endpoint.
+        let vtx = self.vertices.remove(&target)?;
+        let mut incident: Vec<Edge<Ix>> = vtx
+            .get_sources()
+            .into_iter()
+            .map(|s| Edge::new(s.clone(), target.clone()))
+            .collect();
+        incident.extend(
+            vtx.get_references()
+                .into_iter()
+                .map(|r| Edge::new(target.clone(), r.clone())),
+        );
+
+        incident.iter().for_each(|e| {
+            self.remove_edge(e);
+        });
+
+        self.roots.remove(&target);
+        self.leaves.remove(&target);
+        self.epochs.remove(&target);
+        self.sequence.remove(&target);
+        self.clear_conflicts(&target);
+        self.notify(Event::VertexRemoved(&vtx));
+        Some(vtx)
+    }
+
+    /// Retains only the vertices for which the predicate returns true,
+    /// removing all others along with every edge they are part of, like
+    /// [`HashMap::retain`]. Roots and leaves are updated as edges are
+    /// removed.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v2, &v3)]);
+    ///
+    /// graph.retain_vertices(|_, vtx| vtx.get_data() != 4);
+    /// assert!(graph.len() == 2);
+    /// assert!(graph.n_edges() == 0);
+    /// assert!(graph.n_roots() == 2);
+    /// ```
+    pub fn retain_vertices(&mut self, mut f: impl FnMut(&Ix, &Vertex<T, Ix>) -> bool) {
+        let remove: Vec<Ix> = self
+            .vertices
+            .iter()
+            .filter(|(ix, vtx)| !f(ix, vtx))
+            .map(|(ix, _)| ix.clone())
+            .collect();
+
+        remove.into_iter().for_each(|ix| {
+            self.remove_vertex(ix);
+        });
+    }
+
+    /// Removes every vertex and edge, along with the epochs, epoch
+    /// checkpoints and conflicts, keeping the memory already allocated so
+    /// the graph can be reused. Observers, checkpoints and history are
+    /// kept. Like [`BullDag::restore`], neither observers nor the version
+    /// history see the change, and the undo and redo history is cleared.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// graph.clear();
+    /// assert!(graph.is_empty());
+    /// assert!(graph.n_edges() == 0 && graph.n_roots() == 0);
+    /// ```
+    pub fn clear(&mut self) {
+        self.roots.clear();
+        self.leaves.clear();
+        self.vertices.clear();
+        self.edges.clear();
+        self.epochs.clear();
+        self.epoch_checkpoints.clear();
+        self.conflicts.clear();
+        self.sequence.clear();
+        self.next_sequence = 0;
+        if let Some(keys) = self.edge_keys.as_mut() {
+            keys.clear();
+        }
+        self.edge_kinds.clear();
+        self.edge_times.clear();
+        self.edge_weights.clear();
+        self.invalidate_caches();
+        if self.journal.is_some() {
+            self.disable_journal();
+            self.enable_journal();
+        }
+    }
+
+    /// Clears the graph like [`BullDag::clear`], returning its vertices
+    /// with their sources and references intact
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// let drained: Vec<Vertex<usize, &str>> = graph.drain_vertices().collect();
+    /// assert!(drained.len() == 2);
+    /// assert!(drained.iter().all(|vtx| vtx.n_sources() + vtx.n_references() == 1));
+    /// assert!(graph.is_empty());
+    /// ```
+    pub fn drain_vertices(&mut self) -> impl Iterator<Item = Vertex<T, Ix>> + '_ {
+        let vertices = std::mem::take(&mut self.vertices);
+        self.clear();
+        self.vertices = vertices;
+        // Dropping the iterator early still removes every vertex
+        self.vertices.drain().map(|(_, vtx)| vtx)
+    }
+
+    /// Removes every vertex that is buried more than `depth` levels beneath
+    /// the leaf frontier, returning the removed vertices (with their sources
+    /// and references intact) so they can be archived. The depth of a vertex
+    /// is the length of the shortest path from it to any leaf, so a vertex
+    /// is only pruned once every path to the frontier is long enough.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, usize> = BullDag::new();
+    /// let chain: Vec<Vertex<usize, usize>> = (0..5).map(|i| Vertex::new(i, i)).collect();
+    /// chain.windows(2).for_each(|w| graph.add_edge(&(&w[0], &w[1])));
+    ///
+    /// let pruned = graph.prune_below_depth(2);
+    /// assert!(pruned.len() == 2);
+    /// assert!(graph.len() == 3);
+    /// assert!(graph.get_roots().contains(&2));
+    /// ```
+    pub fn prune_below_depth(&mut self, depth: usize) -> Vec<Vertex<T, Ix>> {
+        let remove: Vec<Ix> = self
+            .leaf_depths()
+            .into_iter()
+            .filter(|(_, d)| *d > depth)
+            .map(|(ix, _)| ix)
+            .collect();
+
+        remove
+            .into_iter()
+            .filter_map(|ix| self.remove_vertex(ix))
+            .collect()
+    }
+
+    /// Returns how deep a vertex is buried beneath the current leaf
+    /// frontier, i.e. the length of the shortest path from the vertex to
+    /// a leaf. Leaves, and vertices that are not in the graph, have a
+    /// confirmation depth of 0.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, usize> = BullDag::new();
+    /// let chain: Vec<Vertex<usize, usize>> = (0..5).map(|i| Vertex::new(i, i)).collect();
+    /// chain.windows(2).for_each(|w| graph.add_edge(&(&w[0], &w[1])));
+    ///
+    /// assert!(graph.confirmation_depth(&0) == 4);
+    /// assert!(graph.confirmation_depth(&4) == 0);
+    /// ```
+    pub fn confirmation_depth(&self, ix: &Ix) -> usize {
+        self.leaf_depths().get(ix).copied().unwrap_or(0)
+    }
+
+    /// Returns every vertex whose confirmation depth is at or beyond
+    /// `threshold`.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, usize> = BullDag::new();
+    /// let chain: Vec<Vertex<usize, usize>> = (0..5).map(|i| Vertex::new(i, i)).collect();
+    /// chain.windows(2).for_each(|w| graph.add_edge(&(&w[0], &w[1])));
+    ///
+    /// let finalized = graph.finalized(3);
+    /// assert!(finalized.len() == 2);
+    /// assert!(finalized.contains(&0) && finalized.contains(&1));
+    /// ```
+    pub fn finalized(&self, threshold: usize) -> HashSet<Ix> {
+        self.leaf_depths()
+            .into_iter()
+            .filter(|(_, d)| *d >= threshold)
+            .map(|(ix, _)| ix)
+            .collect()
+    }
+
+    /// Breadth first search from the leaves towards the roots, returning
+    /// the shortest distance from every vertex to the leaf frontier.
+    pub(crate) fn leaf_depths(&self) -> HashMap<Ix, usize> {
+        let mut depths: HashMap<Ix, usize> = HashMap::new();
+        let mut queue: VecDeque<Ix> = VecDeque::new();
+        self.leaves.iter().for_each(|leaf| {
+            depths.insert(leaf.clone(), 0);
+            queue.push_back(leaf.clone());
+        });
+
+        while let Some(ix) = queue.pop_front() {
+            let depth = depths[&ix];
+            if let Some(vtx) = self.vertices.get(&ix) {
+                for source in vtx.get_sources() {
+                    if !depths.contains_key(source) {
+                        depths.insert(source.clone(), depth + 1);
+                        queue.push_back(source.clone());
+                    }
+                }
+            }
+        }
+
+        depths
+    }
+
+    /// Groups the vertices by generation, the length of the longest path
+    /// to them from a root, so every vertex comes in a later generation
+    /// than all of its sources. Roots make up the first generation. The
+    /// order of the vertices within a generation is unspecified.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v2, &v3), (&v1, &v3)]);
+    ///
+    /// let generations = graph.generations();
+    /// assert!(generations == vec![vec!["source"], vec!["reference"], vec!["ref_reference"]]);
+    /// ```
+    pub fn generations(&self) -> Vec<Vec<Ix>> {
+        let mut generations: Vec<Vec<Ix>> = vec![];
+        for (ix, depth) in self.depth_map() {
+            if generations.len() <= depth {
+                generations.resize_with(depth + 1, Vec::new);
+            }
+            generations[depth].push(ix);
+        }
+        generations
+    }
+
+    /// Returns the number of edges on the longest path from a root to a
+    /// leaf, 0 for a graph without edges
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v2, &v3), (&v1, &v3)]);
+    ///
+    /// assert!(graph.depth() == 2);
+    /// assert!(graph.depth_of(&"ref_reference") == Some(2));
+    /// assert!(graph.depth_of(&"source") == Some(0));
+    /// ```
+    pub fn depth(&self) -> usize {
+        self.depth_map().into_values().max().unwrap_or(0)
+    }
+
+    /// Returns the number of edges on the longest path from a root to the
+    /// vertex at `ix`, or `None` if there is no such vertex
+    pub fn depth_of(&self, ix: &Ix) -> Option<usize> {
+        self.depth_map().get(ix).copied()
+    }
+
+    /// Returns the number of edges on the longest path from a root to
+    /// every vertex, from a single pass in topological order
+    pub fn depth_map(&self) -> HashMap<Ix, usize> {
+        let mut depths: HashMap<Ix, usize> = HashMap::with_capacity(self.len());
+        for ix in self.topological_order() {
+            let depth = self.vertices[&ix]
+                .get_sources()
+                .into_iter()
+                .filter_map(|source| depths.get(source))
+                .map(|d| d + 1)
+                .max()
+                .unwrap_or(0);
+            depths.insert(ix, depth);
+        }
+        depths
+    }
+
+    /// Gets the vertex at key `target`
+    pub fn get_vertex(&self, target: Ix) -> Option<&Vertex<T, Ix>> {
+        self.vertices.get(&target)
+    }
+
+    pub fn get_vertex_mut(&mut self, target: Ix) -> Option<&mut Vertex<T, Ix>> {
+        self.vertices.get_mut(&target)
+    }
+
+    /// Whether there is a vertex at key `target`
+    pub fn contains_vertex(&self, target: &Ix) -> bool {
+        self.vertices.contains_key(target)
+    }
+
+    /// Whether there is an edge from `source` to `reference`
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// assert!(graph.contains_vertex(&"source"));
+    /// assert!(graph.has_edge(&"source", &"reference"));
+    /// assert!(!graph.has_edge(&"reference", &"source"));
+    /// ```
+    pub fn has_edge(&self, source: &Ix, reference: &Ix) -> bool {
+        self.vertices
+            .get(source)
+            .is_some_and(|vtx| vtx.is_reference(reference))
+    }
+
+    pub fn add_vertices(&mut self, vertices: &[Vertex<T, Ix>]) {
+        vertices.iter().for_each(|v| {
+            self.add_vertex(v);
+        });
+    }
+
+    /// Iterates over the vertices in the graph, in no particular order
+    pub fn iter(&self) -> Values<'_, Ix, Vertex<T, Ix>> {
+        self.vertices.values()
+    }
+
+    /// Returns every vertex whose data matches the predicate, in no
+    /// particular order
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3)]);
+    ///
+    /// assert!(graph.find(|data| *data > 3).len() == 2);
+    /// assert!(graph.find_indices(|data| *data == 3) == vec!["reference_2"]);
+    /// ```
+    pub fn find(&self, pred: impl Fn(&T) -> bool) -> Vec<&Vertex<T, Ix>> {
+        self.vertices
+            .values()
+            .filter(|vtx| pred(vtx.data()))
+            .collect()
+    }
+
+    /// Returns a graph with the same vertices, edges, epochs and conflicts,
+    /// with `f` applied to the data of every vertex. Observers, the journal
+    /// and checkpoints are not carried over.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge((&v1, &v2));
+    ///
+    /// let labels: BullDag<String, &str> = graph.map_data(|data| data.to_string());
+    /// assert!(labels.get_vertex("reference").unwrap().get_data() == "4");
+    /// assert!(labels.has_edge(&"source", &"reference"));
+    /// ```
+    pub fn map_data<U: Clone + Debug>(&self, f: impl Fn(&T) -> U) -> BullDag<U, Ix> {
+        self.with_vertices(
+            self.vertices
+                .iter()
+                .map(|(ix, vtx)| (ix.clone(), vtx.with_data(f(vtx.data()))))
+                .collect(),
+        )
+    }
+
+    /// A graph with the structure of this one, i.e. its edges and the
+    /// epochs, epoch checkpoints, conflicts, insertion order and edge
+    /// kinds, times, weights and keys tagged on them, and the given
+    /// vertices. It has no observers, checkpoints, journal or history.
+    pub(crate) fn with_vertices<U: Clone + Debug>(
+        &self,
+        vertices: HashMap<Ix, Vertex<U, Ix>>,
+    ) -> BullDag<U, Ix> {
+        // Destructured in full so that a new field can't be left out
+        let BullDag {
+            roots,
+            leaves,
+            vertices: _,
+            edges,
+            epochs,
+            epoch_checkpoints,
+            conflicts,
+            sequence,
+            next_sequence,
+            edge_keys,
+            edge_kinds,
+            edge_times,
+            edge_weights,
+            observers: _,
+            journal: _,
+            checkpoints: _,
+            history: _,
+            topo_order: _,
+            reachability: _,
+        } = self;
+        BullDag {
+            roots: roots.clone(),
+            leaves: leaves.clone(),
+            vertices,
+            edges: edges.clone(),
+            epochs: epochs.clone(),
+            epoch_checkpoints: epoch_checkpoints.clone(),
+            conflicts: conflicts.clone(),
+            sequence: sequence.clone(),
+            next_sequence: *next_sequence,
+            edge_keys: edge_keys.clone(),
+            edge_kinds: edge_kinds.clone(),
+            edge_times: edge_times.clone(),
+            edge_weights: edge_weights.clone(),
+            ..BullDag::new()
+        }
+    }
+
+    /// Returns the index of every vertex whose data matches the predicate,
+    /// in no particular order
+    pub fn find_indices(&self, pred: impl Fn(&T) -> bool) -> Vec<Ix> {
+        self.find(pred)
+            .into_iter()
+            .map(|vtx| vtx.get_index())
+            .collect()
+    }
+
+    /// Returns the number of vertices in the graph as usize
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of edges in the graph as a usize
+    pub fn n_edges(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Returns the number of sources of the vertex at `ix`, or `None` if
+    /// there is no such vertex
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3)]);
+    ///
+    /// assert!(graph.in_degree(&"reference_1") == Some(1));
+    /// assert!(graph.out_degree(&"source") == Some(2));
+    /// assert!(graph.out_degree(&"missing").is_none());
+    /// ```
+    pub fn in_degree(&self, ix: &Ix) -> Option<usize> {
+        self.vertices.get(ix).map(|vtx| vtx.n_sources())
+    }
+
+    /// Returns the number of references of the vertex at `ix`, or `None`
+    /// if there is no such vertex
+    pub fn out_degree(&self, ix: &Ix) -> Option<usize> {
+        self.vertices.get(ix).map(|vtx| vtx.n_references())
+    }
+
+    /// Returns the anticone of the vertex at `ix`, every vertex that is
+    /// neither reachable from it nor able to reach it, i.e. the vertices
+    /// that are concurrent with it. Empty if there is no such vertex.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4)]);
+    ///
+    /// assert!(graph.anticone(&"reference_2") == ["reference_1", "ref_reference"].into());
+    /// assert!(graph.anticone(&"source").is_empty());
+    /// ```
+    pub fn anticone(&self, ix: &Ix) -> HashSet<Ix> {
+        if !self.contains_vertex(ix) {
+            return HashSet::new();
+        }
+        let start = std::slice::from_ref(ix);
+        let past = self.reachable(start, Direction::Source, |_| false);
+        let future = self.reachable(start, Direction::Reference, |_| false);
+        self.vertices
+            .keys()
+            .filter(|other| !past.contains(*other) && !future.contains(*other))
+            .cloned()
+            .collect()
+    }
+
+    pub fn trace(&self, target: &Vertex<T, Ix>, direction: Direction) -> Vec<Ix> {
+        let mut stack = vec![];
+        match direction {
+            Direction::Source => {
+                self.get_sources(target, &mut stack);
+            }
+            Direction::Reference => {
+                self.get_references(target, &mut stack);
+            }
+        }
+
+        stack
+    }
+
+    fn get_sources(&self, target: &Vertex<T, Ix>, stack: &mut Vec<Ix>) {
+        self.dfs_post_order(
+            target.get_index(),
+            Direction::Source,
+            &mut HashSet::new(),
+            stack,
+        );
+    }
+
+    fn get_references(&self, target: &Vertex<T, Ix>, stack: &mut Vec<Ix>) {
+        self.dfs_post_order(
+            target.get_index(),
+            Direction::Reference,
+            &mut HashSet::new(),
+            stack,
+        );
+    }
+
+    /// Depth first search from `start` following `direction`, pushing each
+    /// vertex onto `stack` after everything reachable from it. Uses an
+    /// explicit stack rather than recursion, so that long chains can't
+    /// overflow the call stack, and skips the vertices in `visited`.
+    fn dfs_post_order(
+        &self,
+        start: Ix,
+        direction: Direction,
+        visited: &mut HashSet<Ix>,
+        stack: &mut Vec<Ix>,
+    ) {
+        self.post_order_where(start, direction, visited, stack, |_, _| true);
+    }
+
+    /// Like `dfs_post_order`, but only follows the edges for whose source
+    /// and reference `follow` returns true
+    pub(crate) fn post_order_where(
+        &self,
+        start: Ix,
+        direction: Direction,
+        visited: &mut HashSet<Ix>,
+        stack: &mut Vec<Ix>,
+        follow: impl Fn(&Ix, &Ix) -> bool,
+    ) {
+        let mut pending: Vec<(Ix, bool)> = vec![(start, false)];
+        while let Some((ix, expanded)) = pending.pop() {
+            if expanded {
+                stack.push(ix);
+                continue;
+            }
+            if !visited.insert(ix.clone()) {
+                continue;
+            }
+            let mut neighbors = self
+                .vertices
+                .get(&ix)
+                .map(|vtx| vtx.neighbors(direction))
+                .unwrap_or_default();
+            neighbors.retain(|n| match direction {
+                Direction::Source => follow(n, &ix),
+                Direction::Reference => follow(&ix, n),
+            });
+            pending.push((ix, true));
+            pending.extend(
+                neighbors
+                    .into_iter()
+                    .filter(|n| !visited.contains(*n))
+                    .map(|n| (n.clone(), false)),
+            );
+        }
+    }
+
+    fn auto_source_cycle(&self) -> bool {
+        self.n_roots() == 0 && !self.is_empty()
+    }
+
+    fn auto_ref_cycle(&self) -> bool {
+        self.n_leaves() == 0 && !self.is_empty()
+    }
+
+    /// Checks whether the given edge would cause a cycle
+    fn check_cycles(&self, source: &Vertex<T, Ix>, reference: &Vertex<T, Ix>) -> GraphResult {
+        if self.auto_source_cycle() || self.auto_ref_cycle() {
             return Err(GraphError::WouldCycle);
         }
 
-        let source_trace = self.trace(edge.0, Direction::Source);
-        if source_trace.contains(&edge.1.get_index()) {
+        if let Some(index) = self.reachability_index() {
+            let (source, reference) = (source.get_index(), reference.get_index());
+            if source == reference || index.is_reachable(&reference, &source) {
+                return Err(GraphError::WouldCycle);
+            }
+            return Ok(());
+        }
+
+        let source_trace = self.trace(source, Direction::Source);
+        if source_trace.contains(&reference.get_index()) {
             return Err(GraphError::WouldCycle);
         }
 
-        let ref_trace = self.trace(edge.1, Direction::Reference);
-        if ref_trace.contains(&edge.0.get_index()) {
+        let ref_trace = self.trace(reference, Direction::Reference);
+        if ref_trace.contains(&source.get_index()) {
             return Err(GraphError::WouldCycle);
         }
 
-        Ok(GraphOk::Ok)
+        Ok(())
+    }
+
+    /// Collects the vertices in `start` and everything reachable from them
+    /// following `direction`, without entering any vertex for which `skip`
+    /// returns true.
+    pub(crate) fn reachable(
+        &self,
+        start: &[Ix],
+        direction: Direction,
+        skip: impl Fn(&Ix) -> bool,
+    ) -> HashSet<Ix> {
+        let mut found: HashSet<Ix> = HashSet::new();
+        let mut stack: Vec<Ix> = start
+            .iter()
+            .filter(|ix| self.vertices.contains_key(ix) && !skip(ix))
+            .cloned()
+            .collect();
+
+        while let Some(ix) = stack.pop() {
+            if !found.insert(ix.clone()) {
+                continue;
+            }
+
+            if let Some(vtx) = self.vertices.get(&ix) {
+                for next in vtx.neighbors(direction) {
+                    if !skip(next) && !found.contains(next) {
+                        stack.push(next.clone());
+                    }
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Returns the members of `subset` in topological order
+    pub(crate) fn topological_order_of(&self, subset: &HashSet<Ix>) -> Vec<Ix> {
+        self.topological_order()
+            .into_iter()
+            .filter(|ix| subset.contains(ix))
+            .collect()
+    }
+
+    /// Returns every vertex index with each vertex appearing after all of
+    /// its sources. The order is cached, so on a graph that only grows by
+    /// new vertices and edges from earlier to later vertices it is computed
+    /// once, see [`TopoOrder::update`].
+    pub(crate) fn topological_order(&self) -> Vec<Ix> {
+        self.topo_order
+            .get_or_init(|| TopoOrder::new(self.compute_topological_order()))
+            .order
+            .clone()
+    }
+
+    /// Copies the vertices and the structure of the graph into a new graph,
+    /// see [`BullDag::with_vertices`]
+    pub(crate) fn copy_structure(&self) -> BullDag<T, Ix> {
+        self.with_vertices(self.vertices.clone())
+    }
+
+    /// Drops the cached topological order and reachability index, for
+    /// changes made to the fields directly rather than through the methods
+    /// that notify observers
+    pub(crate) fn invalidate_caches(&mut self) {
+        self.topo_order.take();
+        if let Some(index) = self.reachability.as_mut() {
+            index.take();
+        }
+    }
+
+    /// Kahn's algorithm, returns every vertex index with each vertex
+    /// appearing after all of its sources.
+    fn compute_topological_order(&self) -> Vec<Ix> {
+        let mut in_degree: HashMap<&Ix, usize> = self
+            .vertices
+            .iter()
+            .map(|(ix, vtx)| (ix, vtx.n_sources()))
+            .collect();
+        let mut queue: VecDeque<&Ix> = in_degree
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(ix, _)| *ix)
+            .collect();
+        let mut order = Vec::with_capacity(self.vertices.len());
+
+        while let Some(ix) = queue.pop_front() {
+            order.push(ix.clone());
+            if let Some(vtx) = self.vertices.get(ix) {
+                for r in vtx.get_references() {
+                    if let Some(d) = in_degree.get_mut(r) {
+                        *d -= 1;
+                        if *d == 0 {
+                            queue.push_back(r);
+                        }
+                    }
+                }
+            }
+        }
+
+        order
+    }
+
+    /// Computes the cumulative weight of every vertex, i.e. one for the
+    /// vertex itself plus the number of vertices that reference it directly
+    /// or indirectly. Vertices are visited once, in reverse topological
+    /// order, so each vertex's set of referencing vertices is built from
+    /// the sets of its references, and a set is dropped as soon as every
+    /// vertex that needs it has been visited. A leaf has a cumulative
+    /// weight of 1.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4), (&v3, &v4)]);
+    ///
+    /// let weights = graph.cumulative_weights();
+    /// assert!(weights["source"] == 4);
+    /// assert!(weights["reference_1"] == 2);
+    /// assert!(weights["ref_reference"] == 1);
+    /// ```
+    pub fn cumulative_weights(&self) -> HashMap<Ix, u64> {
+        self.reach_counts(Direction::Reference)
+    }
+
+    /// Counts, for every vertex, itself plus the vertices reachable from
+    /// it in `direction`. Following references this is the cumulative
+    /// weight, following sources it is the number of ancestors plus one.
+    pub(crate) fn reach_counts(&self, direction: Direction) -> HashMap<Ix, u64> {
+        let mut reachable: HashMap<Ix, HashSet<Ix>> = HashMap::new();
+        let mut counts = HashMap::with_capacity(self.vertices.len());
+
+        // How many vertices still have to read each vertex's set
+        let mut pending: HashMap<&Ix, usize> = HashMap::with_capacity(self.vertices.len());
+        for vtx in self.vertices.values() {
+            for n in vtx.neighbors(direction) {
+                *pending.entry(n).or_default() += 1;
+            }
+        }
+
+        // Visit every vertex after all of its neighbors in `direction`
+        let mut order = self.topological_order();
+        if direction == Direction::Reference {
+            order.reverse();
+        }
+
+        for ix in order {
+            let mut set: HashSet<Ix> = HashSet::new();
+            if let Some(vtx) = self.vertices.get(&ix) {
+                for n in vtx.neighbors(direction) {
+                    set.insert(n.clone());
+                    if let Some(n_set) = reachable.get(n) {
+                        set.extend(n_set.iter().cloned());
+                    }
+                    if let Some(left) = pending.get_mut(n) {
+                        *left -= 1;
+                        if *left == 0 {
+                            reachable.remove(n);
+                        }
+                    }
+                }
+            }
+            counts.insert(ix.clone(), set.len() as u64 + 1);
+            if pending.get(&ix).is_some_and(|left| *left > 0) {
+                reachable.insert(ix, set);
+            }
+        }
+
+        counts
     }
 
     #[cfg(test)]
-    pub(crate) fn topological_sort(&self) -> GraphResult<Ix> {
+    pub(crate) fn topological_sort(&self) -> GraphResult<Vec<Ix>> {
         let roots = self.get_roots();
         let leaves = self.get_leaves();
 
@@ -377,40 +1762,160 @@ where
             return Err(GraphError::WouldCycle);
         }
 
+        let mut visited: HashSet<Ix> = HashSet::new();
         let mut stack: Vec<Ix> = vec![];
-        let mut visited: Vec<Ix> = vec![];
 
         for root in roots {
             if let Some(vtx) = self.get_vertex(root.clone()) {
-                self.dfs(vtx, &mut stack, &mut visited)?;
+                self.dfs(vtx, &mut visited, &mut stack)?;
             }
         }
 
         stack.reverse();
 
-        Ok(GraphOk::VecRes(stack))
+        Ok(stack)
     }
 
     #[cfg(test)]
     fn dfs(
         &self,
         vertex: &Vertex<T, Ix>,
+        visited: &mut HashSet<Ix>,
         stack: &mut Vec<Ix>,
-        visited: &mut Vec<Ix>,
-    ) -> GraphResult<Ix> {
-        let references = vertex.get_references();
-        if !references.is_empty() {
-            for r in references {
-                if let Some(vtx) = self.get_vertex(r.clone()) {
-                    self.dfs(vtx, stack, visited)?;
-                }
-            }
+    ) -> GraphResult {
+        self.dfs_post_order(vertex.get_index(), Direction::Reference, visited, stack);
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "hash")]
+impl<T> BullDag<T, [u8; 32]>
+where
+    T: Clone + Debug + Serialize,
+{
+    /// Adds a content addressed vertex (see [`Vertex::content_addressed`])
+    /// holding `data` to the graph and returns its index. Inserting the
+    /// same data twice leaves the existing vertex and its edges untouched.
+    pub fn insert_content(&mut self, data: T) -> Result<[u8; 32], serde_json::Error> {
+        let vertex = Vertex::content_addressed(data)?;
+        let index = vertex.get_index();
+        if !self.contains_vertex(&index) {
+            self.add_vertex(&vertex);
         }
 
-        if !stack.contains(&vertex.get_index()) {
-            stack.push(vertex.get_index().clone());
+        Ok(index)
+    }
+}
+
+#[cfg(feature = "hash")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize,
+    Ix: Index + Debug + Serialize,
+{
+    /// Computes the Merkle style hash of every vertex: the SHA-256 of the
+    /// JSON serialized index and data followed by the sorted hashes of the
+    /// vertex's sources. A vertex hash therefore commits to the vertex and
+    /// to its entire ancestry. Vertices are visited in topological order
+    /// so every source is hashed before the vertices that reference it.
+    pub fn vertex_hashes(&self) -> Result<HashMap<Ix, [u8; 32]>, serde_json::Error> {
+        use sha2::{Digest, Sha256};
+
+        let mut hashes: HashMap<Ix, [u8; 32]> = HashMap::with_capacity(self.vertices.len());
+        for ix in self.topological_order() {
+            let vtx = &self.vertices[&ix];
+            let mut sources: Vec<[u8; 32]> = vtx
+                .get_sources()
+                .into_iter()
+                .filter_map(|s| hashes.get(s).copied())
+                .collect();
+            sources.sort_unstable();
+
+            let mut hasher = Sha256::new();
+            hasher.update(serde_json::to_vec(&ix)?);
+            hasher.update(serde_json::to_vec(&vtx.get_data())?);
+            sources.iter().for_each(|h| hasher.update(h));
+            hashes.insert(ix, hasher.finalize().into());
         }
 
-        Ok(GraphOk::Ok)
+        Ok(hashes)
+    }
+
+    /// Produces a commitment to the entire graph: the SHA-256 of the sorted
+    /// hashes of the leaves (see [`BullDag::vertex_hashes`]). Every vertex
+    /// is an ancestor of at least one leaf, so any change to a vertex's
+    /// data or edges changes the state root, and membership of a vertex
+    /// can be proven by the chain of vertex hashes from it to a leaf.
+    pub fn state_root(&self) -> Result<[u8; 32], serde_json::Error> {
+        use sha2::{Digest, Sha256};
+
+        let hashes = self.vertex_hashes()?;
+        let mut leaves: Vec<[u8; 32]> = self
+            .leaves
+            .iter()
+            .filter_map(|leaf| hashes.get(leaf).copied())
+            .collect();
+        leaves.sort_unstable();
+
+        let mut hasher = Sha256::new();
+        leaves.iter().for_each(|h| hasher.update(h));
+        Ok(hasher.finalize().into())
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Hash,
+    Ix: Index + Debug,
+{
+    /// Produces a hash of the graph that does not depend on the iteration
+    /// order of the underlying maps, so two replicas holding the same
+    /// vertices, data and edges produce the same value. Every vertex and
+    /// edge is hashed on its own with a fresh `H` and the results are
+    /// combined with a commutative sum before being fed into the final
+    /// hasher. Use a hasher with fixed keys (i.e. not `RandomState`) when
+    /// comparing values across processes.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    /// use std::collections::hash_map::DefaultHasher;
+    ///
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+    ///
+    /// let mut g1: BullDag<usize, &str> = BullDag::new();
+    /// g1.extend_from_edges(&[(&v1, &v2), (&v3, &v1)]);
+    /// let mut g2: BullDag<usize, &str> = BullDag::new();
+    /// g2.extend_from_edges(&[(&v3, &v1), (&v1, &v2)]);
+    ///
+    /// assert_eq!(
+    ///     g1.canonical_hash::<DefaultHasher>(),
+    ///     g2.canonical_hash::<DefaultHasher>()
+    /// );
+    /// ```
+    pub fn canonical_hash<H: Hasher + Default>(&self) -> u64 {
+        let vertices = self.vertices.iter().fold(0u64, |acc, (ix, vtx)| {
+            let mut hasher = H::default();
+            ix.hash(&mut hasher);
+            vtx.get_data().hash(&mut hasher);
+            acc.wrapping_add(hasher.finish())
+        });
+
+        let edges = self.edges.iter().fold(0u64, |acc, e| {
+            let mut hasher = H::default();
+            e.hash(&mut hasher);
+            acc.wrapping_add(hasher.finish())
+        });
+
+        let mut hasher = H::default();
+        self.vertices.len().hash(&mut hasher);
+        vertices.hash(&mut hasher);
+        self.edges.len().hash(&mut hasher);
+        edges.hash(&mut hasher);
+        hasher.finish()
     }
 }
@@ -0,0 +1,308 @@
+use crate::edge::Edge;
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::observe::Event;
+use crate::vertex::{Direction, Vertex};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+
+/// The versions during which something was in the graph, from the version
+/// it was added at up to, but not including, the version it was removed at
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    from: u64,
+    until: Option<u64>,
+}
+
+impl Span {
+    fn contains(&self, version: u64) -> bool {
+        self.from <= version && self.until.is_none_or(|until| version < until)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct VertexHistory<T, Ix: Index> {
+    /// Each time the vertex was in the graph, with its data at the time
+    spans: Vec<(Span, T)>,
+    /// Every edge the vertex has ever been part of
+    edges: HashSet<Edge<Ix>>,
+}
+
+/// When every vertex and edge was in a graph with history enabled
+#[derive(Debug, Clone)]
+pub(crate) struct History<T: Clone + Debug, Ix: Index + Debug> {
+    version: u64,
+    vertices: HashMap<Ix, VertexHistory<T, Ix>>,
+    edges: HashMap<Edge<Ix>, Vec<Span>>,
+}
+
+impl<T, Ix> History<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn add_vertex(&mut self, vertex: &Vertex<T, Ix>) {
+        let span = Span {
+            from: self.version,
+            until: None,
+        };
+        self.vertices
+            .entry(vertex.get_index())
+            .or_insert_with(|| VertexHistory {
+                spans: vec![],
+                edges: HashSet::new(),
+            })
+            .spans
+            .push((span, vertex.get_data()));
+    }
+
+    fn add_edge(&mut self, edge: &Edge<Ix>) {
+        for ix in [edge.get_source(), edge.get_reference()] {
+            if let Some(history) = self.vertices.get_mut(&ix) {
+                history.edges.insert(edge.clone());
+            }
+        }
+        self.edges.entry(edge.clone()).or_default().push(Span {
+            from: self.version,
+            until: None,
+        });
+    }
+
+    fn close<'a>(spans: impl Iterator<Item = &'a mut Span>, version: u64) {
+        spans
+            .filter(|span| span.until.is_none())
+            .for_each(|span| span.until = Some(version));
+    }
+
+    /// Records a change to the graph at the current version
+    pub(crate) fn track(&mut self, event: &Event<'_, T, Ix>) {
+        let version = self.version;
+        match event {
+            Event::VertexAdded(vertex) => self.add_vertex(vertex),
+            Event::VertexUpdated { index, data, .. } => {
+                // The vertex is still there, with its edges, but from now
+                // on with the new data
+                if let Some(history) = self.vertices.get_mut(*index) {
+                    History::<T, Ix>::close(history.spans.iter_mut().map(|(s, _)| s), version);
+                    let span = Span {
+                        from: version,
+                        until: None,
+                    };
+                    history.spans.push((span, (*data).clone()));
+                }
+            }
+            Event::EdgeAdded(edge) => self.add_edge(edge),
+            Event::EdgeRemoved(edge) => {
+                if let Some(spans) = self.edges.get_mut(edge) {
+                    History::<T, Ix>::close(spans.iter_mut(), version);
+                }
+            }
+            Event::VertexRemoved(vertex) => {
+                if let Some(history) = self.vertices.get_mut(&vertex.get_index()) {
+                    History::<T, Ix>::close(history.spans.iter_mut().map(|(s, _)| s), version);
+                }
+            }
+            // Only vertices, their data and edges have a history
+            Event::EdgeRejected(..)
+            | Event::EdgeKindSet(..)
+            | Event::EdgeTimeSet(..)
+            | Event::EdgeWeightSet(..)
+            | Event::EdgeKeysSet(..)
+            | Event::MultiEdgesSet(_)
+            | Event::EpochSet(..)
+            | Event::ConflictAdded(..)
+            | Event::ConflictRemoved(..) => {}
+        }
+    }
+}
+
+/// A read-only view of a graph as it was at the end of a version, returned
+/// by [`BullDag::at_version`]. The view is rebuilt from the recorded
+/// history on every query rather than from a stored copy of the graph.
+#[derive(Debug, Clone, Copy)]
+pub struct VersionView<'a, T: Clone + Debug, Ix: Index + Debug> {
+    history: &'a History<T, Ix>,
+    version: u64,
+}
+
+impl<'a, T, Ix> VersionView<'a, T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn edge_exists(&self, edge: &Edge<Ix>) -> bool {
+        self.history
+            .edges
+            .get(edge)
+            .is_some_and(|spans| spans.iter().any(|span| span.contains(self.version)))
+    }
+
+    pub fn contains_vertex(&self, target: &Ix) -> bool {
+        self.history.vertices.get(target).is_some_and(|history| {
+            history
+                .spans
+                .iter()
+                .any(|(span, _)| span.contains(self.version))
+        })
+    }
+
+    /// Returns the vertex as it was at this version, with the data it was
+    /// added with and the edges it had then
+    pub fn get_vertex(&self, target: &Ix) -> Option<Vertex<T, Ix>> {
+        let history = self.history.vertices.get(target)?;
+        let (_, data) = history
+            .spans
+            .iter()
+            .find(|(span, _)| span.contains(self.version))?;
+        let mut vertex = Vertex::new(data.clone(), target.clone());
+        history
+            .edges
+            .iter()
+            .filter(|edge| self.edge_exists(edge))
+            .for_each(|edge| vertex.add_edge(edge));
+        Some(vertex)
+    }
+
+    /// Returns every vertex reachable from `target` in the given direction
+    /// at this version, including `target` itself, or an empty `Vec` if it
+    /// didn't exist then
+    pub fn trace(&self, target: &Ix, direction: Direction) -> Vec<Ix> {
+        let mut visited = HashSet::new();
+        let mut order = vec![];
+        let mut queue = VecDeque::from([target.clone()]);
+        while let Some(ix) = queue.pop_front() {
+            if visited.contains(&ix) {
+                continue;
+            }
+            let Some(vertex) = self.get_vertex(&ix) else {
+                continue;
+            };
+            visited.insert(ix.clone());
+            queue.extend(vertex.neighbors(direction).into_iter().cloned());
+            order.push(ix);
+        }
+        order
+    }
+
+    /// Returns every vertex that existed at this version
+    pub fn vertices(&self) -> Vec<Ix> {
+        self.history
+            .vertices
+            .keys()
+            .filter(|ix| self.contains_vertex(ix))
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.vertices().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn n_edges(&self) -> usize {
+        self.history
+            .edges
+            .keys()
+            .filter(|edge| self.edge_exists(edge))
+            .count()
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Starts recording when each vertex and edge is added and removed, so
+    /// the graph can be queried as it was at an earlier version with
+    /// [`BullDag::at_version`]. Changes are stamped with the current
+    /// version, which only moves forward when [`BullDag::next_version`] or
+    /// [`BullDag::set_version`] is called, e.g. once per block height. The
+    /// graph as it is when history is enabled is recorded at the current
+    /// version. Data changed in place through [`BullDag::get_vertex_mut`]
+    /// is not recorded. Does nothing if history is already enabled.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::{Direction, Vertex};
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// graph.enable_history();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// graph.next_version();
+    /// graph.remove_vertex("source");
+    ///
+    /// let before = graph.at_version(0).unwrap();
+    /// assert!(before.trace(&"reference", Direction::Source).len() == 2);
+    /// assert!(graph.at_version(1).unwrap().get_vertex(&"source").is_none());
+    /// ```
+    pub fn enable_history(&mut self) {
+        if self.history.is_some() {
+            return;
+        }
+        let mut history = History {
+            version: 0,
+            vertices: HashMap::new(),
+            edges: HashMap::new(),
+        };
+        self.vertices
+            .values()
+            .for_each(|vtx| history.add_vertex(vtx));
+        self.edges.iter().for_each(|edge| history.add_edge(edge));
+        self.history = Some(history);
+    }
+
+    /// Stops recording and drops the history
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Returns the version changes are currently recorded at, 0 if history
+    /// is disabled
+    pub fn version(&self) -> u64 {
+        self.history.as_ref().map_or(0, |history| history.version)
+    }
+
+    /// Moves to the next version and returns it, or returns 0 if history
+    /// is disabled
+    pub fn next_version(&mut self) -> u64 {
+        match self.history.as_mut() {
+            Some(history) => {
+                history.version += 1;
+                history.version
+            }
+            None => 0,
+        }
+    }
+
+    /// Moves to `version`, returning false if history is disabled or if it
+    /// would move backwards
+    pub fn set_version(&mut self, version: u64) -> bool {
+        match self.history.as_mut() {
+            Some(history) if version >= history.version => {
+                history.version = version;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns a view of the graph as it was after every change recorded at
+    /// or before `version`, or `None` if history is disabled
+    pub fn at_version(&self, version: u64) -> Option<VersionView<'_, T, Ix>> {
+        self.history
+            .as_ref()
+            .map(|history| VersionView { history, version })
+    }
+}
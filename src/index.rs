@@ -1,19 +1,9 @@
 use std::fmt::Debug;
 use std::hash::Hash;
 
+/// The key vertices are stored under. Any type that can be cloned, hashed
+/// and compared is one, so keys like `Uuid`, `[u8; 20]` or `(u64, u64)`
+/// can be used as they are.
 pub trait Index: Clone + Debug + Hash + PartialEq + Eq {}
 
-impl Index for usize {}
-impl Index for u8 {}
-impl Index for u16 {}
-impl Index for u32 {}
-impl Index for u64 {}
-impl Index for u128 {}
-impl Index for i8 {}
-impl Index for i16 {}
-impl Index for i32 {}
-impl Index for i64 {}
-impl Index for i128 {}
-impl Index for &str {}
-impl Index for String {}
-impl Index for [u8; 32] {}
+impl<T: Clone + Debug + Hash + Eq> Index for T {}
@@ -0,0 +1,536 @@
+use crate::edge::{Edge, EdgeSpec, IntoEdgeSpec};
+use crate::graph::{BullDag, GraphError, GraphResult};
+use crate::index::Index;
+use crate::memory::{table_bytes, MemoryReport};
+use crate::vertex::{Direction, Vertex};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::mem::size_of;
+
+/// A vertex of an [`InternedBullDag`], with its neighbors given by id
+#[derive(Debug, Clone)]
+struct Slot<T, Ix> {
+    index: Ix,
+    data: T,
+    sources: Vec<u32>,
+    references: Vec<u32>,
+}
+
+impl<T, Ix> Slot<T, Ix> {
+    fn neighbors(&self, direction: Direction) -> &[u32] {
+        match direction {
+            Direction::Source => &self.sources,
+            Direction::Reference => &self.references,
+        }
+    }
+}
+
+/// Adds `id` to a sorted list, returning false if it was already there
+fn insert_sorted(list: &mut Vec<u32>, id: u32) -> bool {
+    match list.binary_search(&id) {
+        Ok(_) => false,
+        Err(at) => {
+            list.insert(at, id);
+            true
+        }
+    }
+}
+
+fn remove_sorted(list: &mut Vec<u32>, id: u32) -> bool {
+    match list.binary_search(&id) {
+        Ok(at) => {
+            list.remove(at);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// A graph with the same index based API as [`BullDag`] that stores each
+/// index only once. Every vertex is given a `u32` id when it is added, and
+/// the sources and references of a vertex are kept as sorted lists of ids
+/// rather than sets of indices, with no separate set of edges. For indices
+/// like 32 byte hashes the adjacency then takes an eighth of the space, and
+/// the graph as a whole several times less than a [`BullDag`].
+///
+/// Lookups by index go through one hash map to find the id, so they cost
+/// about the same as in a [`BullDag`], while checking for an edge is a
+/// binary search of the source's references. Roots and leaves aren't
+/// tracked and are found by going over every vertex.
+///
+/// The vertices themselves live in a slab, a `Vec` that an id is the
+/// position in, so traversals that go from id to id, like
+/// [`InternedBullDag::cumulative_weights`], stay in one contiguous block of
+/// memory instead of hopping between hash map buckets. The ids of removed
+/// vertices are reused, and [`InternedBullDag::compact`] lays the slab out
+/// in topological order.
+///
+/// Example
+///
+/// ```
+/// use bulldag::interned::InternedBullDag;
+/// use bulldag::vertex::Vertex;
+///
+/// let mut graph: InternedBullDag<usize, [u8; 32]> = InternedBullDag::new();
+/// let v1: Vertex<usize, [u8; 32]> = Vertex::new(5, [1; 32]);
+/// let v2: Vertex<usize, [u8; 32]> = Vertex::new(4, [2; 32]);
+/// let v3: Vertex<usize, [u8; 32]> = Vertex::new(3, [3; 32]);
+/// graph.add_edge((&v1, &v2)).unwrap();
+/// graph.add_edge((&v2, &v3)).unwrap();
+///
+/// assert!(graph.len() == 3 && graph.n_edges() == 2);
+/// assert!(graph.is_reachable(&[1; 32], &[3; 32]));
+/// assert!(graph.add_edge(([3; 32], [1; 32])).is_err());
+/// assert!(graph.to_dag().has_edge(&[2; 32], &[3; 32]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct InternedBullDag<T: Clone + Debug, Ix: Index + Debug> {
+    ids: HashMap<Ix, u32>,
+    slots: Vec<Option<Slot<T, Ix>>>,
+    free: Vec<u32>,
+    n_edges: usize,
+}
+
+impl<T, Ix> Default for InternedBullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, Ix> InternedBullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    pub fn new() -> InternedBullDag<T, Ix> {
+        InternedBullDag::with_capacity(0)
+    }
+
+    /// Creates an empty graph with room for at least `n_vertices` vertices
+    pub fn with_capacity(n_vertices: usize) -> InternedBullDag<T, Ix> {
+        InternedBullDag {
+            ids: HashMap::with_capacity(n_vertices),
+            slots: Vec::with_capacity(n_vertices),
+            free: vec![],
+            n_edges: 0,
+        }
+    }
+
+    fn slot(&self, id: u32) -> &Slot<T, Ix> {
+        self.slots[id as usize]
+            .as_ref()
+            .expect("ids only refer to vertices in the graph")
+    }
+
+    fn slot_mut(&mut self, id: u32) -> &mut Slot<T, Ix> {
+        self.slots[id as usize]
+            .as_mut()
+            .expect("ids only refer to vertices in the graph")
+    }
+
+    /// Returns the id of the vertex, adding it with no edges if it isn't in
+    /// the graph yet, or `None` if every id is taken
+    fn intern(&mut self, vertex: &Vertex<T, Ix>) -> Option<u32> {
+        let ix = vertex.get_index();
+        if let Some(id) = self.ids.get(&ix) {
+            return Some(*id);
+        }
+        let slot = Some(Slot {
+            index: ix.clone(),
+            data: vertex.get_data(),
+            sources: vec![],
+            references: vec![],
+        });
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.slots[id as usize] = slot;
+                id
+            }
+            None => {
+                let id = u32::try_from(self.slots.len()).ok()?;
+                self.slots.push(slot);
+                id
+            }
+        };
+        self.ids.insert(ix, id);
+        Some(id)
+    }
+
+    /// Adds a vertex with no edges. If a vertex with the same index is
+    /// already in the graph only its data is replaced, its edges are kept.
+    /// Any sources or references the vertex has are ignored.
+    ///
+    /// Panics if the graph already has `u32::MAX + 1` vertices.
+    pub fn add_vertex(&mut self, vertex: &Vertex<T, Ix>) {
+        match self.ids.get(&vertex.get_index()) {
+            Some(id) => self.slot_mut(*id).data = vertex.get_data(),
+            None => {
+                self.intern(vertex).expect("too many vertices to intern");
+            }
+        }
+    }
+
+    /// Adds an edge, along with either of its vertices that isn't in the
+    /// graph yet when it is given by vertices. Fails with
+    /// `GraphError::WouldCycle` if the edge would create a cycle, with
+    /// `NonExistentSource` or `NonExistentReference` if it is given by
+    /// index and either vertex is missing, and with `GraphError::Other` if
+    /// every id is taken.
+    pub fn add_edge(&mut self, edge: impl IntoEdgeSpec<T, Ix>) -> GraphResult {
+        let (source, reference) = match edge.into_edge_spec() {
+            EdgeSpec::Vertices(source, reference) => {
+                if source.get_index() == reference.get_index() {
+                    return Err(GraphError::WouldCycle);
+                }
+                let too_many = || GraphError::other("too many vertices to intern");
+                (
+                    self.intern(&source).ok_or_else(too_many)?,
+                    self.intern(&reference).ok_or_else(too_many)?,
+                )
+            }
+            EdgeSpec::Indices(source, reference) => (
+                *self.ids.get(&source).ok_or(GraphError::NonExistentSource)?,
+                *self
+                    .ids
+                    .get(&reference)
+                    .ok_or(GraphError::NonExistentReference)?,
+            ),
+        };
+        if self
+            .slot(source)
+            .references
+            .binary_search(&reference)
+            .is_ok()
+        {
+            return Ok(());
+        }
+        if self.reaches(reference, source) {
+            return Err(GraphError::WouldCycle);
+        }
+
+        insert_sorted(&mut self.slot_mut(source).references, reference);
+        insert_sorted(&mut self.slot_mut(reference).sources, source);
+        self.n_edges += 1;
+        Ok(())
+    }
+
+    /// Removes an edge, returning false if it wasn't in the graph
+    pub fn remove_edge(&mut self, edge: &Edge<Ix>) -> bool {
+        let (Some(source), Some(reference)) = (
+            self.ids.get(&edge.get_source()).copied(),
+            self.ids.get(&edge.get_reference()).copied(),
+        ) else {
+            return false;
+        };
+        if !remove_sorted(&mut self.slot_mut(source).references, reference) {
+            return false;
+        }
+        remove_sorted(&mut self.slot_mut(reference).sources, source);
+        self.n_edges -= 1;
+        true
+    }
+
+    /// Removes a vertex and every edge it is part of from the graph,
+    /// returning the removed vertex if it existed
+    pub fn remove_vertex(&mut self, target: Ix) -> Option<Vertex<T, Ix>> {
+        let vertex = self.get_vertex(&target)?;
+        let id = self.ids.remove(&target)?;
+        let slot = self.slots[id as usize].take()?;
+        for s in &slot.sources {
+            remove_sorted(&mut self.slot_mut(*s).references, id);
+        }
+        for r in &slot.references {
+            remove_sorted(&mut self.slot_mut(*r).sources, id);
+        }
+        self.n_edges -= slot.sources.len() + slot.references.len();
+        self.free.push(id);
+        Some(vertex)
+    }
+
+    /// Returns the vertex at `target`, with its sources and references
+    /// looked up from their ids
+    pub fn get_vertex(&self, target: &Ix) -> Option<Vertex<T, Ix>> {
+        let slot = self.slot(*self.ids.get(target)?);
+        let mut vertex = Vertex::new(slot.data.clone(), slot.index.clone());
+        for s in &slot.sources {
+            vertex.add_edge(&Edge::new(self.slot(*s).index.clone(), target.clone()));
+        }
+        for r in &slot.references {
+            vertex.add_edge(&Edge::new(target.clone(), self.slot(*r).index.clone()));
+        }
+        Some(vertex)
+    }
+
+    pub fn get_data(&self, target: &Ix) -> Option<&T> {
+        self.ids.get(target).map(|id| &self.slot(*id).data)
+    }
+
+    pub fn get_data_mut(&mut self, target: &Ix) -> Option<&mut T> {
+        let id = *self.ids.get(target)?;
+        Some(&mut self.slot_mut(id).data)
+    }
+
+    pub fn contains_vertex(&self, target: &Ix) -> bool {
+        self.ids.contains_key(target)
+    }
+
+    pub fn has_edge(&self, source: &Ix, reference: &Ix) -> bool {
+        match (self.ids.get(source), self.ids.get(reference)) {
+            (Some(source), Some(reference)) => self
+                .slot(*source)
+                .references
+                .binary_search(reference)
+                .is_ok(),
+            _ => false,
+        }
+    }
+
+    /// Returns the sources or references of the vertex at `ix`, empty if
+    /// there is no such vertex
+    pub fn neighbors(&self, ix: &Ix, direction: Direction) -> Vec<&Ix> {
+        self.ids.get(ix).map_or(vec![], |id| {
+            self.slot(*id)
+                .neighbors(direction)
+                .iter()
+                .map(|n| &self.slot(*n).index)
+                .collect()
+        })
+    }
+
+    pub fn in_degree(&self, ix: &Ix) -> Option<usize> {
+        self.ids.get(ix).map(|id| self.slot(*id).sources.len())
+    }
+
+    pub fn out_degree(&self, ix: &Ix) -> Option<usize> {
+        self.ids.get(ix).map(|id| self.slot(*id).references.len())
+    }
+
+    fn reaches(&self, from: u32, to: u32) -> bool {
+        let mut visited: HashSet<u32> = HashSet::new();
+        let mut stack: Vec<u32> = vec![from];
+        while let Some(id) = stack.pop() {
+            if id == to {
+                return true;
+            }
+            if visited.insert(id) {
+                stack.extend(&self.slot(id).references);
+            }
+        }
+        false
+    }
+
+    /// Whether `to` can be reached from `from` by following references. A
+    /// vertex is reachable from itself.
+    pub fn is_reachable(&self, from: &Ix, to: &Ix) -> bool {
+        match (self.ids.get(from), self.ids.get(to)) {
+            (Some(from), Some(to)) => self.reaches(*from, *to),
+            _ => false,
+        }
+    }
+
+    fn indices_where(&self, f: impl Fn(&Slot<T, Ix>) -> bool) -> HashSet<Ix> {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|slot| f(slot))
+            .map(|slot| slot.index.clone())
+            .collect()
+    }
+
+    pub fn get_roots(&self) -> HashSet<Ix> {
+        self.indices_where(|slot| slot.sources.is_empty())
+    }
+
+    pub fn get_leaves(&self) -> HashSet<Ix> {
+        self.indices_where(|slot| slot.references.is_empty())
+    }
+
+    /// Kahn's algorithm over the ids
+    fn topological_ids(&self) -> Vec<u32> {
+        let mut in_degree: Vec<usize> = self
+            .slots
+            .iter()
+            .map(|slot| slot.as_ref().map_or(0, |slot| slot.sources.len()))
+            .collect();
+        let mut ready: VecDeque<u32> = (0..self.slots.len() as u32)
+            .filter(|id| matches!(&self.slots[*id as usize], Some(slot) if slot.sources.is_empty()))
+            .collect();
+        let mut order = Vec::with_capacity(self.len());
+        while let Some(id) = ready.pop_front() {
+            order.push(id);
+            for r in &self.slot(id).references {
+                in_degree[*r as usize] -= 1;
+                if in_degree[*r as usize] == 0 {
+                    ready.push_back(*r);
+                }
+            }
+        }
+        order
+    }
+
+    /// Returns every vertex, each after all of its sources
+    pub fn topological_order(&self) -> Vec<Ix> {
+        self.topological_ids()
+            .into_iter()
+            .map(|id| self.slot(id).index.clone())
+            .collect()
+    }
+
+    /// Moves the vertices to the front of the slab in topological order,
+    /// dropping the gaps removed vertices left, so that a traversal from
+    /// the roots to the leaves mostly walks the slab from front to back.
+    /// Changes the id of every vertex.
+    pub fn compact(&mut self) {
+        let order = self.topological_ids();
+        let mut new_ids: Vec<u32> = vec![u32::MAX; self.slots.len()];
+        for (new, old) in order.iter().enumerate() {
+            new_ids[*old as usize] = new as u32;
+        }
+        let mut slots: Vec<Option<Slot<T, Ix>>> = Vec::with_capacity(order.len());
+        for old in order {
+            let mut slot = self.slots[old as usize]
+                .take()
+                .expect("ids only refer to vertices in the graph");
+            for list in [&mut slot.sources, &mut slot.references] {
+                list.iter_mut().for_each(|id| *id = new_ids[*id as usize]);
+                list.sort_unstable();
+            }
+            self.ids.insert(slot.index.clone(), new_ids[old as usize]);
+            slots.push(Some(slot));
+        }
+        self.slots = slots;
+        self.free.clear();
+    }
+
+    /// Returns the id of the vertex at `ix`, its position in the slab
+    pub fn id_of(&self, ix: &Ix) -> Option<u32> {
+        self.ids.get(ix).copied()
+    }
+
+    /// Returns the index of the vertex with the id `id`
+    pub fn index_at(&self, id: u32) -> Option<&Ix> {
+        self.slots
+            .get(id as usize)?
+            .as_ref()
+            .map(|slot| &slot.index)
+    }
+
+    pub fn data_at(&self, id: u32) -> Option<&T> {
+        self.slots.get(id as usize)?.as_ref().map(|slot| &slot.data)
+    }
+
+    /// The ids of a vertex's sources or references, in increasing order
+    pub fn neighbors_at(&self, id: u32, direction: Direction) -> &[u32] {
+        match self.slots.get(id as usize) {
+            Some(Some(slot)) => slot.neighbors(direction),
+            _ => &[],
+        }
+    }
+
+    /// Computes the cumulative weight of every vertex, one for the vertex
+    /// itself plus the number of vertices that reference it directly or
+    /// indirectly, like [`BullDag::cumulative_weights`] but with the sets
+    /// of referencing vertices kept as sets of ids
+    pub fn cumulative_weights(&self) -> HashMap<Ix, u64> {
+        let mut referenced_by: Vec<HashSet<u32>> = vec![HashSet::new(); self.slots.len()];
+        let mut weights = HashMap::with_capacity(self.len());
+        for id in self.topological_ids().into_iter().rev() {
+            let slot = self.slot(id);
+            let mut set: HashSet<u32> = HashSet::new();
+            for r in &slot.references {
+                set.insert(*r);
+                set.extend(&referenced_by[*r as usize]);
+            }
+            weights.insert(slot.index.clone(), set.len() as u64 + 1);
+            referenced_by[id as usize] = set;
+        }
+        weights
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn n_edges(&self) -> usize {
+        self.n_edges
+    }
+
+    /// Estimates the memory the graph uses, counted the same way as
+    /// [`BullDag::memory_usage`]. The map from indices to ids is counted
+    /// under `vertices`, along with the data.
+    pub fn memory_usage(&self) -> MemoryReport {
+        MemoryReport {
+            vertices: table_bytes::<(Ix, u32)>(self.ids.capacity())
+                + self.slots.capacity() * size_of::<Option<Slot<T, Ix>>>(),
+            adjacency: self
+                .slots
+                .iter()
+                .flatten()
+                .map(|slot| (slot.sources.capacity() + slot.references.capacity()) * 4)
+                .sum(),
+            edges: 0,
+            bookkeeping: self.free.capacity() * 4,
+            caches: 0,
+        }
+    }
+
+    /// Copies the graph into a [`BullDag`]
+    pub fn to_dag(&self) -> BullDag<T, Ix> {
+        let mut graph = BullDag::with_capacity(self.len(), self.n_edges);
+        for slot in self.slots.iter().flatten() {
+            let vertex = self
+                .get_vertex(&slot.index)
+                .expect("every slot is in the graph");
+            if slot.sources.is_empty() {
+                graph.roots.insert(slot.index.clone());
+            }
+            if slot.references.is_empty() {
+                graph.leaves.insert(slot.index.clone());
+            }
+            for r in &slot.references {
+                graph
+                    .edges
+                    .insert(Edge::new(slot.index.clone(), self.slot(*r).index.clone()));
+            }
+            graph.vertices.insert(slot.index.clone(), vertex);
+        }
+        graph
+    }
+}
+
+impl<T, Ix> From<&BullDag<T, Ix>> for InternedBullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Interns the vertices of the graph, leaving out its epochs and
+    /// conflicts.
+    ///
+    /// Panics if the graph has more than `u32::MAX + 1` vertices.
+    fn from(graph: &BullDag<T, Ix>) -> Self {
+        let mut interned = InternedBullDag::with_capacity(graph.len());
+        for vtx in graph.vertices.values() {
+            interned.add_vertex(vtx);
+        }
+        for e in &graph.edges {
+            let source = interned.ids[&e.get_source()];
+            let reference = interned.ids[&e.get_reference()];
+            interned.slot_mut(source).references.push(reference);
+            interned.slot_mut(reference).sources.push(source);
+        }
+        for slot in interned.slots.iter_mut().flatten() {
+            slot.sources.sort_unstable();
+            slot.references.sort_unstable();
+        }
+        interned.n_edges = graph.n_edges();
+        interned
+    }
+}
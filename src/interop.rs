@@ -0,0 +1,670 @@
+use crate::builder::{BuildError, BullDagBuilder};
+use crate::graph::{BullDag, GraphError};
+use crate::index::Index;
+use crate::vertex::Vertex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "binary")]
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::str::FromStr;
+
+/// A vertex in the [`AdjacencyList`] format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdjacencyVertex<T, Ix> {
+    pub index: Ix,
+    pub data: T,
+}
+
+/// A compact, stable representation of a graph as a list of vertices and
+/// a list of `[source, reference]` index pairs. Unlike the serde derive on
+/// [`BullDag`] it doesn't repeat each edge in the vertices' sources and
+/// references or include the derived roots and leaves, so it is smaller and
+/// easy to produce and consume outside of Rust:
+///
+/// ```json
+/// {
+///   "vertices": [{ "index": "a", "data": 1 }, { "index": "b", "data": 2 }],
+///   "edges": [["a", "b"]]
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdjacencyList<T, Ix> {
+    pub vertices: Vec<AdjacencyVertex<T, Ix>>,
+    pub edges: Vec<(Ix, Ix)>,
+}
+
+impl<T, Ix> From<&BullDag<T, Ix>> for AdjacencyList<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn from(graph: &BullDag<T, Ix>) -> Self {
+        AdjacencyList {
+            vertices: graph
+                .vertices
+                .values()
+                .map(|vtx| AdjacencyVertex {
+                    index: vtx.get_index(),
+                    data: vtx.get_data(),
+                })
+                .collect(),
+            edges: graph
+                .edges
+                .iter()
+                .map(|e| (e.get_source(), e.get_reference()))
+                .collect(),
+        }
+    }
+}
+
+impl<T, Ix> TryFrom<AdjacencyList<T, Ix>> for BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    type Error = GraphError;
+
+    /// Rebuilds the graph with [`BullDagBuilder`], so the edges are checked
+    /// for cycles once, in bulk. Fails if an edge refers to a vertex that
+    /// is not in the list or if the edges contain a cycle.
+    fn try_from(list: AdjacencyList<T, Ix>) -> Result<Self, Self::Error> {
+        let mut builder = BullDagBuilder::new();
+        for v in list.vertices {
+            builder.add_vertex(Vertex::new(v.data, v.index));
+        }
+        for (source, reference) in list.edges {
+            builder.add_edge(source, reference);
+        }
+
+        builder.build().map_err(first_build_error)
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize,
+    Ix: Index + Debug + Serialize,
+{
+    /// Serializes the graph in the [`AdjacencyList`] format
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// let json = graph.to_adjacency_json().unwrap();
+    /// assert!(json.contains(r#""edges":[["source","reference"]]"#));
+    ///
+    /// let parsed: BullDag<usize, &str> = BullDag::from_adjacency_json(&json).unwrap();
+    /// assert!(parsed == graph);
+    /// ```
+    pub fn to_adjacency_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&AdjacencyList::from(self))
+    }
+}
+
+impl<'a, T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Deserialize<'a>,
+    Ix: Index + Debug + Deserialize<'a>,
+{
+    /// Parses a graph in the [`AdjacencyList`] format, failing if the JSON
+    /// is malformed, an edge refers to a missing vertex, or the edges
+    /// contain a cycle
+    pub fn from_adjacency_json(json: &'a str) -> Result<BullDag<T, Ix>, GraphError> {
+        let list: AdjacencyList<T, Ix> = serde_json::from_str(json).map_err(GraphError::other)?;
+        BullDag::try_from(list)
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Default,
+    Ix: Index + Debug + FromStr + Display,
+    <Ix as FromStr>::Err: Display,
+{
+    /// Reads a graph from a two column `source,reference` CSV edge list,
+    /// creating every vertex that appears in it with `T::default()` data.
+    /// Fields are trimmed and blank lines are skipped; there is no header
+    /// row and no quoting, so indices must not contain commas. The edges
+    /// are checked for cycles once, in bulk, with [`BullDagBuilder`]. Fails
+    /// on I/O errors, malformed rows, and edges that would form a cycle.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    ///
+    /// let csv = "1,2\n1,3\n2,3\n";
+    /// let graph: BullDag<(), u64> = BullDag::from_edge_csv(csv.as_bytes()).unwrap();
+    /// assert!(graph.len() == 3);
+    /// assert!(graph.n_edges() == 3);
+    ///
+    /// let mut out = vec![];
+    /// graph.to_edge_csv(&mut out).unwrap();
+    /// assert_eq!(String::from_utf8(out).unwrap(), csv);
+    /// ```
+    pub fn from_edge_csv<R: Read>(reader: R) -> Result<BullDag<T, Ix>, GraphError> {
+        let mut builder = BullDagBuilder::new();
+        for (n, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line.map_err(GraphError::other)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let parse = |field: Option<&str>| -> Result<Ix, GraphError> {
+                let field = field.map(str::trim).unwrap_or_default();
+                field
+                    .parse()
+                    .map_err(|e| GraphError::other(format!("line {}: {:?}: {}", n + 1, field, e)))
+            };
+            let mut fields = line.split(',');
+            let source = parse(fields.next())?;
+            let reference = parse(fields.next())?;
+            if fields.next().is_some() {
+                return Err(GraphError::other(format!(
+                    "line {}: expected two columns",
+                    n + 1
+                )));
+            }
+
+            builder.add_vertex(Vertex::new(T::default(), source.clone()));
+            builder.add_vertex(Vertex::new(T::default(), reference.clone()));
+            builder.add_edge(source, reference);
+        }
+
+        builder.build().map_err(first_build_error)
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Display,
+{
+    /// Writes the edges as a two column `source,reference` CSV edge list,
+    /// sorted so the output is stable. Vertex data, and vertices without
+    /// any edges, are not written; see [`BullDag::from_edge_csv`].
+    pub fn to_edge_csv<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let mut rows: Vec<String> = self
+            .edges
+            .iter()
+            .map(|e| format!("{},{}", e.get_source(), e.get_reference()))
+            .collect();
+        rows.sort();
+        for row in rows {
+            writeln!(writer, "{}", row)?;
+        }
+
+        writer.flush()
+    }
+}
+
+/// Converts the errors from a [`BullDagBuilder`] to the first one's
+/// equivalent [`GraphError`]
+fn first_build_error<Ix: Index + Debug>(errors: Vec<BuildError<Ix>>) -> GraphError {
+    match errors.first() {
+        Some(BuildError::WouldCycle(_)) => GraphError::WouldCycle,
+        Some(BuildError::NonExistentSource(_)) => GraphError::NonExistentSource,
+        Some(BuildError::NonExistentReference(_)) => GraphError::NonExistentReference,
+        None => GraphError::other("invalid graph"),
+    }
+}
+
+/// A single record of the [`BullDag::serialize_stream`] format
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StreamRecord<T, Ix> {
+    Vertex(AdjacencyVertex<T, Ix>),
+    Edge(Ix, Ix),
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize,
+    Ix: Index + Debug + Serialize,
+{
+    /// Writes the graph as newline delimited JSON, one record per vertex
+    /// and then one per edge, without building the whole serialized graph
+    /// in memory first:
+    ///
+    /// ```json
+    /// {"vertex":{"index":"a","data":1}}
+    /// {"vertex":{"index":"b","data":2}}
+    /// {"edge":["a","b"]}
+    /// ```
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, String> = BullDag::new();
+    /// let v1: Vertex<usize, String> = Vertex::new(5, "source".to_string());
+    /// let v2: Vertex<usize, String> = Vertex::new(4, "reference".to_string());
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// let mut buf = vec![];
+    /// graph.serialize_stream(&mut buf).unwrap();
+    /// let read: BullDag<usize, String> = BullDag::deserialize_stream(buf.as_slice()).unwrap();
+    /// assert!(read == graph);
+    /// ```
+    pub fn serialize_stream<W: Write>(&self, writer: W) -> Result<(), serde_json::Error> {
+        let mut writer = BufWriter::new(writer);
+        for vtx in self.vertices.values() {
+            let record: StreamRecord<T, Ix> = StreamRecord::Vertex(AdjacencyVertex {
+                index: vtx.get_index(),
+                data: vtx.get_data(),
+            });
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+        }
+        for e in self.edges.iter() {
+            let record: StreamRecord<T, Ix> = StreamRecord::Edge(e.get_source(), e.get_reference());
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+        }
+
+        writer.flush().map_err(serde_json::Error::io)
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + DeserializeOwned,
+    Ix: Index + Debug + DeserializeOwned,
+{
+    /// Reads a graph written by [`BullDag::serialize_stream`] one record at
+    /// a time. The edges are checked for cycles once, in bulk, with
+    /// [`BullDagBuilder`] rather than on every insert. Fails if a record is
+    /// malformed, an edge refers to a missing vertex, or the edges contain
+    /// a cycle.
+    pub fn deserialize_stream<R: Read>(reader: R) -> Result<BullDag<T, Ix>, GraphError> {
+        let mut builder = BullDagBuilder::new();
+        let records = serde_json::Deserializer::from_reader(BufReader::new(reader))
+            .into_iter::<StreamRecord<T, Ix>>();
+        for record in records {
+            match record.map_err(GraphError::other)? {
+                StreamRecord::Vertex(v) => builder.add_vertex(Vertex::new(v.data, v.index)),
+                StreamRecord::Edge(source, reference) => builder.add_edge(source, reference),
+            };
+        }
+
+        builder.build().map_err(first_build_error)
+    }
+}
+
+/// Converts an index to the string used to identify it in text formats.
+/// String indices are used as they are so that the output is readable in
+/// other tools, anything else is written as JSON.
+#[cfg(feature = "graphml")]
+fn index_to_id<Ix: Serialize>(ix: &Ix) -> Result<String, serde_json::Error> {
+    match serde_json::to_value(ix)? {
+        serde_json::Value::String(s) => Ok(s),
+        value => Ok(value.to_string()),
+    }
+}
+
+/// The inverse of `index_to_id`, tries the id as a plain string first and
+/// then as JSON.
+#[cfg(feature = "graphml")]
+fn id_to_index<Ix: DeserializeOwned>(id: &str) -> Result<Ix, GraphError> {
+    serde_json::from_value(serde_json::Value::String(id.to_string()))
+        .or_else(|_| serde_json::from_str(id))
+        .map_err(|e| GraphError::other(format!("invalid index {:?}: {}", id, e)))
+}
+
+#[cfg(feature = "graphml")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize,
+    Ix: Index + Debug + Serialize,
+{
+    /// Exports the graph as GraphML, e.g. for Gephi or yEd. Every vertex
+    /// becomes a node with its data stored as JSON in the `data` attribute,
+    /// and every edge a directed edge from source to reference. Nodes and
+    /// edges are sorted by id so the output is stable.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, String> = BullDag::new();
+    /// let v1: Vertex<usize, String> = Vertex::new(5, "source".to_string());
+    /// let v2: Vertex<usize, String> = Vertex::new(4, "reference".to_string());
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// let graphml = graph.to_graphml().unwrap();
+    /// assert!(graphml.contains(r#"<edge source="source" target="reference"/>"#));
+    ///
+    /// let imported: BullDag<usize, String> = BullDag::from_graphml(&graphml).unwrap();
+    /// assert!(imported == graph);
+    /// ```
+    pub fn to_graphml(&self) -> Result<String, serde_json::Error> {
+        use quick_xml::escape::escape;
+
+        let mut nodes = Vec::with_capacity(self.vertices.len());
+        for (ix, vtx) in self.vertices.iter() {
+            let data = serde_json::to_string(&vtx.get_data())?;
+            nodes.push((index_to_id(ix)?, data));
+        }
+        nodes.sort();
+
+        let mut edges = Vec::with_capacity(self.edges.len());
+        for e in self.edges.iter() {
+            edges.push((
+                index_to_id(&e.get_source())?,
+                index_to_id(&e.get_reference())?,
+            ));
+        }
+        edges.sort();
+
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"data\" for=\"node\" attr.name=\"data\" attr.type=\"string\"/>\n",
+            "  <graph id=\"G\" edgedefault=\"directed\">\n",
+        ));
+        for (id, data) in nodes {
+            out.push_str(&format!(
+                "    <node id=\"{}\"><data key=\"data\">{}</data></node>\n",
+                escape(id.as_str()),
+                escape(data.as_str())
+            ));
+        }
+        for (source, target) in edges {
+            out.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\"/>\n",
+                escape(source.as_str()),
+                escape(target.as_str())
+            ));
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+
+        Ok(out)
+    }
+}
+
+#[cfg(feature = "graphml")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + DeserializeOwned,
+    Ix: Index + Debug + DeserializeOwned,
+{
+    /// Imports a graph from GraphML. Every node must carry its data as JSON
+    /// in a `data` attribute (see [`BullDag::to_graphml`]). The edges are
+    /// checked for cycles once, in bulk, with [`BullDagBuilder`]. Fails if
+    /// the document is malformed, an edge refers to an unknown node, or the
+    /// edges contain a cycle.
+    pub fn from_graphml(graphml: &str) -> Result<BullDag<T, Ix>, GraphError> {
+        use quick_xml::events::{BytesStart, Event};
+        use quick_xml::Reader;
+
+        let attribute = |e: &BytesStart, name: &[u8]| -> Result<Option<String>, GraphError> {
+            for attr in e.attributes() {
+                let attr = attr.map_err(GraphError::other)?;
+                if attr.key.as_ref() == name {
+                    return Ok(Some(
+                        attr.unescape_value()
+                            .map_err(GraphError::other)?
+                            .into_owned(),
+                    ));
+                }
+            }
+            Ok(None)
+        };
+
+        // The data key id is looked up rather than assumed, so files that
+        // were edited in other tools still import.
+        let mut data_key = String::from("data");
+        let mut nodes: Vec<(String, Option<String>)> = vec![];
+        let mut edges: Vec<(String, String)> = vec![];
+        let mut in_data = false;
+
+        let mut reader = Reader::from_str(graphml);
+        loop {
+            match reader.read_event().map_err(GraphError::other)? {
+                Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                    b"key" if attribute(&e, b"attr.name")?.as_deref() == Some("data") => {
+                        if let Some(id) = attribute(&e, b"id")? {
+                            data_key = id;
+                        }
+                    }
+                    b"node" => {
+                        let id = attribute(&e, b"id")?
+                            .ok_or_else(|| GraphError::other("node without an id"))?;
+                        nodes.push((id, None));
+                    }
+                    b"data" => {
+                        in_data = attribute(&e, b"key")?.as_deref() == Some(data_key.as_str());
+                    }
+                    b"edge" => {
+                        let source =
+                            attribute(&e, b"source")?.ok_or(GraphError::NonExistentSource)?;
+                        let target =
+                            attribute(&e, b"target")?.ok_or(GraphError::NonExistentReference)?;
+                        edges.push((source, target));
+                    }
+                    _ => {}
+                },
+                Event::Text(t) if in_data => {
+                    if let Some((_, data)) = nodes.last_mut() {
+                        let text = t.unescape().map_err(GraphError::other)?;
+                        data.get_or_insert_with(String::new).push_str(&text);
+                    }
+                }
+                Event::End(e) if e.name().as_ref() == b"data" => in_data = false,
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        let mut builder = BullDagBuilder::new();
+        for (id, data) in nodes {
+            let data =
+                data.ok_or_else(|| GraphError::other(format!("node {:?} has no data", id)))?;
+            let data: T = serde_json::from_str(&data)
+                .map_err(|e| GraphError::other(format!("invalid data for node {:?}: {}", id, e)))?;
+            builder.add_vertex(Vertex::new(data, id_to_index(&id)?));
+        }
+        for (source, target) in edges {
+            builder.add_edge(id_to_index(&source)?, id_to_index(&target)?);
+        }
+
+        builder.build().map_err(first_build_error)
+    }
+}
+
+/// Identifies the [`BullDag::to_bytes`] format and its version
+#[cfg(feature = "binary")]
+const BINARY_MAGIC: &[u8; 5] = b"BDAG\x01";
+
+/// The body of the binary format: a table of vertices, and the edges as
+/// pairs of positions in that table so that each index is only encoded once
+#[cfg(feature = "binary")]
+#[derive(Serialize, Deserialize)]
+struct BinaryGraph<T, Ix> {
+    vertices: Vec<(Ix, T)>,
+    edges: Vec<(u32, u32)>,
+}
+
+#[cfg(feature = "binary")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize,
+    Ix: Index + Debug + Serialize,
+{
+    /// Encodes the graph in a compact binary format: a short header, the
+    /// vertex table, then every edge as a pair of `u32` positions in the
+    /// table. The roots, leaves and each vertex's sources and references
+    /// are derived again on decoding rather than stored, which makes this
+    /// much smaller than the serde derive on [`BullDag`] for large graphs.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// let bytes = graph.to_bytes().unwrap();
+    /// let decoded: BullDag<usize, &str> = BullDag::from_bytes(&bytes).unwrap();
+    /// assert!(decoded == graph);
+    /// ```
+    pub fn to_bytes(&self) -> bincode::Result<Vec<u8>> {
+        if self.vertices.len() > u32::MAX as usize {
+            return Err(Box::new(bincode::ErrorKind::Custom(
+                "too many vertices for the binary format".to_string(),
+            )));
+        }
+
+        let mut positions: HashMap<&Ix, u32> = HashMap::with_capacity(self.vertices.len());
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+        for (n, (ix, vtx)) in self.vertices.iter().enumerate() {
+            positions.insert(ix, n as u32);
+            vertices.push((ix.clone(), vtx.get_data()));
+        }
+        let edges = self
+            .edges
+            .iter()
+            .map(|e| (positions[&e.get_source()], positions[&e.get_reference()]))
+            .collect();
+
+        let mut out = BINARY_MAGIC.to_vec();
+        bincode::serialize_into(&mut out, &BinaryGraph { vertices, edges })?;
+
+        Ok(out)
+    }
+}
+
+#[cfg(feature = "binary")]
+impl<'a, T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Deserialize<'a>,
+    Ix: Index + Debug + Deserialize<'a>,
+{
+    /// Decodes a graph written by [`BullDag::to_bytes`]. The edges are
+    /// checked for cycles once, in bulk, with [`BullDagBuilder`]. Fails if
+    /// the header or body is malformed, an edge points outside the vertex
+    /// table, or the edges contain a cycle.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<BullDag<T, Ix>, GraphError> {
+        let body = bytes
+            .strip_prefix(BINARY_MAGIC.as_slice())
+            .ok_or_else(|| GraphError::other("not a bulldag binary graph"))?;
+        let graph: BinaryGraph<T, Ix> = bincode::deserialize(body).map_err(GraphError::other)?;
+
+        let mut builder = BullDagBuilder::new();
+        let mut table = Vec::with_capacity(graph.vertices.len());
+        for (ix, data) in graph.vertices {
+            table.push(ix.clone());
+            builder.add_vertex(Vertex::new(data, ix));
+        }
+        for (source, reference) in graph.edges {
+            let (source, reference) =
+                match (table.get(source as usize), table.get(reference as usize)) {
+                    (Some(source), Some(reference)) => (source, reference),
+                    (None, _) => return Err(GraphError::NonExistentSource),
+                    (_, None) => return Err(GraphError::NonExistentReference),
+                };
+            builder.add_edge(source.clone(), reference.clone());
+        }
+
+        builder.build().map_err(first_build_error)
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize,
+    Ix: Index + Debug + Serialize,
+{
+    /// Encodes the graph as MessagePack, in the [`AdjacencyList`] format
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// let bytes = graph.to_msgpack().unwrap();
+    /// let decoded: BullDag<usize, &str> = BullDag::from_msgpack(&bytes).unwrap();
+    /// assert!(decoded == graph);
+    /// ```
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec(&AdjacencyList::from(self))
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl<'a, T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Deserialize<'a>,
+    Ix: Index + Debug + Deserialize<'a>,
+{
+    /// Decodes a graph written by [`BullDag::to_msgpack`], failing if the
+    /// bytes are malformed, an edge refers to a missing vertex, or the
+    /// edges contain a cycle
+    pub fn from_msgpack(bytes: &'a [u8]) -> Result<BullDag<T, Ix>, GraphError> {
+        let list: AdjacencyList<T, Ix> = rmp_serde::from_slice(bytes).map_err(GraphError::other)?;
+        BullDag::try_from(list)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize,
+    Ix: Index + Debug + Serialize,
+{
+    /// Encodes the graph as CBOR, in the [`AdjacencyList`] format
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, String> = BullDag::new();
+    /// let v1: Vertex<usize, String> = Vertex::new(5, "source".to_string());
+    /// let v2: Vertex<usize, String> = Vertex::new(4, "reference".to_string());
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// let bytes = graph.to_cbor().unwrap();
+    /// let decoded: BullDag<usize, String> = BullDag::from_cbor(&bytes).unwrap();
+    /// assert!(decoded == graph);
+    /// ```
+    pub fn to_cbor(&self) -> Result<Vec<u8>, ciborium::ser::Error<std::io::Error>> {
+        let mut out = vec![];
+        ciborium::into_writer(&AdjacencyList::from(self), &mut out)?;
+        Ok(out)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + DeserializeOwned,
+    Ix: Index + Debug + DeserializeOwned,
+{
+    /// Decodes a graph written by [`BullDag::to_cbor`], failing if the
+    /// bytes are malformed, an edge refers to a missing vertex, or the
+    /// edges contain a cycle
+    pub fn from_cbor(bytes: &[u8]) -> Result<BullDag<T, Ix>, GraphError> {
+        let list: AdjacencyList<T, Ix> = ciborium::from_reader(bytes).map_err(GraphError::other)?;
+        BullDag::try_from(list)
+    }
+}
@@ -0,0 +1,753 @@
+use crate::edge::Edge;
+use crate::graph::{BullDag, GraphError, GraphResult};
+use crate::index::Index;
+use crate::observe::GraphObserver;
+use crate::vertex::Vertex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Debug;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::Mutex;
+
+/// A successful mutation of a graph, as recorded by [`BullDag::log_ops`].
+/// Replaying the ops of a graph, in order, into an empty graph rebuilds it,
+/// see [`BullDag::replay`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphOp<T, Ix> {
+    AddVertex {
+        index: Ix,
+        data: T,
+    },
+    /// The data of a vertex already in the graph was replaced or updated
+    SetData {
+        index: Ix,
+        data: T,
+    },
+    AddEdge {
+        source: Ix,
+        reference: Ix,
+    },
+    RemoveEdge {
+        source: Ix,
+        reference: Ix,
+    },
+    /// Follows a `RemoveEdge` for each of the vertex's edges
+    RemoveVertex {
+        index: Ix,
+    },
+    SetEdgeKind {
+        source: Ix,
+        reference: Ix,
+        kind: u32,
+    },
+    SetEdgeTime {
+        source: Ix,
+        reference: Ix,
+        at: u64,
+    },
+    SetEdgeWeight {
+        source: Ix,
+        reference: Ix,
+        weight: u64,
+    },
+    /// Every key the edge has, which needs multi-edges to be enabled
+    SetEdgeKeys {
+        source: Ix,
+        reference: Ix,
+        keys: BTreeSet<u64>,
+    },
+    SetMultiEdges {
+        enabled: bool,
+    },
+    SetEpoch {
+        index: Ix,
+        epoch: u64,
+    },
+    AddConflict {
+        a: Ix,
+        b: Ix,
+    },
+    RemoveConflict {
+        a: Ix,
+        b: Ix,
+    },
+}
+
+impl<T, Ix> GraphOp<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Applies the op to a graph. Fails if an edge refers to a missing
+    /// vertex or would create a cycle, if the vertex, edge or conflict to
+    /// change or remove is missing, or if keys are set while multi-edges
+    /// are disabled.
+    pub fn apply(&self, graph: &mut BullDag<T, Ix>) -> GraphResult {
+        match self {
+            GraphOp::AddVertex { index, data } => {
+                graph.add_vertex(&Vertex::new(data.clone(), index.clone()));
+                Ok(())
+            }
+            GraphOp::SetData { index, data } => graph
+                .set_vertex_data(index, data.clone())
+                .map(|_| ())
+                .ok_or(GraphError::NonExistentVertex),
+            GraphOp::AddEdge { source, reference } => graph.add_edge_ix(source, reference),
+            GraphOp::RemoveEdge { source, reference } => {
+                match graph.remove_edge(&Edge::new(source.clone(), reference.clone())) {
+                    true => Ok(()),
+                    false => Err(GraphError::NoEdges),
+                }
+            }
+            GraphOp::RemoveVertex { index } => graph
+                .remove_vertex(index.clone())
+                .map(|_| ())
+                .ok_or(GraphError::NonExistentVertex),
+            GraphOp::SetEdgeKind {
+                source,
+                reference,
+                kind,
+            } => edge_changed(
+                graph.set_edge_kind(&Edge::new(source.clone(), reference.clone()), *kind),
+            ),
+            GraphOp::SetEdgeTime {
+                source,
+                reference,
+                at,
+            } => edge_changed(
+                graph.set_edge_time(&Edge::new(source.clone(), reference.clone()), *at),
+            ),
+            GraphOp::SetEdgeWeight {
+                source,
+                reference,
+                weight,
+            } => edge_changed(
+                graph.set_edge_weight(&Edge::new(source.clone(), reference.clone()), *weight),
+            ),
+            GraphOp::SetEdgeKeys {
+                source,
+                reference,
+                keys,
+            } => {
+                if !graph.multi_edges_enabled() {
+                    return Err(GraphError::other("multi-edges are not enabled"));
+                }
+                let edge = Edge::new(source.clone(), reference.clone());
+                if !graph.edges.contains(&edge) {
+                    return Err(GraphError::NoEdges);
+                }
+                graph.set_edge_keys(&edge, keys.clone());
+                Ok(())
+            }
+            GraphOp::SetMultiEdges { enabled: true } => {
+                graph.enable_multi_edges();
+                Ok(())
+            }
+            GraphOp::SetMultiEdges { enabled: false } => {
+                graph.disable_multi_edges();
+                Ok(())
+            }
+            GraphOp::SetEpoch { index, epoch } => match graph.set_epoch(index, *epoch) {
+                true => Ok(()),
+                false => Err(GraphError::NonExistentVertex),
+            },
+            GraphOp::AddConflict { a, b } => match graph.add_conflict(a, b) {
+                true => Ok(()),
+                false => Err(GraphError::NonExistentVertex),
+            },
+            GraphOp::RemoveConflict { a, b } => match graph.remove_conflict(a, b) {
+                true => Ok(()),
+                false => Err(GraphError::other("the conflict does not exist")),
+            },
+        }
+    }
+}
+
+/// Turns the result of setting a tag of an edge into that of an op
+fn edge_changed(changed: bool) -> GraphResult {
+    match changed {
+        true => Ok(()),
+        false => Err(GraphError::NoEdges),
+    }
+}
+
+/// Turns observer callbacks into ops for a sink
+struct OpRecorder<F>(F);
+
+impl<T, Ix, F> GraphObserver<T, Ix> for OpRecorder<F>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+    F: Fn(GraphOp<T, Ix>) + Send + Sync,
+{
+    fn vertex_added(&self, vertex: &Vertex<T, Ix>) {
+        (self.0)(GraphOp::AddVertex {
+            index: vertex.get_index(),
+            data: vertex.get_data(),
+        })
+    }
+
+    fn vertex_updated(&self, index: &Ix, data: &T, _previous: &T) {
+        (self.0)(GraphOp::SetData {
+            index: index.clone(),
+            data: data.clone(),
+        })
+    }
+
+    fn edge_added(&self, edge: &Edge<Ix>) {
+        (self.0)(GraphOp::AddEdge {
+            source: edge.get_source(),
+            reference: edge.get_reference(),
+        })
+    }
+
+    fn edge_removed(&self, edge: &Edge<Ix>) {
+        (self.0)(GraphOp::RemoveEdge {
+            source: edge.get_source(),
+            reference: edge.get_reference(),
+        })
+    }
+
+    fn vertex_removed(&self, vertex: &Vertex<T, Ix>) {
+        (self.0)(GraphOp::RemoveVertex {
+            index: vertex.get_index(),
+        })
+    }
+
+    fn edge_kind_set(&self, edge: &Edge<Ix>, kind: u32) {
+        (self.0)(GraphOp::SetEdgeKind {
+            source: edge.get_source(),
+            reference: edge.get_reference(),
+            kind,
+        })
+    }
+
+    fn edge_time_set(&self, edge: &Edge<Ix>, at: u64) {
+        (self.0)(GraphOp::SetEdgeTime {
+            source: edge.get_source(),
+            reference: edge.get_reference(),
+            at,
+        })
+    }
+
+    fn edge_weight_set(&self, edge: &Edge<Ix>, weight: u64) {
+        (self.0)(GraphOp::SetEdgeWeight {
+            source: edge.get_source(),
+            reference: edge.get_reference(),
+            weight,
+        })
+    }
+
+    fn edge_keys_set(&self, edge: &Edge<Ix>, keys: &BTreeSet<u64>) {
+        (self.0)(GraphOp::SetEdgeKeys {
+            source: edge.get_source(),
+            reference: edge.get_reference(),
+            keys: keys.clone(),
+        })
+    }
+
+    fn multi_edges_set(&self, enabled: bool) {
+        (self.0)(GraphOp::SetMultiEdges { enabled })
+    }
+
+    fn epoch_set(&self, index: &Ix, epoch: u64) {
+        (self.0)(GraphOp::SetEpoch {
+            index: index.clone(),
+            epoch,
+        })
+    }
+
+    fn conflict_added(&self, a: &Ix, b: &Ix) {
+        (self.0)(GraphOp::AddConflict {
+            a: a.clone(),
+            b: b.clone(),
+        })
+    }
+
+    fn conflict_removed(&self, a: &Ix, b: &Ix) {
+        (self.0)(GraphOp::RemoveConflict {
+            a: a.clone(),
+            b: b.clone(),
+        })
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + 'static,
+    Ix: Index + Debug + 'static,
+{
+    /// Calls `sink` with every subsequent successful mutation of the graph,
+    /// including the kinds, times, weights and keys of edges, epochs and
+    /// conflicts. Not seen are data changed in place through
+    /// [`BullDag::get_vertex_mut`], changes that bypass observers, such as
+    /// [`BullDag::restore`], and the checkpoints recorded by
+    /// `prune_epochs_before`. A replayed graph numbers its vertices in the
+    /// order they are added, so a vertex brought back by
+    /// [`BullDag::undo`] or renamed by [`BullDag::reindex`], which keep the
+    /// vertex's place in the insertion order, comes last instead. To log a
+    /// graph that already has vertices, replay [`BullDag::to_ops`] first.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::journal::GraphOp;
+    /// use bulldag::vertex::Vertex;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let ops = Arc::new(Mutex::new(vec![]));
+    /// let sink = ops.clone();
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// graph.log_ops(move |op| sink.lock().unwrap().push(op));
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// let replayed = BullDag::replay(ops.lock().unwrap().clone()).unwrap();
+    /// assert!(replayed == graph);
+    /// ```
+    pub fn log_ops(&mut self, sink: impl Fn(GraphOp<T, Ix>) + Send + Sync + 'static) {
+        self.add_observer(OpRecorder(sink));
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize + 'static,
+    Ix: Index + Debug + Serialize + 'static,
+{
+    /// Writes every subsequent successful mutation of the graph to `writer`
+    /// as a line of JSON, flushing after each one, see
+    /// [`BullDag::replay_log`]. The log stops at the first write error,
+    /// since any later op could not be replayed without the missing one.
+    pub fn log_ops_to<W: Write + Send + 'static>(&mut self, writer: W) {
+        let writer = Mutex::new(Some(writer));
+        self.log_ops(move |op| {
+            let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+            let written = writer.as_mut().map(|w| {
+                serde_json::to_writer(&mut *w, &op)
+                    .map_err(std::io::Error::from)
+                    .and_then(|_| w.write_all(b"\n"))
+                    .and_then(|_| w.flush())
+            });
+            if let Some(Err(_)) = written {
+                *writer = None;
+            }
+        });
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns the ops that build the graph as it is: whether it has
+    /// multi-edges, then the vertices in the order they were inserted with
+    /// their epochs, then each edge followed by its kind, time, weight and
+    /// keys, and last the conflicts. Replaying them gives a graph with the
+    /// same vertices, edges, tags and insertion order, though its vertices
+    /// are numbered afresh from 0 (see [`BullDag::insertion_seq`]) and it
+    /// has none of the checkpoints recorded by `prune_epochs_before`.
+    pub fn to_ops(&self) -> Vec<GraphOp<T, Ix>> {
+        let mut ops = vec![];
+        if self.multi_edges_enabled() {
+            ops.push(GraphOp::SetMultiEdges { enabled: true });
+        }
+
+        let mut vertices: Vec<&Ix> = self.vertices.keys().collect();
+        vertices.sort_by_key(|ix| self.sequence.get(*ix).copied().unwrap_or(u64::MAX));
+        for ix in vertices.iter().copied() {
+            ops.push(GraphOp::AddVertex {
+                index: ix.clone(),
+                data: self.vertices[ix].get_data(),
+            });
+            if let Some(epoch) = self.epoch_of(ix) {
+                ops.push(GraphOp::SetEpoch {
+                    index: ix.clone(),
+                    epoch,
+                });
+            }
+        }
+
+        for ix in self.topological_order() {
+            for r in self.vertices[&ix].get_references() {
+                let edge = Edge::new(ix.clone(), r.clone());
+                let (source, reference) = (ix.clone(), r.clone());
+                ops.push(GraphOp::AddEdge {
+                    source: source.clone(),
+                    reference: reference.clone(),
+                });
+                if let Some(kind) = self.edge_kinds.get(&edge) {
+                    ops.push(GraphOp::SetEdgeKind {
+                        source: source.clone(),
+                        reference: reference.clone(),
+                        kind: *kind,
+                    });
+                }
+                if let Some(at) = self.edge_times.get(&edge) {
+                    ops.push(GraphOp::SetEdgeTime {
+                        source: source.clone(),
+                        reference: reference.clone(),
+                        at: *at,
+                    });
+                }
+                if let Some(weight) = self.edge_weights.get(&edge) {
+                    ops.push(GraphOp::SetEdgeWeight {
+                        source: source.clone(),
+                        reference: reference.clone(),
+                        weight: *weight,
+                    });
+                }
+                // Every edge gets the key 0 when it is added
+                if let Some(keys) = self.edge_keys.as_ref().and_then(|k| k.get(&edge)) {
+                    if *keys != BTreeSet::from([0]) {
+                        ops.push(GraphOp::SetEdgeKeys {
+                            source,
+                            reference,
+                            keys: keys.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        // Each conflict once, from the vertex inserted first
+        let position: HashMap<&Ix, usize> = vertices
+            .iter()
+            .enumerate()
+            .map(|(i, ix)| (*ix, i))
+            .collect();
+        for a in vertices.iter().copied() {
+            for b in self.conflicts.get(a).into_iter().flatten() {
+                if position.get(b).is_some_and(|b| *b > position[a]) {
+                    ops.push(GraphOp::AddConflict {
+                        a: a.clone(),
+                        b: b.clone(),
+                    });
+                }
+            }
+        }
+
+        ops
+    }
+
+    /// Builds a graph by applying `ops` in order to an empty graph, failing
+    /// at the first op that can't be applied
+    pub fn replay(
+        ops: impl IntoIterator<Item = GraphOp<T, Ix>>,
+    ) -> Result<BullDag<T, Ix>, GraphError> {
+        let mut graph = BullDag::new();
+        for op in ops {
+            op.apply(&mut graph)?;
+        }
+        Ok(graph)
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + DeserializeOwned,
+    Ix: Index + Debug + DeserializeOwned,
+{
+    /// Rebuilds a graph from a log written by [`BullDag::log_ops_to`]. A
+    /// final line that is cut short, as left by a crash mid-write, is
+    /// ignored; any other malformed line is an error.
+    pub fn replay_log<R: Read>(reader: R) -> Result<BullDag<T, Ix>, GraphError> {
+        let mut graph = BullDag::new();
+        let mut lines = BufReader::new(reader).lines().peekable();
+        while let Some(line) = lines.next() {
+            let line = line.map_err(GraphError::other)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let op: GraphOp<T, Ix> = match serde_json::from_str(&line) {
+                Ok(op) => op,
+                Err(e) if e.is_eof() && lines.peek().is_none() => break,
+                Err(e) => return Err(GraphError::other(e)),
+            };
+            op.apply(&mut graph)?;
+        }
+        Ok(graph)
+    }
+}
+
+/// A single undoable step
+#[derive(Debug, Clone)]
+pub(crate) enum Change<T: Clone + Debug, Ix: Index + Debug> {
+    AddVertex(Vertex<T, Ix>),
+    /// The data of a vertex was replaced, with the data it had before
+    SetData {
+        index: Ix,
+        data: T,
+        previous: T,
+    },
+    /// An edge, along with the vertices that were added with it
+    AddEdge {
+        edge: Edge<Ix>,
+        new_vertices: Vec<Vertex<T, Ix>>,
+    },
+    RemoveEdge {
+        edge: Edge<Ix>,
+        tags: EdgeTags,
+    },
+    /// A vertex as it was when it was removed, with its sources and
+    /// references, and everything else the graph kept about it and its
+    /// edges
+    RemoveVertex {
+        vertex: Vertex<T, Ix>,
+        epoch: Option<u64>,
+        seq: Option<u64>,
+        conflicts: HashSet<Ix>,
+        edges: Vec<(Edge<Ix>, EdgeTags)>,
+    },
+}
+
+/// The kind, time, weight and keys of a removed edge, so that undoing the
+/// removal puts them back
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EdgeTags {
+    kind: Option<u32>,
+    time: Option<u64>,
+    weight: Option<u64>,
+    keys: Option<BTreeSet<u64>>,
+}
+
+/// The undo and redo stacks of a graph with journaling enabled
+#[derive(Debug, Clone)]
+pub(crate) struct Journal<T: Clone + Debug, Ix: Index + Debug> {
+    undo: Vec<Change<T, Ix>>,
+    redo: Vec<Change<T, Ix>>,
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Starts recording mutations so that they can be reverted with
+    /// [`BullDag::undo`] and replayed with [`BullDag::redo`]. Adding a
+    /// vertex, replacing or updating the data of a vertex, adding an edge
+    /// (with any vertices it adds), removing an edge and removing a vertex
+    /// (with all of its edges) are each one step. Undoing a removal also
+    /// puts back the vertex's place in insertion order, epoch and
+    /// conflicts, and the kinds, times, weights and keys of its edges.
+    /// Data changed in place through [`BullDag::get_vertex_mut`] is not
+    /// recorded. Does nothing if the journal is already enabled.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// graph.enable_journal();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    /// graph.remove_vertex("reference");
+    ///
+    /// assert!(graph.undo());
+    /// assert!(graph.n_edges() == 1);
+    /// assert!(graph.undo());
+    /// assert!(graph.is_empty());
+    /// assert!(graph.redo());
+    /// assert!(graph.n_edges() == 1);
+    /// ```
+    pub fn enable_journal(&mut self) {
+        if self.journal.is_none() {
+            self.journal = Some(Journal {
+                undo: vec![],
+                redo: vec![],
+            });
+        }
+    }
+
+    /// Stops recording mutations and drops the undo and redo history
+    pub fn disable_journal(&mut self) {
+        self.journal = None;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.journal.as_ref().is_some_and(|j| !j.undo.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.journal.as_ref().is_some_and(|j| !j.redo.is_empty())
+    }
+
+    /// Removes the kind, time, weight and keys of an edge, returning them
+    pub(crate) fn take_edge_tags(&mut self, edge: &Edge<Ix>) -> EdgeTags {
+        EdgeTags {
+            kind: self.edge_kinds.remove(edge),
+            time: self.edge_times.remove(edge),
+            weight: self.edge_weights.remove(edge),
+            keys: self.remove_edge_keys(edge),
+        }
+    }
+
+    /// Puts back the kind, time, weight and keys of an edge that is in the
+    /// graph again
+    fn restore_edge_tags(&mut self, edge: &Edge<Ix>, tags: &EdgeTags) {
+        if let Some(kind) = tags.kind {
+            self.set_edge_kind(edge, kind);
+        }
+        if let Some(time) = tags.time {
+            self.set_edge_time(edge, time);
+        }
+        if let Some(weight) = tags.weight {
+            self.set_edge_weight(edge, weight);
+        }
+        if let Some(keys) = &tags.keys {
+            self.set_edge_keys(edge, keys.clone());
+        }
+    }
+
+    /// The change removing the vertex at `ix` would make, taken before it
+    /// is removed
+    pub(crate) fn vertex_removal(&self, ix: &Ix) -> Option<Change<T, Ix>> {
+        let vertex = self.vertices.get(ix)?;
+        let edges = vertex
+            .get_sources()
+            .into_iter()
+            .map(|s| Edge::new(s.clone(), ix.clone()))
+            .chain(
+                vertex
+                    .get_references()
+                    .into_iter()
+                    .map(|r| Edge::new(ix.clone(), r.clone())),
+            )
+            .map(|edge| {
+                let tags = EdgeTags {
+                    kind: self.edge_kinds.get(&edge).copied(),
+                    time: self.edge_times.get(&edge).copied(),
+                    weight: self.edge_weights.get(&edge).copied(),
+                    keys: self.edge_keys.as_ref().and_then(|k| k.get(&edge)).cloned(),
+                };
+                (edge, tags)
+            })
+            .collect();
+        Some(Change::RemoveVertex {
+            vertex: vertex.clone(),
+            epoch: self.epoch_of(ix),
+            seq: self.insertion_seq(ix),
+            conflicts: self.conflicts_of(ix),
+            edges,
+        })
+    }
+
+    /// Journals a change made by a public method, a new change makes the
+    /// redo history obsolete
+    pub(crate) fn record(&mut self, change: Change<T, Ix>) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.undo.push(change);
+            journal.redo.clear();
+        }
+    }
+
+    /// Reverts the most recent step, returning false if there was nothing
+    /// to undo
+    pub fn undo(&mut self) -> bool {
+        let Some(mut journal) = self.journal.take() else {
+            return false;
+        };
+        let Some(change) = journal.undo.pop() else {
+            self.journal = Some(journal);
+            return false;
+        };
+
+        match &change {
+            Change::AddVertex(vtx) => {
+                self.remove_vertex(vtx.get_index());
+            }
+            Change::SetData {
+                index, previous, ..
+            } => {
+                self.set_vertex_data(index, previous.clone());
+            }
+            Change::AddEdge { edge, new_vertices } => {
+                self.remove_edge(edge);
+                for vtx in new_vertices {
+                    self.remove_vertex(vtx.get_index());
+                }
+            }
+            Change::RemoveEdge { edge, tags } => {
+                if self
+                    .add_edge_ix(&edge.get_source(), &edge.get_reference())
+                    .is_ok()
+                {
+                    self.restore_edge_tags(edge, tags);
+                }
+            }
+            Change::RemoveVertex {
+                vertex,
+                epoch,
+                seq,
+                conflicts,
+                edges,
+            } => {
+                let index = vertex.get_index();
+                self.add_vertex(&Vertex::new(vertex.get_data(), index.clone()));
+                if let Some(seq) = seq {
+                    self.sequence.insert(index.clone(), *seq);
+                }
+                if let Some(epoch) = epoch {
+                    self.set_epoch(&index, *epoch);
+                }
+                conflicts.iter().for_each(|other| {
+                    self.add_conflict(&index, other);
+                });
+                for (edge, tags) in edges {
+                    if self
+                        .add_edge_ix(&edge.get_source(), &edge.get_reference())
+                        .is_ok()
+                    {
+                        self.restore_edge_tags(edge, tags);
+                    }
+                }
+            }
+        }
+
+        journal.redo.push(change);
+        self.journal = Some(journal);
+        true
+    }
+
+    /// Replays the most recently undone step, returning false if there was
+    /// nothing to redo
+    pub fn redo(&mut self) -> bool {
+        let Some(mut journal) = self.journal.take() else {
+            return false;
+        };
+        let Some(change) = journal.redo.pop() else {
+            self.journal = Some(journal);
+            return false;
+        };
+
+        match &change {
+            Change::AddVertex(vtx) => self.add_vertex(vtx),
+            Change::SetData { index, data, .. } => {
+                self.set_vertex_data(index, data.clone());
+            }
+            Change::AddEdge { edge, new_vertices } => {
+                for vtx in new_vertices {
+                    self.add_vertex(vtx);
+                }
+                let _ = self.add_edge_ix(&edge.get_source(), &edge.get_reference());
+            }
+            Change::RemoveEdge { edge, .. } => {
+                self.remove_edge(edge);
+            }
+            Change::RemoveVertex { vertex, .. } => {
+                self.remove_vertex(vertex.get_index());
+            }
+        }
+
+        journal.undo.push(change);
+        self.journal = Some(journal);
+        true
+    }
+}
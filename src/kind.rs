@@ -0,0 +1,109 @@
+use crate::edge::{Edge, IntoEdgeSpec};
+use crate::graph::{BullDag, GraphResult};
+use crate::index::Index;
+use crate::observe::Event;
+use crate::vertex::Direction;
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Adds an edge of the given kind, like [`BullDag::try_add_edge`]. Kinds
+    /// tell apart edges that mean different things, e.g. a parent and a
+    /// merely referenced block, and are usually the discriminants of an
+    /// enum. Every edge that isn't given a kind is of kind 0. If the
+    /// vertices are already connected the edge's kind is replaced.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::edge::Edge;
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::{Direction, Vertex};
+    ///
+    /// const PARENT: u32 = 0;
+    /// const REFERENCE: u32 = 1;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "genesis");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "block_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "uncle");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "block_2");
+    /// graph.extend_from_edges([(&v1, &v2), (&v1, &v3), (&v2, &v4)]);
+    /// graph.add_edge_of_kind((&v3, &v4), REFERENCE).unwrap();
+    ///
+    /// assert!(graph.edge_kind(&Edge::new("uncle", "block_2")) == Some(REFERENCE));
+    /// let parents = graph.trace_kind(&"block_2", Direction::Source, PARENT);
+    /// assert!(parents == vec!["genesis", "block_1", "block_2"]);
+    /// ```
+    pub fn add_edge_of_kind(&mut self, edge: impl IntoEdgeSpec<T, Ix>, kind: u32) -> GraphResult {
+        let spec = edge.into_edge_spec();
+        let e = spec.edge();
+        self.try_add_edge(spec)?;
+        self.set_edge_kind(&e, kind);
+        Ok(())
+    }
+
+    /// Changes the kind of an edge, returning false if there is no such
+    /// edge
+    pub fn set_edge_kind(&mut self, edge: &Edge<Ix>, kind: u32) -> bool {
+        if !self.edges.contains(edge) {
+            return false;
+        }
+        match kind {
+            0 => self.edge_kinds.remove(edge),
+            _ => self.edge_kinds.insert(edge, kind),
+        };
+        self.notify(Event::EdgeKindSet(edge, kind));
+        true
+    }
+
+    /// Returns the kind of an edge, or `None` if there is no such edge
+    pub fn edge_kind(&self, edge: &Edge<Ix>) -> Option<u32> {
+        if !self.edges.contains(edge) {
+            return None;
+        }
+        Some(self.edge_kinds.get(edge).copied().unwrap_or(0))
+    }
+
+    fn kind_between(&self, source: &Ix, reference: &Ix) -> u32 {
+        self.edge_kinds
+            .between(source, reference)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns the sources or references of the vertex at `ix` connected to
+    /// it by an edge of the given kind
+    pub fn neighbors_of_kind(&self, ix: &Ix, direction: Direction, kind: u32) -> Vec<&Ix> {
+        let Some(vtx) = self.vertices.get(ix) else {
+            return vec![];
+        };
+        let mut neighbors = vtx.neighbors(direction);
+        neighbors.retain(|n| match direction {
+            Direction::Source => self.kind_between(n, ix) == kind,
+            Direction::Reference => self.kind_between(ix, n) == kind,
+        });
+        neighbors
+    }
+
+    /// Like [`BullDag::trace`], but only follows edges of the given kind.
+    /// Returns the vertices reached, the vertex at `ix` last, or nothing if
+    /// there is no such vertex.
+    pub fn trace_kind(&self, ix: &Ix, direction: Direction, kind: u32) -> Vec<Ix> {
+        if !self.contains_vertex(ix) {
+            return vec![];
+        }
+        let mut stack = vec![];
+        self.post_order_where(
+            ix.clone(),
+            direction,
+            &mut HashSet::new(),
+            &mut stack,
+            |source, reference| self.kind_between(source, reference) == kind,
+        );
+        stack
+    }
+}
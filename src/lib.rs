@@ -1,14 +1,74 @@
+#[cfg(feature = "uuid")]
+pub mod auto;
+pub mod builder;
+pub mod concurrent;
+pub mod conflict;
+#[cfg(feature = "consensus")]
+pub mod consensus;
+pub mod diff;
+pub mod dominators;
 pub mod edge;
+pub mod edit;
+pub mod entry;
+pub mod epoch;
+pub mod exec;
+pub mod frozen;
+pub mod generate;
 pub mod graph;
+pub mod history;
 pub mod index;
+pub mod interned;
+pub mod interop;
+pub mod journal;
+pub mod kind;
+mod macros;
+pub mod memory;
+pub mod multi;
 pub mod node;
+pub mod observe;
+pub mod ordered;
+pub mod ordering;
+#[cfg(feature = "rayon")]
+pub mod par;
+pub mod payload;
+#[cfg(feature = "persistent")]
+pub mod persistent;
+pub mod query;
+pub mod reachability;
+pub mod render;
+pub mod reverse;
+#[cfg(feature = "tokio")]
+pub mod service;
+pub mod snapshot;
+pub mod sorts;
+pub mod staging;
+pub mod stats;
+pub mod store;
+pub mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod time;
+#[cfg(feature = "random")]
+pub mod tips;
+pub mod transaction;
+pub mod validate;
 pub mod vertex;
+pub mod visit;
+pub mod walk;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod weight;
+pub mod width;
 
 #[cfg(test)]
+#[allow(
+    clippy::len_zero,
+    clippy::mixed_attributes_style,
+    clippy::unnecessary_unwrap
+)]
 mod tests {
     #![allow(dead_code)]
     use crate::graph::BullDag;
-    use crate::graph::GraphOk;
     use crate::vertex::Vertex;
 
     #[test]
@@ -180,8 +240,3849 @@ mod tests {
             "new_reference",
         ];
 
-        if let Ok(GraphOk::VecRes(v)) = graph.topological_sort() {
+        if let Ok(v) = graph.topological_sort() {
             assert!((v == opt_1 || v == opt_2));
         }
     }
+
+    #[test]
+    fn test_diff_and_apply_roundtrip() {
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+
+        let mut local: BullDag<usize, &str> = BullDag::new();
+        local.extend_from_edges([(&v1, &v2), (&v3, &v1), (&v2, &v4)]);
+
+        let mut remote: BullDag<usize, &str> = BullDag::new();
+        let changed: Vertex<usize, &str> = Vertex::new(50, "source");
+        remote.extend_from_edges([(&changed, &v2), (&v2, &v5), (&changed, &v5)]);
+
+        let patch = local.diff(&remote);
+        local.apply(patch);
+
+        assert!(local == remote);
+        assert!(local.get_roots() == remote.get_roots());
+        assert!(local.get_leaves() == remote.get_leaves());
+        assert!(local.get_vertex("source").unwrap().get_data() == 50);
+        assert!(local.get_vertex("ultimate_source").is_none());
+        assert!(local.diff(&remote).is_empty());
+    }
+
+    #[test]
+    fn test_graph_equality_ignores_insertion_order() {
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+
+        let mut g1: BullDag<usize, &str> = BullDag::new();
+        g1.extend_from_edges([(&v1, &v2), (&v3, &v1)]);
+
+        let mut g2: BullDag<usize, &str> = BullDag::new();
+        g2.extend_from_edges([(&v3, &v1), (&v1, &v2)]);
+        assert_eq!(g1, g2);
+
+        g2.get_vertex_mut("source").unwrap().set_data(6);
+        assert_ne!(g1, g2);
+    }
+
+    #[test]
+    fn test_canonical_hash_tracks_data_and_edges() {
+        use std::collections::hash_map::DefaultHasher;
+
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        graph.extend_from_edges([(&v1, &v2), (&v3, &v1)]);
+        let original = graph.canonical_hash::<DefaultHasher>();
+
+        let mut changed = graph.clone();
+        changed.get_vertex_mut("reference").unwrap().set_data(7);
+        assert_ne!(original, changed.canonical_hash::<DefaultHasher>());
+
+        let mut extra_edge = graph.clone();
+        extra_edge.add_edge((&v3, &v2));
+        assert_ne!(original, extra_edge.canonical_hash::<DefaultHasher>());
+
+        assert_eq!(original, graph.clone().canonical_hash::<DefaultHasher>());
+    }
+
+    #[test]
+    fn test_retain_vertices_keeps_roots_and_leaves_consistent() {
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        graph.retain_vertices(|ix, _| *ix != "ultimate_source" && *ix != "reference");
+
+        assert!(graph.len() == 3);
+        assert!(graph.n_edges() == 1);
+        assert!(graph.get_roots().contains("source"));
+        assert!(graph.get_roots().contains("ref_reference"));
+        assert!(graph.get_leaves().contains("ref_reference"));
+        assert!(graph.get_leaves().contains("new_reference"));
+        assert!(!graph.get_leaves().contains("source"));
+    }
+
+    #[test]
+    fn test_prune_below_depth_uses_shortest_path_to_frontier() {
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        // "source" is one step from the "new_reference" leaf, so only
+        // "ultimate_source" is buried deeper than 1.
+        let pruned = graph.prune_below_depth(1);
+        assert!(pruned.len() == 1);
+        assert!(pruned[0].get_index() == "ultimate_source");
+        assert!(pruned[0].n_references() == 2);
+        assert!(graph.len() == 4);
+        assert!(graph.get_roots().contains("source"));
+        assert!(graph.prune_below_depth(1).is_empty());
+    }
+
+    #[test]
+    fn test_staging_applies_edges_once_both_vertices_arrive() {
+        use crate::edge::Edge;
+        use crate::staging::StagingDag;
+
+        let mut staging: StagingDag<usize, &str> = StagingDag::new();
+        staging.add_edge(Edge::new("source", "reference"));
+        staging.add_edge(Edge::new("ultimate_source", "source"));
+        staging.add_edge(Edge::new("reference", "ref_reference"));
+        assert!(staging.n_pending() == 3);
+
+        staging.add_vertex(Vertex::new(5, "source"));
+        staging.add_vertex(Vertex::new(4, "reference"));
+        assert!(staging.graph().n_edges() == 1);
+        assert!(staging.n_pending() == 2);
+
+        staging.add_vertex(Vertex::new(3, "ultimate_source"));
+        staging.add_vertex(Vertex::new(2, "ref_reference"));
+        assert!(staging.n_pending() == 0);
+
+        let graph = staging.into_inner();
+        assert!(graph.n_edges() == 3);
+        assert!(graph.get_roots().contains("ultimate_source"));
+        assert!(graph.get_leaves().contains("ref_reference"));
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_tip_selectors_only_return_leaves() {
+        use crate::tips::{RandomWalk, TipSelector, UniformRandom, WeightedByReferences};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let root: Vertex<usize, &str> = Vertex::new(0, "root");
+        let heavy: Vertex<usize, &str> = Vertex::new(1, "heavy");
+        let light: Vertex<usize, &str> = Vertex::new(2, "light");
+        let heavy_1: Vertex<usize, &str> = Vertex::new(3, "heavy_1");
+        let heavy_2: Vertex<usize, &str> = Vertex::new(4, "heavy_2");
+        let heavy_tip: Vertex<usize, &str> = Vertex::new(5, "heavy_tip");
+        graph.extend_from_edges([
+            (&root, &heavy),
+            (&root, &light),
+            (&heavy, &heavy_1),
+            (&heavy, &heavy_2),
+            (&heavy_1, &heavy_tip),
+        ]);
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let leaves = graph.get_leaves();
+        for _ in 0..20 {
+            assert!(leaves.contains(&UniformRandom.select_tip(&graph, &mut rng).unwrap()));
+            assert!(leaves.contains(&WeightedByReferences.select_tip(&graph, &mut rng).unwrap()));
+            assert!(leaves.contains(&RandomWalk::new(0.5).select_tip(&graph, &mut rng).unwrap()));
+        }
+
+        let greedy = RandomWalk::new(100.0);
+        assert!(greedy.select_tip(&graph, &mut rng) == Some("heavy_tip"));
+        assert!(UniformRandom.select_tips(&graph, 50, &mut rng).len() == 3);
+
+        let empty: BullDag<usize, &str> = BullDag::new();
+        assert!(UniformRandom.select_tip(&empty, &mut rng).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_weighted_by_references_favours_tips_with_more_ancestors() {
+        use crate::tips::{TipSelector, WeightedByReferences};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use std::collections::HashMap;
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let root: Vertex<usize, &str> = Vertex::new(0, "root");
+        let heavy: Vertex<usize, &str> = Vertex::new(1, "heavy");
+        let light: Vertex<usize, &str> = Vertex::new(2, "light");
+        let heavy_1: Vertex<usize, &str> = Vertex::new(3, "heavy_1");
+        let heavy_2: Vertex<usize, &str> = Vertex::new(4, "heavy_2");
+        let heavy_tip: Vertex<usize, &str> = Vertex::new(5, "heavy_tip");
+        graph.extend_from_edges([
+            (&root, &heavy),
+            (&root, &light),
+            (&heavy, &heavy_1),
+            (&heavy, &heavy_2),
+            (&heavy_1, &heavy_tip),
+        ]);
+
+        // The tips weigh 4 (heavy_tip), 3 (heavy_2) and 2 (light)
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for _ in 0..900 {
+            let tip = WeightedByReferences.select_tip(&graph, &mut rng).unwrap();
+            *counts.entry(tip).or_default() += 1;
+        }
+
+        assert!(counts["heavy_tip"] > counts["heavy_2"]);
+        assert!(counts["heavy_2"] > counts["light"]);
+        assert!(counts["heavy_tip"] * 2 > counts["light"] * 3);
+    }
+
+    #[test]
+    fn test_confirmation_depth_follows_shortest_path_to_a_leaf() {
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        assert!(graph.confirmation_depth(&"ultimate_source") == 2);
+        assert!(graph.confirmation_depth(&"source") == 1);
+        assert!(graph.confirmation_depth(&"reference") == 1);
+        assert!(graph.confirmation_depth(&"new_reference") == 0);
+        assert!(graph.confirmation_depth(&"missing") == 0);
+
+        assert!(graph.finalized(0).len() == 5);
+        assert!(graph.finalized(1).len() == 3);
+        assert!(graph.finalized(3).is_empty());
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_content_addressed_vertices() {
+        let mut graph: BullDag<String, [u8; 32]> = BullDag::new();
+        let a = graph.insert_content("block_a".to_string()).unwrap();
+        let b = graph.insert_content("block_b".to_string()).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a, graph.insert_content("block_a".to_string()).unwrap());
+        assert!(graph.len() == 2);
+
+        let vertex = Vertex::content_addressed("block_a".to_string()).unwrap();
+        assert!(vertex.get_index() == a);
+        assert!(vertex.verify_content());
+
+        let mut tampered = vertex.clone();
+        tampered.set_data("block_c".to_string());
+        assert!(!tampered.verify_content());
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_state_root_commits_to_data_and_edges() {
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        graph.extend_from_edges([(&v1, &v2), (&v3, &v1), (&v2, &v4)]);
+        let root = graph.state_root().unwrap();
+
+        let mut reordered: BullDag<usize, &str> = BullDag::new();
+        reordered.extend_from_edges([(&v2, &v4), (&v3, &v1), (&v1, &v2)]);
+        assert_eq!(root, reordered.state_root().unwrap());
+
+        let mut changed = graph.clone();
+        changed
+            .get_vertex_mut("ultimate_source")
+            .unwrap()
+            .set_data(30);
+        assert_ne!(root, changed.state_root().unwrap());
+
+        let mut extra_edge = graph.clone();
+        extra_edge.add_edge((&v3, &v2));
+        assert_ne!(root, extra_edge.state_root().unwrap());
+    }
+
+    #[test]
+    fn test_missing_ancestors_stops_at_known_vertices() {
+        use std::collections::HashSet;
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        assert!(graph.have(&"source"));
+        assert!(!graph.have(&"missing"));
+        assert!(graph.frontier().len() == 2);
+
+        let known: HashSet<&str> = ["ultimate_source", "source"].into_iter().collect();
+        let missing = graph.missing_ancestors(&["ref_reference", "missing"], &known);
+        assert!(missing == vec!["reference", "ref_reference"]);
+
+        let everything = graph.missing_ancestors(&graph.frontier(), &HashSet::new());
+        assert!(everything.len() == 5);
+        assert!(everything[0] == "ultimate_source");
+    }
+
+    #[test]
+    fn test_execute_visits_sources_first() {
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        let mut visited = vec![];
+        let results = graph.execute(|vtx, sources| {
+            assert!(sources.len() == vtx.n_sources());
+            visited.push(vtx.get_index());
+            sources.iter().map(|(_, r)| **r).max().unwrap_or(0) + 1
+        });
+
+        assert!(visited.len() == 5);
+        assert!(visited[0] == "ultimate_source");
+        assert!(results["ultimate_source"] == 1);
+        assert!(results["source"] == 2);
+        assert!(results["reference"] == 3);
+        assert!(results["new_reference"] == 4);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_execute_matches_sequential_execute() {
+        let mut graph: BullDag<usize, usize> = BullDag::new();
+        let vertices: Vec<Vertex<usize, usize>> = (0..64).map(|i| Vertex::new(i, i)).collect();
+        // A wide, layered graph: every vertex feeds the two vertices below it
+        let edges: Vec<_> = (0..56)
+            .flat_map(|i| {
+                let next = (i / 8 + 1) * 8;
+                vec![
+                    (&vertices[i], &vertices[next + i % 8]),
+                    (&vertices[i], &vertices[next + (i + 1) % 8]),
+                ]
+            })
+            .collect();
+        graph.extend_from_edges(&edges);
+
+        let work = |vtx: &Vertex<usize, usize>, sources: &[(&usize, &usize)]| {
+            vtx.get_data() + sources.iter().map(|(_, r)| **r).sum::<usize>()
+        };
+        let sequential = graph.execute(work);
+        let parallel = graph.par_execute(4, work).unwrap();
+        assert!(parallel.len() == 64);
+        assert!(sequential == parallel);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_execute_async_matches_sequential_execute() {
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        let sequential = graph.execute(|vtx, sources| {
+            vtx.get_data() * 10 + sources.iter().map(|(_, r)| **r).sum::<usize>()
+        });
+        let concurrent = futures::executor::block_on(graph.execute_async(|vtx, sources| {
+            let data = vtx.get_data();
+            async move { data * 10 + sources.iter().map(|(_, r)| **r).sum::<usize>() }
+        }));
+        assert!(concurrent.len() == sequential.len());
+        assert!(concurrent.iter().all(|(ix, r)| **r == sequential[ix]));
+
+        // Futures may keep the results they were given
+        let kept = std::sync::Mutex::new(vec![]);
+        let results = futures::executor::block_on(graph.execute_async(|vtx, sources| {
+            kept.lock().unwrap().extend(sources);
+            let data = vtx.get_data();
+            async move { data }
+        }));
+        assert!(results.len() == 5 && kept.lock().unwrap().len() == 6);
+    }
+
+    #[test]
+    fn test_dirty_closure_covers_all_descendants_in_order() {
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        let dirty = graph.dirty_closure(&["source"]);
+        assert!(dirty.len() == 4);
+        assert!(dirty[0] == "source");
+        assert!(dirty[1] == "reference");
+        assert!(!dirty.contains(&"ultimate_source"));
+
+        let leaves = graph.dirty_closure(&["ref_reference", "new_reference", "missing"]);
+        assert!(leaves.len() == 2);
+        assert!(graph.dirty_closure(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_visitor_reports_every_event() {
+        use crate::edge::Edge;
+        use crate::visit::Visitor;
+
+        #[derive(Default)]
+        struct Recorder {
+            discovered: Vec<&'static str>,
+            finished: Vec<&'static str>,
+            examined: usize,
+            back_edges: Vec<Edge<&'static str>>,
+        }
+
+        impl Visitor<usize, &'static str> for Recorder {
+            fn discover_vertex(&mut self, vertex: &Vertex<usize, &'static str>) {
+                self.discovered.push(vertex.get_index());
+            }
+
+            fn finish_vertex(&mut self, vertex: &Vertex<usize, &'static str>) {
+                self.finished.push(vertex.get_index());
+            }
+
+            fn examine_edge(&mut self, _edge: &Edge<&'static str>) {
+                self.examined += 1;
+            }
+
+            fn back_edge(&mut self, edge: &Edge<&'static str>) {
+                self.back_edges.push(edge.clone());
+            }
+        }
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        let mut recorder = Recorder::default();
+        graph.visit_all(&mut recorder);
+        assert!(recorder.discovered.len() == 5);
+        assert!(recorder.finished.len() == 5);
+        assert!(recorder.examined == 6);
+        assert!(recorder.back_edges.is_empty());
+        assert!(*recorder.finished.last().unwrap() == "ultimate_source");
+
+        // Sneak a cycle in behind the graph's back to check it is reported
+        let mut cyclic = graph.clone();
+        cyclic
+            .get_vertex_mut("new_reference")
+            .unwrap()
+            .add_edge(&Edge::new("new_reference", "ultimate_source"));
+        let mut recorder = Recorder::default();
+        cyclic.visit(&"ultimate_source", &mut recorder);
+        assert!(recorder.back_edges == vec![Edge::new("new_reference", "ultimate_source")]);
+    }
+
+    #[test]
+    fn test_walkers_allow_mutation_between_steps() {
+        use crate::vertex::Direction;
+        use crate::walk::{Bfs, Dfs, Walker};
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        let mut bfs = Bfs::new("ultimate_source", Direction::Reference);
+        let mut order = vec![];
+        while let Some(ix) = bfs.walk_next(&graph) {
+            graph.get_vertex_mut(ix).unwrap().set_data(order.len());
+            order.push(ix);
+        }
+        assert!(order.len() == 5);
+        assert!(order[0] == "ultimate_source");
+        assert!(graph.get_vertex("ultimate_source").unwrap().get_data() == 0);
+
+        let mut dfs = Dfs::new("ref_reference", Direction::Source);
+        let mut ancestors = vec![];
+        while let Some(ix) = dfs.walk_next(&graph) {
+            ancestors.push(ix);
+        }
+        assert!(ancestors.len() == 4);
+        assert!(!ancestors.contains(&"new_reference"));
+
+        let mut missing = Dfs::new("missing", Direction::Reference);
+        assert!(missing.walk_next(&graph).is_none());
+    }
+
+    #[test]
+    fn test_collect_from_index_pairs_and_vertices() {
+        let graph: BullDag<usize, &str> = vec![
+            ("source", "reference"),
+            ("ultimate_source", "source"),
+            ("ultimate_source", "reference"),
+            ("reference", "ref_reference"),
+            ("reference", "new_reference"),
+            ("source", "new_reference"),
+            ("new_reference", "ultimate_source"),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(graph.len() == 5);
+        assert!(graph.n_edges() == 6);
+        assert!((&graph).into_iter().all(|vtx| vtx.get_data() == 0));
+
+        let indices: Vec<&str> = graph.iter().map(|vtx| vtx.get_index()).collect();
+        assert!(indices.len() == 5);
+
+        let vertices: BullDag<usize, &str> = graph.iter().cloned().collect();
+        assert!(vertices.len() == 5);
+
+        let mut total = 0;
+        for vtx in &graph {
+            total += vtx.n_references();
+        }
+        assert!(total == 6);
+    }
+
+    #[test]
+    fn test_extend_from_streamed_edges() {
+        use crate::edge::Edge;
+        use std::sync::mpsc;
+
+        let (tx, rx) = mpsc::channel();
+        let sender = std::thread::spawn(move || {
+            tx.send(Edge::new("source", "reference")).unwrap();
+            tx.send(Edge::new("ultimate_source", "source")).unwrap();
+            tx.send(Edge::new("reference", "ultimate_source")).unwrap();
+        });
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        graph.extend(rx);
+        sender.join().unwrap();
+        assert!(graph.len() == 3);
+        assert!(graph.n_edges() == 2);
+
+        let v1: Vertex<usize, &str> = Vertex::new(5, "ref_reference");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "new_reference");
+        let existing = graph.get_vertex("reference").unwrap().clone();
+        graph.extend(vec![(existing, v1.clone()), (v1, v2)]);
+        assert!(graph.len() == 5);
+        assert!(graph.n_edges() == 4);
+        assert!(graph.get_leaves().len() == 1);
+    }
+
+    #[test]
+    fn test_dag_macro_matches_extend_from_edges() {
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        let from_macro: BullDag<usize, &str> = crate::dag! {
+            data: {
+                "source" => 5,
+                "reference" => 4,
+                "ultimate_source" => 3,
+                "ref_reference" => 2,
+                "new_reference" => 1,
+            },
+            "source" => ["reference", "new_reference"],
+            "ultimate_source" => ["source", "reference"],
+            "reference" => ["ref_reference", "new_reference"],
+            "new_reference" => ["ultimate_source"],
+        };
+        assert_eq!(graph, from_macro);
+        assert!(from_macro.get_roots() == graph.get_roots());
+
+        let empty: BullDag<usize, &str> = crate::dag! {};
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_builder_matches_incremental_graph_and_reports_errors() {
+        use crate::builder::{BuildError, BullDagBuilder};
+        use crate::edge::Edge;
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        let mut builder: BullDagBuilder<usize, &str> = BullDagBuilder::new();
+        for vtx in [&v1, &v2, &v3, &v4, &v5] {
+            builder.add_vertex(vtx.clone());
+        }
+        for (source, reference) in edges.iter() {
+            builder.add_edge(source.get_index(), reference.get_index());
+        }
+        let built = builder.clone().build().unwrap();
+        assert_eq!(graph, built);
+        assert!(graph.get_roots() == built.get_roots());
+        assert!(graph.get_leaves() == built.get_leaves());
+
+        builder.add_edge("new_reference", "ultimate_source");
+        builder.add_edge("missing", "source");
+        let errors = builder.build().unwrap_err();
+        assert!(errors.len() == 2);
+        assert!(errors.contains(&BuildError::NonExistentSource(Edge::new(
+            "missing", "source"
+        ))));
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, BuildError::WouldCycle(v) if v.len() == 5)));
+    }
+
+    #[test]
+    fn test_add_edge_ix_reports_outcome() {
+        use crate::graph::GraphError;
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        graph.add_vertices(&[v1, v2, v3]);
+        assert!(graph.n_roots() == 3);
+
+        assert!(graph.add_edge_ix(&"ultimate_source", &"source").is_ok());
+        assert!(graph.add_edge_ix(&"source", &"reference").is_ok());
+        assert!(matches!(
+            graph.add_edge_ix(&"reference", &"ultimate_source"),
+            Err(GraphError::WouldCycle)
+        ));
+        assert!(matches!(
+            graph.add_edge_ix(&"reference", &"reference"),
+            Err(GraphError::WouldCycle)
+        ));
+        assert!(matches!(
+            graph.add_edge_ix(&"missing", &"reference"),
+            Err(GraphError::NonExistentSource)
+        ));
+
+        assert!(graph.n_edges() == 2);
+        assert!(graph.get_roots().len() == 1);
+        assert!(graph.get_leaves().len() == 1);
+        assert!(graph
+            .get_vertex("source")
+            .unwrap()
+            .is_source(&"ultimate_source"));
+    }
+
+    #[test]
+    fn test_entry_inserts_once_and_keeps_edges() {
+        use crate::entry::Entry;
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        for (source, reference) in [("source", "reference"), ("source", "new_reference")] {
+            *graph.entry(source).or_default() = 1;
+            graph.entry(reference).or_insert(2);
+            graph.add_edge_ix(&source, &reference).unwrap();
+        }
+
+        assert!(graph.len() == 3);
+        assert!(graph.n_roots() == 1);
+        assert!(graph.n_leaves() == 2);
+
+        graph.enable_journal();
+        graph.entry("reference").and_modify(|data| *data = 20);
+        graph.entry("missing").and_modify(|data| *data = 20);
+        assert!(graph.get_vertex("reference").unwrap().get_data() == 20);
+        assert!(graph.get_vertex("missing").is_none());
+
+        if let Entry::Occupied(mut entry) = graph.entry("new_reference") {
+            assert!(entry.insert(30) == 2);
+            assert!(*entry.get_mut() == 30);
+        }
+        assert!(graph.undo() && graph.undo());
+        assert!(graph.get_vertex("reference").unwrap().get_data() == 2);
+        assert!(graph.get_vertex("new_reference").unwrap().get_data() == 2);
+        graph.disable_journal();
+
+        match graph.entry("source") {
+            Entry::Occupied(entry) => {
+                assert!(entry.get().n_references() == 2);
+                assert!(entry.remove().get_index() == "source");
+            }
+            Entry::Vacant(_) => panic!("Vertex not found"),
+        }
+        assert!(graph.n_edges() == 0);
+        assert!(graph.n_roots() == 2);
+    }
+
+    #[test]
+    fn test_display_renders_tree_with_shared_markers() {
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let v6: Vertex<usize, &str> = Vertex::new(0, "lonely");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+        graph.add_vertex(&v6);
+
+        let expected = "\
+lonely
+ultimate_source
+├── reference
+│   ├── new_reference
+│   └── ref_reference
+└── source
+    ├── new_reference (*)
+    └── reference (*)
+";
+        assert_eq!(graph.to_string(), expected);
+        assert!(graph.render_ascii().starts_with("\"lonely\"\n"));
+    }
+
+    #[cfg(feature = "graphml")]
+    #[test]
+    fn test_graphml_roundtrip_and_rejects_bad_input() {
+        use crate::graph::GraphError;
+
+        let mut graph: BullDag<(usize, String), u64> = BullDag::new();
+        let v1 = Vertex::new((5, "<source & co>".to_string()), 50u64);
+        let v2 = Vertex::new((4, "reference".to_string()), 40u64);
+        let v3 = Vertex::new((3, "ultimate_source".to_string()), 30u64);
+        graph.extend_from_edges([(&v1, &v2), (&v3, &v1), (&v3, &v2)]);
+
+        let graphml = graph.to_graphml().unwrap();
+        assert!(graphml.contains("&lt;source &amp; co&gt;"));
+        let imported: BullDag<(usize, String), u64> = BullDag::from_graphml(&graphml).unwrap();
+        assert_eq!(graph, imported);
+        assert!(imported.get_roots() == graph.get_roots());
+
+        let cyclic = graphml.replace("</graph>", "<edge source=\"40\" target=\"30\"/></graph>");
+        assert!(matches!(
+            BullDag::<(usize, String), u64>::from_graphml(&cyclic),
+            Err(GraphError::WouldCycle)
+        ));
+
+        let dangling = graphml.replace("</graph>", "<edge source=\"40\" target=\"99\"/></graph>");
+        assert!(matches!(
+            BullDag::<(usize, String), u64>::from_graphml(&dangling),
+            Err(GraphError::NonExistentReference)
+        ));
+    }
+
+    #[test]
+    fn test_adjacency_json_is_compact_and_roundtrips() {
+        use crate::graph::GraphError;
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+        let v5: Vertex<usize, &str> = Vertex::new(1, "new_reference");
+        let edges = vec![
+            (&v1, &v2),
+            (&v3, &v1),
+            (&v3, &v2),
+            (&v2, &v4),
+            (&v2, &v5),
+            (&v1, &v5),
+        ];
+        graph.extend_from_edges(&edges);
+
+        let json = graph.to_adjacency_json().unwrap();
+        assert!(json.len() < serde_json::to_string(&graph).unwrap().len());
+
+        let parsed: BullDag<usize, &str> = BullDag::from_adjacency_json(&json).unwrap();
+        assert_eq!(graph, parsed);
+        assert!(parsed.get_roots() == graph.get_roots());
+        assert!(parsed.get_leaves() == graph.get_leaves());
+
+        let cyclic = r#"{"vertices":[{"index":"a","data":1},{"index":"b","data":2}],
+            "edges":[["a","b"],["b","a"]]}"#;
+        assert!(matches!(
+            BullDag::<usize, &str>::from_adjacency_json(cyclic),
+            Err(GraphError::WouldCycle)
+        ));
+        assert!(matches!(
+            BullDag::<usize, &str>::from_adjacency_json("{}"),
+            Err(GraphError::Other(_))
+        ));
+    }
+
+    #[test]
+    fn test_edge_csv_roundtrip_and_errors() {
+        use crate::graph::GraphError;
+
+        let csv = "ultimate_source, source\n\nultimate_source,reference\nsource,reference\nreference,ref_reference\n";
+        let graph: BullDag<usize, String> = BullDag::from_edge_csv(csv.as_bytes()).unwrap();
+        assert!(graph.len() == 4);
+        assert!(graph.n_edges() == 4);
+        assert!(graph.get_roots().contains("ultimate_source"));
+        assert!(graph.get_leaves().contains("ref_reference"));
+        assert!(graph.get_vertex("source".to_string()).unwrap().get_data() == 0);
+
+        let mut out = vec![];
+        graph.to_edge_csv(&mut out).unwrap();
+        let reparsed: BullDag<usize, String> = BullDag::from_edge_csv(out.as_slice()).unwrap();
+        assert_eq!(graph, reparsed);
+
+        assert!(matches!(
+            BullDag::<usize, u64>::from_edge_csv("1,2\n2,1\n".as_bytes()),
+            Err(GraphError::WouldCycle)
+        ));
+        assert!(matches!(
+            BullDag::<usize, u64>::from_edge_csv("1,x\n".as_bytes()),
+            Err(GraphError::Other(_))
+        ));
+        assert!(matches!(
+            BullDag::<usize, u64>::from_edge_csv("1,2,3\n".as_bytes()),
+            Err(GraphError::Other(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "binary")]
+    fn test_binary_roundtrip_is_smaller_than_serde() {
+        use crate::graph::GraphError;
+
+        let mut graph: BullDag<u64, u64> = BullDag::new();
+        let vertices: Vec<Vertex<u64, u64>> = (0..100).map(|i| Vertex::new(i * 2, i)).collect();
+        let edges: Vec<_> = vertices.windows(2).map(|w| (&w[0], &w[1])).collect();
+        graph.extend_from_edges(&edges);
+
+        let bytes = graph.to_bytes().unwrap();
+        assert!(bytes.len() < serde_json::to_vec(&graph).unwrap().len());
+
+        let decoded: BullDag<u64, u64> = BullDag::from_bytes(&bytes).unwrap();
+        assert_eq!(graph, decoded);
+        assert!(decoded.get_roots() == graph.get_roots());
+        assert!(decoded.get_leaves() == graph.get_leaves());
+
+        assert!(matches!(
+            BullDag::<u64, u64>::from_bytes(&bytes[1..]),
+            Err(GraphError::Other(_))
+        ));
+        assert!(matches!(
+            BullDag::<u64, u64>::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(GraphError::Other(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(all(feature = "msgpack", feature = "cbor"))]
+    fn test_msgpack_and_cbor_roundtrip() {
+        use crate::graph::GraphError;
+
+        let mut graph: BullDag<usize, String> = BullDag::new();
+        let v1: Vertex<usize, String> = Vertex::new(5, "source".to_string());
+        let v2: Vertex<usize, String> = Vertex::new(4, "reference".to_string());
+        let v3: Vertex<usize, String> = Vertex::new(3, "ultimate_source".to_string());
+        let v4: Vertex<usize, String> = Vertex::new(2, "ref_reference".to_string());
+        let edges = vec![(&v1, &v2), (&v3, &v1), (&v3, &v2), (&v2, &v4)];
+        graph.extend_from_edges(&edges);
+
+        let msgpack = graph.to_msgpack().unwrap();
+        let decoded: BullDag<usize, String> = BullDag::from_msgpack(&msgpack).unwrap();
+        assert_eq!(graph, decoded);
+
+        let cbor = graph.to_cbor().unwrap();
+        let decoded: BullDag<usize, String> = BullDag::from_cbor(&cbor).unwrap();
+        assert_eq!(graph, decoded);
+
+        assert!(matches!(
+            BullDag::<usize, String>::from_msgpack(&cbor),
+            Err(GraphError::Other(_))
+        ));
+        assert!(matches!(
+            BullDag::<usize, String>::from_cbor(&msgpack),
+            Err(GraphError::Other(_))
+        ));
+
+        // Both are checked for cycles and dangling edges like the JSON format
+        let mut list = crate::interop::AdjacencyList::from(&graph);
+        list.edges
+            .push(("ref_reference".to_string(), "source".to_string()));
+        let cyclic = rmp_serde::to_vec(&list).unwrap();
+        assert!(matches!(
+            BullDag::<usize, String>::from_msgpack(&cyclic),
+            Err(GraphError::WouldCycle)
+        ));
+        list.edges.pop();
+        list.edges
+            .push(("source".to_string(), "missing".to_string()));
+        let mut dangling = vec![];
+        ciborium::into_writer(&list, &mut dangling).unwrap();
+        assert!(matches!(
+            BullDag::<usize, String>::from_cbor(&dangling),
+            Err(GraphError::NonExistentReference)
+        ));
+    }
+
+    #[test]
+    fn test_stream_serialization_roundtrip() {
+        use crate::graph::GraphError;
+
+        let mut graph: BullDag<usize, u64> = BullDag::new();
+        let vertices: Vec<Vertex<usize, u64>> =
+            (0..50).map(|i| Vertex::new(i as usize, i)).collect();
+        let edges: Vec<_> = vertices.windows(2).map(|w| (&w[0], &w[1])).collect();
+        graph.extend_from_edges(&edges);
+        graph.add_vertex(&Vertex::new(100, 100));
+
+        let mut buf = vec![];
+        graph.serialize_stream(&mut buf).unwrap();
+        assert!(buf.iter().filter(|b| **b == b'\n').count() == 51 + 49);
+
+        let read: BullDag<usize, u64> = BullDag::deserialize_stream(buf.as_slice()).unwrap();
+        assert_eq!(graph, read);
+        assert!(read.get_roots() == graph.get_roots());
+        assert!(read.get_leaves() == graph.get_leaves());
+
+        let cyclic = "{\"vertex\":{\"index\":1,\"data\":0}}\n{\"vertex\":{\"index\":2,\"data\":0}}\n{\"edge\":[1,2]}\n{\"edge\":[2,1]}\n";
+        assert!(matches!(
+            BullDag::<usize, u64>::deserialize_stream(cyclic.as_bytes()),
+            Err(GraphError::WouldCycle)
+        ));
+        assert!(matches!(
+            BullDag::<usize, u64>::deserialize_stream("{\"edge\":[1,2]}".as_bytes()),
+            Err(GraphError::NonExistentSource)
+        ));
+    }
+
+    #[test]
+    fn test_validate_catches_inconsistent_payloads() {
+        use crate::edge::Edge;
+        use crate::validate::{CheckedError, GraphViolation};
+
+        let mut graph: BullDag<usize, String> = BullDag::new();
+        let v1: Vertex<usize, String> = Vertex::new(5, "source".to_string());
+        let v2: Vertex<usize, String> = Vertex::new(4, "reference".to_string());
+        graph.add_edge((&v1, &v2));
+        assert!(graph.validate().is_ok());
+
+        let json = serde_json::to_string(&graph).unwrap();
+        let checked: BullDag<usize, String> = BullDag::from_json_checked(&json).unwrap();
+        assert_eq!(graph, checked);
+        assert!(BullDag::<usize, String>::from_reader_checked(json.as_bytes()).is_ok());
+
+        let mut value = serde_json::to_value(&graph).unwrap();
+        value["edges"] = serde_json::json!([]);
+        let unrecorded = Edge::new("source".to_string(), "reference".to_string());
+        match BullDag::<usize, String>::from_json_checked(&value.to_string()) {
+            Err(CheckedError::Invalid(violations)) => {
+                assert_eq!(violations, vec![GraphViolation::UnrecordedEdge(unrecorded)])
+            }
+            other => panic!("expected a violation, got {:?}", other),
+        }
+
+        let mut cyclic = graph.clone();
+        let back = Edge::new("reference".to_string(), "source".to_string());
+        cyclic
+            .get_vertex_mut("reference".to_string())
+            .unwrap()
+            .add_edge(&back);
+        cyclic
+            .get_vertex_mut("source".to_string())
+            .unwrap()
+            .add_edge(&back);
+        cyclic.edges.insert(back);
+        let violations = cyclic.validate().unwrap_err();
+        assert!(violations.contains(&GraphViolation::RootMismatch("source".to_string())));
+        assert!(violations.contains(&GraphViolation::LeafMismatch("reference".to_string())));
+        assert!(violations
+            .iter()
+            .any(|v| matches!(v, GraphViolation::Cycle(ixs) if ixs.len() == 2)));
+
+        assert!(matches!(
+            BullDag::<usize, String>::from_json_checked("{"),
+            Err(CheckedError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_catches_tags_of_missing_vertices_and_edges() {
+        use crate::edge::Edge;
+        use crate::validate::{CheckedError, GraphViolation};
+
+        let mut graph: BullDag<usize, String> = BullDag::new();
+        let v1: Vertex<usize, String> = Vertex::new(5, "source".to_string());
+        let v2: Vertex<usize, String> = Vertex::new(4, "reference".to_string());
+        let v3: Vertex<usize, String> = Vertex::new(3, "other".to_string());
+        graph.enable_multi_edges();
+        graph.extend_from_edges([(&v1, &v2), (&v1, &v3)]);
+        graph.set_epoch(&"source".to_string(), 1);
+        graph.add_conflict(&"reference".to_string(), &"other".to_string());
+        graph.add_edge_of_kind((&v1, &v2), 1).unwrap();
+        assert!(graph.validate().is_ok());
+
+        let ix = |s: &str| s.to_string();
+        let ghost = Edge::new(ix("reference"), ix("source"));
+        let violations = |value: &serde_json::Value| {
+            let json = value.to_string();
+            match BullDag::<usize, String>::from_json_checked(&json) {
+                Err(CheckedError::Invalid(violations)) => violations,
+                other => panic!("expected a violation, got {:?}", other),
+            }
+        };
+        let value = serde_json::to_value(&graph).unwrap();
+
+        let mut payload = value.clone();
+        payload["sequence"]["ghost"] = 9.into();
+        assert_eq!(
+            violations(&payload),
+            vec![GraphViolation::DanglingSequence(ix("ghost"))]
+        );
+
+        let mut payload = value.clone();
+        payload["epochs"]["ghost"] = 1.into();
+        assert_eq!(
+            violations(&payload),
+            vec![GraphViolation::DanglingEpoch(ix("ghost"))]
+        );
+
+        let mut payload = value.clone();
+        payload["conflicts"]["ghost"] = serde_json::json!(["other"]);
+        payload["conflicts"]["other"] = serde_json::json!(["reference", "ghost"]);
+        let found = violations(&payload);
+        assert!(found.len() == 2);
+        assert!(found.contains(&GraphViolation::DanglingConflict {
+            a: ix("ghost"),
+            b: ix("other")
+        }));
+        assert!(found.contains(&GraphViolation::DanglingConflict {
+            a: ix("other"),
+            b: ix("ghost")
+        }));
+
+        let mut payload = value.clone();
+        payload["conflicts"]["other"] = serde_json::json!([]);
+        assert_eq!(
+            violations(&payload),
+            vec![GraphViolation::AsymmetricConflict {
+                a: ix("reference"),
+                b: ix("other")
+            }]
+        );
+
+        let mut payload = value.clone();
+        payload["edge_kinds"]["reference"] = serde_json::json!({ "source": 2 });
+        assert_eq!(
+            violations(&payload),
+            vec![GraphViolation::DanglingEdgeKind(ghost.clone())]
+        );
+
+        let mut payload = value.clone();
+        payload["edge_times"] = serde_json::json!({ "reference": { "source": 60 } });
+        assert_eq!(
+            violations(&payload),
+            vec![GraphViolation::DanglingEdgeTime(ghost.clone())]
+        );
+
+        let mut payload = value.clone();
+        payload["edge_weights"] = serde_json::json!({ "reference": { "source": 3 } });
+        assert_eq!(
+            violations(&payload),
+            vec![GraphViolation::DanglingEdgeWeight(ghost.clone())]
+        );
+
+        let mut payload = value.clone();
+        payload["edge_keys"]["reference"] = serde_json::json!({ "source": [0] });
+        assert_eq!(
+            violations(&payload),
+            vec![GraphViolation::DanglingEdgeKeys(ghost.clone())]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "wasm")]
+    fn test_wasm_wrapper_builds_and_queries() {
+        use crate::wasm::JsBullDag;
+
+        let mut graph = JsBullDag::new();
+        graph.add_vertex("a".to_string(), "1").unwrap();
+        graph.add_vertex("b".to_string(), r#"{"x":2}"#).unwrap();
+        graph.add_vertex("c".to_string(), "null").unwrap();
+        assert!(graph.add_vertex("d".to_string(), "{").is_err());
+
+        graph.add_edge("a".to_string(), "b".to_string()).unwrap();
+        graph.add_edge("b".to_string(), "c".to_string()).unwrap();
+        assert!(graph.add_edge("c".to_string(), "a".to_string()).is_err());
+        assert!(graph.add_edge("a".to_string(), "d".to_string()).is_err());
+
+        assert!(graph.len() == 3 && graph.n_edges() == 2);
+        assert_eq!(graph.roots(), vec!["a".to_string()]);
+        assert_eq!(graph.leaves(), vec!["c".to_string()]);
+        assert_eq!(graph.references("a".to_string()), vec!["b".to_string()]);
+        assert_eq!(
+            graph.descendants("a".to_string()),
+            vec!["b".to_string(), "c".to_string()]
+        );
+        assert_eq!(graph.ancestors("a".to_string()), Vec::<String>::new());
+        assert_eq!(graph.get_data("b".to_string()).unwrap(), r#"{"x":2}"#);
+
+        let copy = JsBullDag::from_json(&graph.to_json().unwrap()).unwrap();
+        assert_eq!(copy.descendants("a".to_string()).len(), 2);
+    }
+
+    #[test]
+    fn test_shared_dag_concurrent_readers_and_writer() {
+        use crate::concurrent::SharedBullDag;
+        use crate::graph::GraphError;
+        use crate::vertex::Direction;
+
+        let shared: SharedBullDag<usize, u64> = SharedBullDag::with_shards(4);
+        shared.add_vertex(&Vertex::new(0, 0));
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for i in 1..200u64 {
+                    shared.add_vertex(&Vertex::new(i as usize, i));
+                    shared.add_edge_ix(&(i - 1), &i).unwrap();
+                }
+            });
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..200 {
+                        let reachable = shared.trace(&0, Direction::Reference);
+                        assert!(!reachable.is_empty());
+                        assert!(shared.get_roots().contains(&0));
+                    }
+                });
+            }
+        });
+
+        assert!(shared.len() == 200);
+        assert!(shared.n_edges() == 199);
+        assert!(shared.get_leaves() == [199].into_iter().collect());
+        assert!(shared.trace(&199, Direction::Source).len() == 200);
+        assert!(matches!(
+            shared.add_edge_ix(&199, &0),
+            Err(GraphError::WouldCycle)
+        ));
+        assert!(matches!(
+            shared.add_edge_ix(&0, &500),
+            Err(GraphError::NonExistentReference)
+        ));
+
+        let dag = shared.to_dag();
+        assert!(dag.len() == 200 && dag.n_edges() == 199);
+        let roundtrip: SharedBullDag<usize, u64> = dag.clone().into();
+        assert_eq!(roundtrip.to_dag(), dag);
+
+        // Edges can be given in any of the forms `BullDag::add_edge` takes
+        shared.add_edge((Vertex::new(0, 300), Vertex::new(0, 301)));
+        shared.add_edge((199, 300));
+        shared.add_edge((&0, &301));
+        shared.add_edge((301, 0));
+        shared.add_edge((0, 999));
+        assert!(shared.len() == 202 && shared.n_edges() == 202);
+        assert!(shared.get_leaves() == [301].into_iter().collect());
+    }
+
+    #[test]
+    fn test_snapshot_is_isolated_from_writer() {
+        let mut graph: BullDag<usize, u64> = BullDag::new();
+        let vertices: Vec<Vertex<usize, u64>> =
+            (0..10).map(|i| Vertex::new(i as usize, i)).collect();
+        let edges: Vec<_> = vertices.windows(2).map(|w| (&w[0], &w[1])).collect();
+        graph.extend_from_edges(&edges);
+
+        let snapshot = graph.snapshot();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                let reader = snapshot.clone();
+                s.spawn(move || {
+                    assert!(reader.len() == 10);
+                    assert!(reader.get_leaves() == [9].into_iter().collect());
+                });
+            }
+            let last = Vertex::new(10, 10);
+            graph.add_edge((&vertices[9], &last));
+        });
+
+        assert!(graph.len() == 11);
+        assert!(snapshot.len() == 10 && snapshot.n_edges() == 9);
+        assert!(snapshot.n_readers() == 1);
+        let mut restored = snapshot.into_dag();
+        restored.add_vertex(&Vertex::new(0, 11));
+        assert!(restored.len() == 11);
+
+        // Only the vertices and edges are copied, not the checkpoints or
+        // history kept alongside them
+        graph.checkpoint("before");
+        graph.enable_journal();
+        graph.add_vertex(&Vertex::new(0, 12));
+        let snapshot = graph.snapshot();
+        assert!(snapshot.len() == 12);
+        assert!(snapshot.checkpoint_labels().is_empty());
+        assert!(!snapshot.can_undo());
+    }
+
+    #[test]
+    fn test_observers_are_notified_of_mutations() {
+        use crate::edge::Edge;
+        use crate::graph::GraphError;
+        use crate::observe::GraphObserver;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Log(Mutex<Vec<String>>);
+
+        impl GraphObserver<usize, &'static str> for Arc<Log> {
+            fn vertex_added(&self, vertex: &Vertex<usize, &'static str>) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("+{}", vertex.get_index()));
+            }
+            fn vertex_removed(&self, vertex: &Vertex<usize, &'static str>) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("-{}", vertex.get_index()));
+            }
+        }
+
+        let log = Arc::new(Log::default());
+        let rejected = Arc::new(Mutex::new(vec![]));
+        let edges = Arc::new(Mutex::new(vec![]));
+
+        let mut graph: BullDag<usize, &'static str> = BullDag::new();
+        graph.add_observer(log.clone());
+        let r = rejected.clone();
+        graph.on_edge_rejected(move |e: &Edge<&str>, reason: &GraphError| {
+            r.lock()
+                .unwrap()
+                .push((e.clone(), matches!(reason, GraphError::WouldCycle)))
+        });
+        let a = edges.clone();
+        graph.on_edge_added(move |e: &Edge<&str>| a.lock().unwrap().push(e.clone()));
+
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        graph.add_edge((&v1, &v2));
+        graph.add_edge((&v1, &v2));
+        graph.add_edge((&v2, &v1));
+        assert!(graph.add_edge_ix(&"source", &"missing").is_err());
+        graph.remove_vertex("reference");
+
+        assert_eq!(
+            *log.0.lock().unwrap(),
+            vec!["+source", "+reference", "-reference"]
+        );
+        assert_eq!(
+            *edges.lock().unwrap(),
+            vec![Edge::new("source", "reference")]
+        );
+        assert_eq!(
+            *rejected.lock().unwrap(),
+            vec![
+                (Edge::new("reference", "source"), true),
+                (Edge::new("source", "missing"), false)
+            ]
+        );
+
+        let mut clone = graph.clone();
+        clone.add_vertex(&Vertex::new(1, "unobserved"));
+        assert!(log.0.lock().unwrap().len() == 3);
+    }
+
+    #[test]
+    #[cfg(feature = "tokio")]
+    fn test_subscribe_broadcasts_graph_events() {
+        use crate::edge::Edge;
+        use crate::graph::GraphError;
+        use crate::observe::GraphEvent;
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let mut events = graph.subscribe();
+        let mut late = graph.subscribe_with_capacity(1);
+
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        graph.add_edge((&v1, &v2));
+        graph.add_edge((&v2, &v1));
+        graph.prune_below_depth(0);
+
+        let mut received = vec![];
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert!(matches!(
+            received.as_slice(),
+            [
+                GraphEvent::VertexAdded("source"),
+                GraphEvent::VertexAdded("reference"),
+                GraphEvent::EdgeAdded(_),
+                GraphEvent::EdgeRejected {
+                    reason: GraphError::WouldCycle,
+                    ..
+                },
+                GraphEvent::EdgeRemoved(_),
+                GraphEvent::Pruned("source"),
+            ]
+        ));
+        if let GraphEvent::EdgeAdded(e) = &received[2] {
+            assert_eq!(*e, Edge::new("source", "reference"));
+        }
+
+        assert!(late.try_recv().is_err());
+        assert!(matches!(late.try_recv(), Ok(GraphEvent::Pruned("source"))));
+    }
+
+    #[test]
+    #[cfg(feature = "tokio")]
+    fn test_graph_service_serves_concurrent_handles() {
+        use crate::graph::GraphError;
+        use crate::service::GraphService;
+        use crate::vertex::Direction;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let graph = runtime.block_on(async {
+            let (handle, task) = GraphService::spawn(BullDag::<usize, u64>::new(), 8);
+            handle.add_vertex(Vertex::new(0, 0)).await.unwrap();
+
+            let writers: Vec<_> = (0..4u64)
+                .map(|w| {
+                    let handle = handle.clone();
+                    tokio::spawn(async move {
+                        for i in 1..=10 {
+                            let ix = w * 10 + i;
+                            handle
+                                .add_edge(Vertex::new(0, 0), Vertex::new(ix as usize, ix))
+                                .await
+                                .unwrap();
+                        }
+                    })
+                })
+                .collect();
+            for writer in writers {
+                writer.await.unwrap();
+            }
+
+            assert!(matches!(
+                handle.add_edge(Vertex::new(1, 1), Vertex::new(0, 0)).await,
+                Err(GraphError::WouldCycle)
+            ));
+            assert!(handle.trace(0, Direction::Reference).await.unwrap().len() == 41);
+            assert!(matches!(
+                handle.trace(100, Direction::Reference).await,
+                Err(GraphError::NonExistentVertex)
+            ));
+            let order = handle.topological_sort().await.unwrap();
+            assert!(order.len() == 41 && order[0] == 0);
+            assert!(handle.get_vertex(7).await.unwrap().unwrap().get_data() == 7);
+
+            drop(handle);
+            task.await.unwrap()
+        });
+
+        assert!(graph.len() == 41);
+        assert!(graph.n_edges() == 40);
+    }
+
+    #[test]
+    fn test_undo_redo_restores_graph_states() {
+        use crate::edge::Edge;
+
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        let v3: Vertex<usize, &str> = Vertex::new(3, "ultimate_source");
+        graph.add_edge((&v1, &v2));
+        assert!(!graph.undo());
+
+        graph.enable_journal();
+        let mut states = vec![graph.clone()];
+        graph.add_vertex(&v3);
+        states.push(graph.clone());
+        graph.add_edge((&v3, &v1));
+        states.push(graph.clone());
+        graph.add_edge((&v2, &v3));
+        graph.remove_edge(&Edge::new("source", "reference"));
+        states.push(graph.clone());
+        graph.remove_vertex("source");
+        states.push(graph.clone());
+
+        for state in states.iter().rev().skip(1) {
+            assert!(graph.undo());
+            assert_eq!(&graph, state);
+            assert!(graph.get_roots() == state.get_roots());
+            assert!(graph.get_leaves() == state.get_leaves());
+        }
+        assert!(!graph.can_undo() && !graph.undo());
+
+        for state in states.iter().skip(1) {
+            assert!(graph.redo());
+            assert_eq!(&graph, state);
+        }
+        assert!(!graph.redo());
+
+        graph.undo();
+        graph.add_vertex(&Vertex::new(1, "new"));
+        assert!(!graph.can_redo());
+        graph.disable_journal();
+        assert!(!graph.can_undo());
+    }
+
+    #[test]
+    fn test_op_log_replays_into_equal_graph() {
+        use crate::edge::Edge;
+        use crate::journal::GraphOp;
+        use std::io::Write;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for SharedBuf {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buf = SharedBuf::default();
+        let ops = Arc::new(Mutex::new(vec![]));
+        let sink = ops.clone();
+
+        let mut graph: BullDag<usize, String> = BullDag::new();
+        graph.log_ops_to(buf.clone());
+        graph.log_ops(move |op| sink.lock().unwrap().push(op));
+
+        let v: Vec<Vertex<usize, String>> = (0..5).map(|i| Vertex::new(i, i.to_string())).collect();
+        graph.extend_from_edges([(&v[0], &v[1]), (&v[1], &v[2]), (&v[0], &v[3])]);
+        graph.add_edge((&v[2], &v[0]));
+        graph.add_vertex(&v[4]);
+        graph.remove_edge(&Edge::new("0".to_string(), "3".to_string()));
+        graph.remove_vertex("1".to_string());
+
+        let ops = ops.lock().unwrap().clone();
+        assert!(ops.contains(&GraphOp::RemoveVertex {
+            index: "1".to_string()
+        }));
+        assert_eq!(BullDag::replay(ops).unwrap(), graph);
+
+        let mut log = buf.0.lock().unwrap().clone();
+        assert_eq!(
+            BullDag::<usize, String>::replay_log(log.as_slice()).unwrap(),
+            graph
+        );
+        log.extend_from_slice(b"{\"add_vertex\":{\"ind");
+        assert_eq!(
+            BullDag::<usize, String>::replay_log(log.as_slice()).unwrap(),
+            graph
+        );
+
+        assert_eq!(BullDag::replay(graph.to_ops()).unwrap(), graph);
+    }
+
+    #[test]
+    fn test_ops_keep_tags_and_insertion_order() {
+        use crate::edge::Edge;
+        use crate::journal::GraphOp;
+        use std::sync::{Arc, Mutex};
+
+        let ops = Arc::new(Mutex::new(vec![]));
+        let sink = ops.clone();
+        let mut graph: BullDag<usize, usize> = BullDag::new();
+        graph.log_ops(move |op| sink.lock().unwrap().push(op));
+
+        // Inserted out of index order, with a gap left by a removed vertex
+        graph.enable_multi_edges();
+        for ix in [4, 9, 2, 7, 0, 5] {
+            graph.add_vertex(&Vertex::new(ix, ix));
+        }
+        graph.remove_vertex(7);
+        graph.add_edge_of_kind((&4, &9), 2).unwrap();
+        graph.add_edge_at((&4, &2), 30).unwrap();
+        graph.add_edge_weighted((&9, &0), 8).unwrap();
+        graph.add_keyed_edge((&2, &0), 3).unwrap();
+        graph.add_keyed_edge((&2, &0), 6).unwrap();
+        graph.add_keyed_edge((&2, &5), 1).unwrap();
+        graph.remove_keyed_edge(&Edge::new(2, 0), 3);
+        graph.set_edge_kind(&Edge::new(2, 5), 1);
+        graph.set_epoch(&9, 4);
+        graph.add_conflict(&9, &2);
+        graph.add_conflict(&0, &5);
+        graph.add_conflict(&4, &5);
+        graph.remove_conflict(&5, &4);
+
+        let same = |other: &BullDag<usize, usize>| {
+            assert_eq!(other, &graph);
+            assert!(other.multi_edges_enabled());
+            for edge in graph.edges.iter() {
+                assert_eq!(other.edge_kind(edge), graph.edge_kind(edge));
+                assert_eq!(other.edge_time(edge), graph.edge_time(edge));
+                assert_eq!(other.edge_weight(edge), graph.edge_weight(edge));
+                assert_eq!(other.edge_keys(edge), graph.edge_keys(edge));
+            }
+            for ix in graph.vertices.keys() {
+                assert_eq!(other.epoch_of(ix), graph.epoch_of(ix));
+                assert_eq!(other.conflicts_of(ix), graph.conflicts_of(ix));
+            }
+            assert_eq!(
+                other.topological_sort_stable(),
+                graph.topological_sort_stable()
+            );
+        };
+
+        let logged = ops.lock().unwrap().clone();
+        assert!(logged.contains(&GraphOp::SetEdgeKeys {
+            source: 2,
+            reference: 0,
+            keys: [6].into(),
+        }));
+        same(&BullDag::replay(logged).unwrap());
+
+        let snapshot = graph.to_ops();
+        let added: Vec<usize> = snapshot
+            .iter()
+            .filter_map(|op| match op {
+                GraphOp::AddVertex { index, .. } => Some(*index),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(added, vec![4, 9, 2, 0, 5]);
+        same(&BullDag::replay(snapshot).unwrap());
+    }
+
+    #[test]
+    fn test_restore_checkpoint_after_failed_reorg() {
+        let mut graph: BullDag<usize, u64> = BullDag::new();
+        let chain: Vec<Vertex<usize, u64>> = (0..10).map(|i| Vertex::new(i as usize, i)).collect();
+        for pair in chain[..6].windows(2) {
+            graph.add_edge((&pair[0], &pair[1]));
+        }
+        graph.enable_journal();
+        graph.checkpoint("epoch-5");
+        let expected = graph.clone();
+
+        graph.remove_vertex(5);
+        graph.remove_vertex(4);
+        for pair in chain[3..].windows(2) {
+            graph.add_edge((&pair[0], &pair[1]));
+        }
+        assert_eq!(graph.len(), 10);
+        assert_eq!(graph.get_checkpoint("epoch-5"), Some(&expected));
+
+        assert!(graph.restore("epoch-5"));
+        assert_eq!(graph, expected);
+        assert!(!graph.can_undo());
+        assert_eq!(graph.checkpoint_labels(), vec!["epoch-5"]);
+
+        assert!(!graph.restore("epoch-6"));
+        assert!(graph.remove_checkpoint("epoch-5").is_some());
+        assert!(!graph.restore("epoch-5"));
+    }
+
+    #[test]
+    fn test_ancestors_at_earlier_versions() {
+        use crate::vertex::Direction;
+
+        let mut graph: BullDag<u64, u64> = BullDag::new();
+        let genesis = Vertex::new(0, 0);
+        graph.add_vertex(&genesis);
+        graph.enable_history();
+
+        // One block per height, each referencing the one before it.
+        for height in 1..=5 {
+            graph.set_version(height);
+            graph.add_edge((
+                &Vertex::new(height - 1, height - 1),
+                &Vertex::new(height, height),
+            ));
+        }
+        graph.next_version();
+        graph.remove_vertex(0);
+        graph.add_edge((&Vertex::new(2, 2), &Vertex::new(60, 60)));
+
+        assert_eq!(graph.version(), 6);
+        let at_3 = graph.at_version(3).unwrap();
+        let mut ancestors = at_3.trace(&3, Direction::Source);
+        ancestors.sort();
+        assert_eq!(ancestors, vec![0, 1, 2, 3]);
+        assert_eq!(at_3.len(), 4);
+        assert_eq!(at_3.n_edges(), 3);
+        assert!(at_3.get_vertex(&4).is_none());
+        assert_eq!(at_3.get_vertex(&2).unwrap().get_references(), vec![&3]);
+
+        let at_6 = graph.at_version(6).unwrap();
+        assert!(!at_6.contains_vertex(&0));
+        assert_eq!(at_6.trace(&1, Direction::Source), vec![1]);
+        assert_eq!(at_6.get_vertex(&2).unwrap().n_references(), 2);
+        assert_eq!(at_6.len(), graph.len());
+        assert_eq!(at_6.n_edges(), graph.n_edges());
+
+        assert!(!graph.set_version(2));
+        assert!(graph.at_version(0).unwrap().contains_vertex(&0));
+        assert!(BullDag::<u64, u64>::new().at_version(0).is_none());
+    }
+
+    #[test]
+    fn test_persistent_dag_reads_payloads_through_store() {
+        use crate::edge::Edge;
+        use crate::graph::GraphError;
+        use crate::store::{GraphStore, MemoryStore, PersistentBullDag};
+        use crate::vertex::Direction;
+        use std::cell::Cell;
+
+        // Counts payload reads and refuses edges out of vertex 0.
+        #[derive(Default)]
+        struct Audited {
+            inner: MemoryStore<String, u64>,
+            reads: Cell<usize>,
+        }
+
+        impl GraphStore<String, u64> for Audited {
+            fn get_vertex(&self, index: &u64) -> Result<Option<String>, GraphError> {
+                self.reads.set(self.reads.get() + 1);
+                self.inner.get_vertex(index)
+            }
+            fn put_vertex(&mut self, index: &u64, data: &String) -> Result<(), GraphError> {
+                self.inner.put_vertex(index, data)
+            }
+            fn put_edge(&mut self, edge: &Edge<u64>) -> Result<(), GraphError> {
+                match edge.get_source() {
+                    0 => Err(GraphError::other("read only")),
+                    _ => self.inner.put_edge(edge),
+                }
+            }
+            fn vertices(&self) -> Box<dyn Iterator<Item = Result<u64, GraphError>> + '_> {
+                self.inner.vertices()
+            }
+            fn edges(&self) -> Box<dyn Iterator<Item = Result<Edge<u64>, GraphError>> + '_> {
+                self.inner.edges()
+            }
+        }
+
+        let mut graph = PersistentBullDag::new(Audited::default());
+        for i in 0..50u64 {
+            graph.add_vertex(&i, &format!("block {i}")).unwrap();
+        }
+        for i in 1..49u64 {
+            graph.add_edge_ix(&i, &(i + 1)).unwrap();
+        }
+        assert!(matches!(
+            graph.add_edge_ix(&0, &1),
+            Err(GraphError::Other(_))
+        ));
+        assert!(matches!(
+            graph.add_edge_ix(&49, &1),
+            Err(GraphError::WouldCycle)
+        ));
+        assert_eq!(graph.n_edges(), 48);
+        assert!(graph.get_roots().contains(&0));
+
+        assert_eq!(graph.trace(&1, Direction::Reference).len(), 49);
+        assert_eq!(graph.store().reads.get(), 0);
+
+        let vertex = graph.get_vertex(&2).unwrap().unwrap();
+        assert_eq!(vertex.get_data(), "block 2");
+        assert_eq!(vertex.get_sources(), vec![&1]);
+        assert_eq!(graph.get_data(&50).unwrap(), None);
+        assert_eq!(graph.store().reads.get(), 1);
+
+        let dag = graph.to_dag().unwrap();
+        let reopened = PersistentBullDag::open(graph.into_store()).unwrap();
+        assert_eq!(reopened.structure().n_edges(), 48);
+        assert_eq!(reopened.to_dag().unwrap(), dag);
+    }
+
+    #[cfg(feature = "sled")]
+    #[test]
+    fn test_sled_dag_survives_reopening() {
+        use crate::store::{PersistentBullDag, SledStore};
+        use crate::vertex::Direction;
+
+        // sled releases its lock on the files in the background, so the
+        // database is kept open and only the store and graph are reopened.
+        let path = std::env::temp_dir().join(format!("bulldag-sled-{}", std::process::id()));
+        let db = sled::open(&path).unwrap();
+        {
+            let store: SledStore<String, u64> = SledStore::from_db(&db).unwrap();
+            let mut graph = PersistentBullDag::new(store);
+            for i in 0..20u64 {
+                graph.add_vertex(&i, &format!("block {i}")).unwrap();
+            }
+            for i in 0..19u64 {
+                graph.add_edge_ix(&i, &(i + 1)).unwrap();
+            }
+            graph.store().flush().unwrap();
+        }
+
+        let store: SledStore<String, u64> = SledStore::from_db(&db).unwrap();
+        let graph = PersistentBullDag::open(store).unwrap();
+        assert_eq!(graph.len(), 20);
+        assert_eq!(graph.n_edges(), 19);
+        assert_eq!(graph.trace(&10, Direction::Source).len(), 11);
+        assert_eq!(graph.get_data(&7).unwrap(), Some("block 7".to_string()));
+        assert_eq!(
+            graph.get_vertex(&7).unwrap().unwrap().get_sources(),
+            vec![&6]
+        );
+
+        drop((graph, db));
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn test_lazy_payloads_load_once_on_demand() {
+        use crate::graph::GraphError;
+        use crate::payload::{Payload, PayloadLoader};
+        use crate::store::{GraphStore, MemoryStore};
+        use crate::vertex::Direction;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        struct Counting {
+            store: MemoryStore<Vec<u8>, u64>,
+            loads: AtomicUsize,
+        }
+
+        impl PayloadLoader<Vec<u8>> for Counting {
+            type Key = u64;
+
+            fn load(&self, key: &u64) -> Result<Vec<u8>, GraphError> {
+                self.loads.fetch_add(1, Ordering::SeqCst);
+                self.store.load(key)
+            }
+        }
+
+        let mut store = MemoryStore::new();
+        for i in 1..100u64 {
+            store.put_vertex(&i, &vec![i as u8; 1024]).unwrap();
+        }
+        let loader = Arc::new(Counting {
+            store,
+            loads: AtomicUsize::new(0),
+        });
+
+        let mut graph: BullDag<Payload<Vec<u8>>, u64> = BullDag::new();
+        let vertices: Vec<Vertex<Payload<Vec<u8>>, u64>> = (0..=100u64)
+            .map(|i| match i {
+                0 => Vertex::new(Payload::inline(vec![]), i),
+                _ => Vertex::new(Payload::lazy(loader.clone(), i), i),
+            })
+            .collect();
+        for pair in vertices.windows(2) {
+            graph.add_edge((&pair[0], &pair[1]));
+        }
+
+        let tip = graph.get_vertex(100).unwrap();
+        assert_eq!(graph.trace(tip, Direction::Source).len(), 101);
+        assert_eq!(loader.loads.load(Ordering::SeqCst), 0);
+
+        let vertex = graph.get_vertex(42).unwrap();
+        assert_eq!(vertex.load_data().unwrap(), vec![42; 1024]);
+        assert_eq!(
+            graph.get_vertex(42).unwrap().load_data().unwrap().len(),
+            1024
+        );
+        assert_eq!(loader.loads.load(Ordering::SeqCst), 1);
+        assert!(format!("{:?}", graph.get_vertex(41).unwrap().get_data()) == "Unloaded");
+
+        // Vertex 100 was never stored, and a failed load is retried.
+        assert!(matches!(
+            tip.load_data(),
+            Err(GraphError::NonExistentVertex)
+        ));
+        assert!(tip.load_data().is_err());
+        assert_eq!(loader.loads.load(Ordering::SeqCst), 3);
+        assert!(graph.get_vertex(0).unwrap().get_data().is_loaded());
+    }
+
+    #[test]
+    fn test_frozen_graph_matches_source_graph() {
+        use crate::frozen::FrozenBullDag;
+        use crate::vertex::Direction;
+
+        let mut graph: BullDag<String, u64> = BullDag::new();
+        let chain: Vec<Vertex<String, u64>> = (0..200u64)
+            .map(|i| Vertex::new(format!("block {i}"), i))
+            .collect();
+        for pair in chain.windows(2) {
+            graph.add_edge((&pair[0], &pair[1]));
+        }
+        graph.add_edge((&chain[10], &chain[150]));
+        graph.add_vertex(&Vertex::new("orphan".to_string(), 1000));
+
+        let bytes = graph.freeze().unwrap();
+        let frozen: FrozenBullDag<&[u8], String, u64> = FrozenBullDag::new(&bytes[..]).unwrap();
+        assert_eq!(frozen.len(), 201);
+        assert_eq!(frozen.n_edges(), 200);
+        assert_eq!(frozen.get_data(&42), Some("block 42".to_string()));
+        assert_eq!(frozen.get_data(&500), None);
+
+        let mut references = frozen.neighbors(&10, Direction::Reference);
+        references.sort();
+        assert_eq!(references, vec![11, 150]);
+        let mut ancestors = frozen.trace(&150, Direction::Source);
+        ancestors.sort();
+        assert_eq!(ancestors, (0..=150).collect::<Vec<u64>>());
+        let mut roots = frozen.get_roots();
+        roots.sort();
+        assert_eq!(roots, vec![0, 1000]);
+        assert_eq!(frozen.thaw().unwrap(), graph);
+
+        assert!(FrozenBullDag::<_, String, u64>::new(&bytes[..bytes.len() - 1]).is_err());
+        assert!(FrozenBullDag::<_, String, u64>::new(&b"BDAGFRZ2"[..]).is_err());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_frozen_graph_opens_memory_mapped_file() {
+        use crate::frozen::FrozenBullDag;
+        use crate::vertex::Direction;
+
+        let mut graph: BullDag<u64, u64> = BullDag::new();
+        let chain: Vec<Vertex<u64, u64>> = (0..100u64).map(|i| Vertex::new(i * 2, i)).collect();
+        for pair in chain.windows(2) {
+            graph.add_edge((&pair[0], &pair[1]));
+        }
+        let path = std::env::temp_dir().join(format!("bulldag-frozen-{}", std::process::id()));
+        std::fs::write(&path, graph.freeze().unwrap()).unwrap();
+
+        let frozen: FrozenBullDag<_, u64, u64> = FrozenBullDag::open(&path).unwrap();
+        assert_eq!(frozen.trace(&0, Direction::Reference).len(), 100);
+        assert_eq!(frozen.get_data(&99), Some(198));
+        drop(frozen);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "testing")]
+    mod arbitrary {
+        use crate::graph::BullDag;
+        use crate::testing::DagParams;
+        use crate::validate::GraphViolation;
+        use proptest::prelude::*;
+
+        proptest! {
+            #[test]
+            fn test_arbitrary_dags_are_valid(graph in any::<BullDag<u8, u32>>()) {
+                let validated: Result<(), Vec<GraphViolation<u32>>> = graph.validate();
+                prop_assert!(validated.is_ok(), "{:?}", validated);
+                prop_assert!(graph.len() < 32);
+            }
+
+            #[test]
+            fn test_arbitrary_dags_respect_params(
+                graph in any_with::<BullDag<(), u16>>(DagParams { vertices: 40..41, edges: 100..101 })
+            ) {
+                prop_assert!(graph.len() <= 40);
+                prop_assert!(graph.n_edges() <= 100);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_generated_graphs_have_expected_shape() {
+        use crate::generate;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let chain: BullDag<(), usize> = generate::chain(1000);
+        assert_eq!((chain.len(), chain.n_edges()), (1000, 999));
+        assert!(chain.validate().is_ok());
+
+        let tree: BullDag<(), usize> = generate::binary_tree(10);
+        assert_eq!((tree.len(), tree.n_edges()), (1023, 1022));
+        assert_eq!(tree.n_leaves(), 512);
+
+        let lattice: BullDag<(), usize> = generate::diamond_lattice(30, 20);
+        assert_eq!(lattice.len(), 600);
+        assert_eq!(lattice.n_edges(), 29 * 20 + 30 * 19);
+        assert_eq!(lattice.get_roots(), [0].into());
+        assert_eq!(lattice.get_leaves(), [599].into());
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let layered: BullDag<(), usize> = generate::layered(5, 10, 1.0, &mut rng);
+        assert_eq!(layered.n_edges(), 4 * 100);
+        assert_eq!(layered.n_roots(), 10);
+        let random: BullDag<u8, usize> = generate::random(200, 0.05, &mut rng);
+        assert_eq!(random.len(), 200);
+        assert!(random.n_edges() > 0 && random.n_edges() < 199 * 100);
+        assert!(random.validate().is_ok());
+
+        let empty: BullDag<(), usize> = generate::random(0, 0.5, &mut rng);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_invariants_hold_under_random_mutations() {
+        use crate::edge::Edge;
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(1079);
+        let mut graph: BullDag<u8, u8> = BullDag::new();
+        for _ in 0..500 {
+            let (a, b) = (rng.gen_range(0..24u8), rng.gen_range(0..24u8));
+            match rng.gen_range(0..5) {
+                0 => graph.add_vertex(&Vertex::new(a, a)),
+                1 => graph.add_edge((&Vertex::new(a, a), &Vertex::new(b, b))),
+                2 => {
+                    let _ = graph.add_edge_ix(&a, &b);
+                }
+                3 => {
+                    graph.remove_edge(&Edge::new(a, b));
+                }
+                _ => {
+                    graph.remove_vertex(a);
+                }
+            }
+            graph.check_invariants();
+        }
+
+        let mut corrupted = graph.clone();
+        corrupted.roots.insert(200);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            corrupted.check_invariants()
+        }));
+        let message = result.unwrap_err();
+        assert!(message
+            .downcast_ref::<String>()
+            .unwrap()
+            .contains("RootMismatch(200)"));
+    }
+
+    #[test]
+    fn test_sorted_helpers_ignore_insertion_order() {
+        let v: Vec<Vertex<String, u32>> = (0..8).map(|i| Vertex::new(format!("v{i}"), i)).collect();
+        let pairs = [(7, 3), (6, 3), (3, 1), (5, 1), (4, 0), (2, 0), (1, 0)];
+
+        let mut forward: BullDag<String, u32> = BullDag::new();
+        for (s, r) in pairs {
+            forward.add_edge((&v[s], &v[r]));
+        }
+        let mut backward: BullDag<String, u32> = BullDag::new();
+        for (s, r) in pairs.iter().rev() {
+            backward.add_edge((&v[*s], &v[*r]));
+        }
+
+        let order = forward.sorted_topological_order();
+        assert_eq!(order, vec![2, 4, 5, 6, 7, 3, 1, 0]);
+        assert_eq!(backward.sorted_topological_order(), order);
+        assert_eq!(forward.sorted_indices(), (0..8).collect::<Vec<u32>>());
+        assert_eq!(forward.sorted_edges(), backward.sorted_edges());
+        assert_eq!(forward.sorted_edges()[0].get_source(), 1);
+        assert_eq!(forward.sorted_vertices()[3].get_data(), "v3");
+        assert_eq!(
+            forward.to_sorted_adjacency_json().unwrap(),
+            backward.to_sorted_adjacency_json().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_traversals_survive_million_vertex_chain() {
+        use crate::generate;
+        use crate::vertex::Direction;
+
+        let n = 1_000_000;
+        let mut graph: BullDag<(), usize> = generate::chain(n);
+
+        let tip = graph.get_vertex(n - 1).unwrap().clone();
+        let ancestors = graph.trace(&tip, Direction::Source);
+        assert_eq!(ancestors.len(), n);
+        assert_eq!(ancestors[0], 0);
+        assert_eq!(ancestors[n - 1], n - 1);
+
+        let genesis = graph.get_vertex(0).unwrap().clone();
+        assert_eq!(graph.trace(&genesis, Direction::Reference).len(), n);
+
+        match graph.topological_sort() {
+            Ok(order) => assert!(order.iter().copied().eq(0..n)),
+            _ => panic!("expected a topological order"),
+        }
+
+        graph.add_edge((&tip, &genesis));
+        assert_eq!(graph.n_edges(), n - 1);
+    }
+
+    #[test]
+    fn test_topological_order_is_cached_and_updated() {
+        use crate::edge::Edge;
+        use std::collections::HashMap;
+
+        let mut graph: BullDag<usize, usize> = (0..4)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .collect();
+        let is_topological = |graph: &BullDag<usize, usize>, order: &[usize]| {
+            let position: HashMap<usize, usize> =
+                order.iter().enumerate().map(|(i, ix)| (*ix, i)).collect();
+            order.len() == graph.len()
+                && graph
+                    .edges
+                    .iter()
+                    .all(|e| position[&e.get_source()] < position[&e.get_reference()])
+        };
+
+        assert!(graph.topo_order.get().is_none());
+        assert!(is_topological(&graph, &graph.topological_order()));
+        assert!(graph.topo_order.get().is_some());
+
+        graph.add_edge((&Vertex::new(3, 3), &Vertex::new(4, 4)));
+        graph.add_edge((&Vertex::new(1, 1), &Vertex::new(4, 4)));
+        assert!(graph.topo_order.get().is_some());
+        assert!(is_topological(&graph, &graph.topological_order()));
+
+        graph.add_edge((&Vertex::new(5, 5), &Vertex::new(0, 0)));
+        assert!(graph.topo_order.get().is_none());
+        assert!(is_topological(&graph, &graph.topological_order()));
+
+        graph.remove_edge(&Edge::new(1, 4));
+        assert!(graph.topo_order.get().is_some());
+        graph.remove_vertex(2);
+        assert!(graph.topo_order.get().is_none());
+        assert!(is_topological(&graph, &graph.topological_order()));
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_reachability_index_matches_traversal() {
+        use crate::generate;
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut graph: BullDag<(), usize> = generate::random(25, 0.1, &mut rng);
+        graph.enable_reachability_index();
+
+        for step in 0..100 {
+            let (a, b) = (rng.gen_range(0..30), rng.gen_range(0..30));
+            match step % 10 {
+                0 => {
+                    graph.remove_vertex(a);
+                }
+                1 => {
+                    graph.remove_edge(&crate::edge::Edge::new(a, b));
+                }
+                _ => {
+                    graph.add_edge((&Vertex::new((), a), &Vertex::new((), b)));
+                }
+            }
+
+            let mut plain = graph.clone();
+            plain.disable_reachability_index();
+            for from in 0..30 {
+                for to in 0..30 {
+                    assert_eq!(
+                        graph.is_reachable(&from, &to),
+                        plain.is_reachable(&from, &to),
+                        "{from} -> {to} after step {step}"
+                    );
+                }
+            }
+        }
+        graph.check_invariants();
+    }
+
+    #[test]
+    fn test_stats_of_generated_graphs() {
+        use crate::generate;
+
+        let lattice: BullDag<(), usize> = generate::diamond_lattice(4, 3);
+        let stats = lattice.stats();
+        assert_eq!((stats.vertices, stats.edges), (12, 17));
+        assert_eq!((stats.roots, stats.leaves), (1, 1));
+        assert_eq!(stats.max_depth, 5);
+        assert_eq!((stats.max_in_degree, stats.max_out_degree), (2, 2));
+        assert_eq!(stats.components, 1);
+
+        let mut forest: BullDag<(), usize> = generate::binary_tree(3);
+        forest.remove_vertex(0);
+        let stats = forest.stats();
+        assert_eq!(stats.components, 2);
+        assert_eq!(stats.max_depth, 1);
+
+        let empty: BullDag<(), usize> = BullDag::new();
+        assert_eq!(empty.stats().average_degree, 0.0);
+        assert_eq!(empty.stats().components, 0);
+    }
+
+    #[test]
+    fn test_degrees_follow_edge_changes() {
+        use crate::edge::Edge;
+
+        let mut graph: BullDag<(), usize> = crate::generate::binary_tree(2);
+        assert_eq!(
+            (graph.in_degree(&0), graph.out_degree(&0)),
+            (Some(0), Some(2))
+        );
+        assert_eq!(
+            (graph.in_degree(&1), graph.out_degree(&1)),
+            (Some(1), Some(0))
+        );
+
+        graph.add_edge((&Vertex::new((), 1), &Vertex::new((), 2)));
+        assert_eq!(
+            (graph.in_degree(&2), graph.out_degree(&1)),
+            (Some(2), Some(1))
+        );
+
+        graph.remove_edge(&Edge::new(0, 2));
+        assert_eq!(
+            (graph.in_degree(&2), graph.out_degree(&0)),
+            (Some(1), Some(1))
+        );
+
+        graph.remove_vertex(1);
+        assert_eq!(graph.in_degree(&1), None);
+        assert_eq!(
+            (graph.in_degree(&2), graph.out_degree(&0)),
+            (Some(0), Some(0))
+        );
+    }
+
+    #[test]
+    fn test_membership_after_removal() {
+        let mut graph: BullDag<(), usize> = crate::generate::chain(3);
+        assert!(graph.has_edge(&0, &1) && graph.has_edge(&1, &2));
+        assert!(!graph.has_edge(&0, &2));
+
+        graph.remove_vertex(1);
+        assert!(!graph.contains_vertex(&1));
+        assert!(graph.contains_vertex(&0) && graph.contains_vertex(&2));
+        assert!(!graph.has_edge(&0, &1) && !graph.has_edge(&1, &2));
+    }
+
+    #[test]
+    fn test_find_by_payload() {
+        let mut graph: BullDag<(&str, u64), usize> = BullDag::new();
+        let blocks = [("alice", 1), ("bob", 2), ("alice", 3), ("carol", 4)];
+        let vertices: Vec<Vertex<(&str, u64), usize>> = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| Vertex::new(*block, i))
+            .collect();
+        let edges: Vec<_> = vertices.windows(2).map(|w| (&w[0], &w[1])).collect();
+        graph.extend_from_edges(&edges);
+
+        let mut from_alice = graph.find_indices(|(proposer, _)| *proposer == "alice");
+        from_alice.sort();
+        assert_eq!(from_alice, vec![0, 2]);
+        assert!(graph.find(|(_, height)| *height > 10).is_empty());
+        assert_eq!(graph.find(|_| true).len(), 4);
+    }
+
+    #[test]
+    fn test_query_depth_bounds_and_filters() {
+        let lattice: BullDag<(), usize> = crate::generate::diamond_lattice(3, 3);
+
+        // 0 1 2
+        // 3 4 5
+        // 6 7 8
+        let mut within_two = lattice.query().from(0).max_depth(2).indices();
+        within_two.sort();
+        assert_eq!(within_two, vec![1, 2, 3, 4, 6]);
+
+        let mut ring = lattice.query().from(0).min_depth(2).max_depth(2).indices();
+        ring.sort();
+        assert_eq!(ring, vec![2, 4, 6]);
+
+        let mut up = lattice
+            .query()
+            .from(8)
+            .via_sources()
+            .filter(|vtx| vtx.get_index() % 2 == 0)
+            .filter(|vtx| vtx.get_index() != 0)
+            .indices();
+        up.sort();
+        assert_eq!(up, vec![2, 4, 6]);
+
+        assert_eq!(
+            lattice
+                .query()
+                .from(0)
+                .from(8)
+                .min_depth(0)
+                .max_depth(0)
+                .count(),
+            2
+        );
+        assert_eq!(lattice.query().from(42).count(), 0);
+    }
+
+    #[test]
+    fn test_generations_of_lattice() {
+        use std::collections::HashMap;
+
+        let lattice: BullDag<(), usize> = crate::generate::diamond_lattice(3, 3);
+        let mut generations = lattice.generations();
+        generations
+            .iter_mut()
+            .for_each(|generation| generation.sort());
+        assert_eq!(
+            generations,
+            vec![vec![0], vec![1, 3], vec![2, 4, 6], vec![5, 7], vec![8]]
+        );
+
+        let generation_of: HashMap<usize, usize> = generations
+            .iter()
+            .enumerate()
+            .flat_map(|(g, generation)| generation.iter().map(move |ix| (*ix, g)))
+            .collect();
+        assert!(lattice
+            .edges
+            .iter()
+            .all(|e| generation_of[&e.get_source()] < generation_of[&e.get_reference()]));
+
+        assert!(BullDag::<(), usize>::new().generations().is_empty());
+    }
+
+    #[test]
+    fn test_depth_is_longest_path() {
+        let mut graph: BullDag<(), usize> = crate::generate::chain(5);
+        assert_eq!(graph.depth(), 4);
+
+        // A shortcut doesn't make the chain any shallower
+        graph.add_edge((&Vertex::new((), 0), &Vertex::new((), 4)));
+        assert_eq!(graph.depth(), 4);
+        assert_eq!(graph.depth_of(&4), Some(4));
+        assert_eq!(graph.depth_of(&5), None);
+
+        let depths = graph.depth_map();
+        assert_eq!(depths.len(), 5);
+        assert!((0..5).all(|ix| depths[&ix] == ix));
+
+        assert_eq!(BullDag::<(), usize>::new().depth(), 0);
+    }
+
+    #[test]
+    fn test_exact_width_beats_generations() {
+        use crate::generate;
+
+        // 0 -> 1 -> 2 -> 3 and 0 -> 4 -> 5 with 6 -> 5: the generations are
+        // {0, 6}, {1, 4}, {2, 5}, {3}, but 3, 5 and 6 are an antichain
+        let mut graph: BullDag<(), usize> = BullDag::new();
+        for (s, r) in [(0, 1), (1, 2), (2, 3), (0, 4), (4, 5), (6, 5)] {
+            graph.add_edge((&Vertex::new((), s), &Vertex::new((), r)));
+        }
+        assert_eq!(graph.width(), 2);
+        assert_eq!(graph.exact_width(), 3);
+
+        let chain: BullDag<(), usize> = generate::chain(100);
+        assert_eq!((chain.width(), chain.exact_width()), (1, 1));
+
+        let lattice: BullDag<(), usize> = generate::diamond_lattice(4, 4);
+        assert_eq!((lattice.width(), lattice.exact_width()), (4, 4));
+
+        let mut indexed: BullDag<(), usize> = generate::binary_tree(5);
+        indexed.enable_reachability_index();
+        assert_eq!((indexed.width(), indexed.exact_width()), (16, 16));
+
+        assert_eq!(BullDag::<(), usize>::new().exact_width(), 0);
+    }
+
+    #[test]
+    fn test_degree_distribution_shows_hub() {
+        let mut graph: BullDag<(), usize> = BullDag::new();
+        let hub = Vertex::new((), 0);
+        for ix in 1..=50 {
+            graph.add_edge((&hub, &Vertex::new((), ix)));
+        }
+        graph.add_edge((&Vertex::new((), 1), &Vertex::new((), 2)));
+
+        let distribution = graph.degree_distribution();
+        assert_eq!(distribution.out_degree, [(0, 49), (1, 1), (50, 1)].into());
+        assert_eq!(distribution.in_degree, [(0, 1), (1, 49), (2, 1)].into());
+        assert_eq!(distribution.in_degree.values().sum::<usize>(), graph.len());
+        assert_eq!(
+            distribution.out_degree.last_key_value(),
+            Some((&graph.stats().max_out_degree, &1))
+        );
+    }
+
+    #[test]
+    fn test_dominators_of_control_flow() {
+        use std::collections::HashMap;
+
+        //      0
+        //     / \
+        //    1   2
+        //   / \  |
+        //  3   4 |
+        //   \ /  |
+        //    5   |
+        //     \ /
+        //      6 -> 7
+        let mut graph: BullDag<(), usize> = BullDag::new();
+        let edges = [
+            (0, 1),
+            (0, 2),
+            (1, 3),
+            (1, 4),
+            (3, 5),
+            (4, 5),
+            (5, 6),
+            (2, 6),
+            (6, 7),
+        ];
+        for (s, r) in edges {
+            graph.add_edge((&Vertex::new((), s), &Vertex::new((), r)));
+        }
+        let idom = graph.dominators(&0);
+        let expected: HashMap<usize, usize> =
+            [(1, 0), (2, 0), (3, 1), (4, 1), (5, 1), (6, 0), (7, 6)].into();
+        assert_eq!(idom, expected);
+
+        // From 1, vertex 2 is unreachable so 6 is only entered through 5
+        let idom = graph.dominators(&1);
+        assert_eq!(idom.len(), 5);
+        assert_eq!((idom[&6], idom[&7]), (5, 6));
+
+        assert!(graph.dominators(&42).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_chain_decomposition_covers_every_vertex() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let graph: BullDag<(), usize> = crate::generate::random(40, 0.08, &mut rng);
+        let chains = graph.chain_decomposition();
+        assert_eq!(chains.len(), graph.exact_width());
+
+        let mut covered: Vec<usize> = chains.iter().flatten().copied().collect();
+        covered.sort();
+        assert_eq!(covered, (0..40).collect::<Vec<_>>());
+        for chain in &chains {
+            assert!(chain.windows(2).all(|w| graph.is_reachable(&w[0], &w[1])));
+        }
+
+        let chain: BullDag<(), usize> = crate::generate::chain(10);
+        assert_eq!(
+            chain.chain_decomposition(),
+            vec![(0..10).collect::<Vec<_>>()]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_anticone_is_symmetric() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let graph: BullDag<(), usize> = crate::generate::layered(5, 4, 0.3, &mut rng);
+        for a in graph.vertices.keys() {
+            let anticone = graph.anticone(a);
+            assert!(!anticone.contains(a));
+            for b in graph.vertices.keys().filter(|b| *b != a) {
+                let concurrent = !graph.is_reachable(a, b) && !graph.is_reachable(b, a);
+                assert_eq!(anticone.contains(b), concurrent);
+                assert_eq!(graph.anticone(b).contains(a), concurrent);
+            }
+        }
+        assert!(graph.anticone(&100).is_empty());
+    }
+
+    #[test]
+    #[cfg(all(feature = "consensus", feature = "random"))]
+    fn test_ghostdag_blues_form_k_cluster() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use std::collections::HashMap;
+
+        let mut rng = StdRng::seed_from_u64(5);
+        let graph: BullDag<(), usize> = crate::generate::layered(8, 5, 0.4, &mut rng);
+        for k in [0, 1, 3] {
+            let ghostdag = graph.ghostdag(k);
+
+            let order = ghostdag.order();
+            assert_eq!(order.len(), graph.len());
+            let position: HashMap<usize, usize> =
+                order.iter().enumerate().map(|(i, ix)| (*ix, i)).collect();
+            assert!(graph
+                .edges
+                .iter()
+                .all(|e| position[&e.get_source()] < position[&e.get_reference()]));
+
+            let blues = ghostdag.blue_set();
+            for blue in blues {
+                let anticone = graph.anticone(blue);
+                assert!(anticone.intersection(blues).count() <= k);
+            }
+            let tip = ghostdag.selected_tip().unwrap();
+            assert!(ghostdag.blue_score(tip).unwrap() < blues.len() as u64);
+        }
+        assert!(graph.ghostdag(0).blue_set().len() < graph.ghostdag(3).blue_set().len());
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_ordering_rules_are_topological_and_deterministic() {
+        use crate::ordering::{HashTiebreak, HeaviestSubtree, OrderingRule};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use std::collections::HashMap;
+
+        fn check(graph: &BullDag<(), usize>, rule: &impl OrderingRule<(), usize>) {
+            let order = graph.canonical_order(rule);
+            assert_eq!(order.len(), graph.len());
+            let position: HashMap<usize, usize> =
+                order.iter().enumerate().map(|(i, ix)| (*ix, i)).collect();
+            assert!(graph
+                .edges
+                .iter()
+                .all(|e| position[&e.get_source()] < position[&e.get_reference()]));
+            assert_eq!(order, graph.clone().canonical_order(rule));
+            let tip = graph.selected_tip(rule).unwrap();
+            assert!(graph.get_leaves().contains(&tip));
+        }
+
+        let mut rng = StdRng::seed_from_u64(9);
+        let graph: BullDag<(), usize> = crate::generate::layered(6, 4, 0.3, &mut rng);
+        check(&graph, &HashTiebreak);
+        check(&graph, &HeaviestSubtree);
+        #[cfg(feature = "consensus")]
+        check(&graph, &crate::consensus::GhostdagRule { k: 2 });
+    }
+
+    #[test]
+    #[cfg(feature = "hash")]
+    fn test_prune_epochs_keeps_graph_bounded() {
+        let mut graph: BullDag<usize, usize> = crate::generate::diamond_lattice(3, 6);
+        for ix in 0..18 {
+            graph.set_epoch(&ix, ix as u64 / 3);
+        }
+        let json = serde_json::to_string(&graph).unwrap();
+        let decoded: BullDag<usize, usize> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.epoch_of(&17), Some(5));
+
+        for epoch in 1..5 {
+            let checkpoint = graph.prune_epochs_before(epoch).unwrap();
+            assert_eq!(graph.len(), 18 - 3 * epoch as usize);
+            assert_eq!(checkpoint.roots, [3 * epoch as usize].into());
+            assert!(graph.vertices_in_epoch(epoch - 1).is_empty());
+            assert_eq!(graph.epoch_of(&(3 * epoch as usize - 1)), None);
+            graph.check_invariants();
+        }
+        assert_eq!(graph.epoch_checkpoints().len(), 4);
+        assert_ne!(
+            graph.epoch_checkpoints()[0].state_root,
+            graph.epoch_checkpoints()[1].state_root
+        );
+
+        // Untagged vertices are never pruned
+        graph.add_edge((&Vertex::new(0, 100), &Vertex::new(0, 12)));
+        graph.prune_epochs_before(5).unwrap();
+        assert!(graph.contains_vertex(&100));
+
+        // Checkpoints taken before a prune roll its record back with it
+        let mut graph: BullDag<usize, usize> = crate::generate::diamond_lattice(3, 6);
+        for ix in 0..18 {
+            graph.set_epoch(&ix, ix as u64 / 3);
+        }
+        graph.prune_epochs_before(1).unwrap();
+        graph.checkpoint("pruned-once");
+        graph.prune_epochs_before(2).unwrap();
+        assert_eq!(graph.epoch_checkpoints().len(), 2);
+        assert!(graph.restore("pruned-once"));
+        assert_eq!(graph.epoch_checkpoints().len(), 1);
+        assert_eq!(graph.epoch_checkpoints()[0].epoch, 1);
+        assert_eq!(graph.snapshot().into_dag().epoch_checkpoints().len(), 1);
+    }
+
+    #[test]
+    fn test_conflicts_follow_vertex_removal() {
+        let mut graph: BullDag<(), usize> = crate::generate::binary_tree(3);
+        assert!(graph.add_conflict(&3, &4));
+        assert!(graph.add_conflict(&3, &5));
+        assert!(!graph.add_conflict(&3, &3));
+        assert!(!graph.add_conflict(&3, &42));
+        assert!(graph.has_conflict(&4, &3) && !graph.has_conflict(&4, &5));
+        assert!(graph.is_conflict_free(&[4, 5, 6]));
+
+        let json = serde_json::to_string(&graph).unwrap();
+        let decoded: BullDag<(), usize> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.conflicts_of(&3), [4, 5].into());
+
+        graph.remove_vertex(3);
+        assert!(graph.conflicts_of(&4).is_empty());
+        assert!(graph.conflicts.is_empty());
+        assert!(!graph.remove_conflict(&4, &5));
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_reverse_twice_is_identity() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(1101);
+        let mut graph: BullDag<(), usize> = crate::generate::random(40, 0.1, &mut rng);
+        let reversed = graph.reverse();
+        assert!(reversed.validate().is_ok());
+        assert_eq!(reversed.n_edges(), graph.n_edges());
+        assert!(graph
+            .edges
+            .iter()
+            .all(|edge| reversed.has_edge(&edge.get_reference(), &edge.get_source())));
+
+        // The cached order no longer holds once the edges are flipped
+        graph.topological_order();
+        graph.reverse_in_place();
+        assert_eq!(graph.edges, reversed.edges);
+        assert!(graph.validate().is_ok());
+        let order = graph.topological_order();
+        let position = |ix: &usize| order.iter().position(|x| x == ix);
+        assert!(graph
+            .edges
+            .iter()
+            .all(|edge| position(&edge.get_source()) < position(&edge.get_reference())));
+
+        graph.reverse_in_place();
+        assert_eq!(graph.edges, reversed.reverse().edges);
+        assert_eq!(graph.get_roots(), reversed.get_leaves());
+    }
+
+    #[test]
+    fn test_contract_edge_keeps_graph_acyclic() {
+        use crate::edge::Edge;
+
+        // 0 1 2
+        // 3 4 5
+        // 6 7 8
+        let mut graph: BullDag<(), usize> = crate::generate::diamond_lattice(3, 3);
+        assert_eq!(graph.n_edges(), 12);
+        graph.enable_multi_edges();
+        graph.set_edge_kind(&Edge::new(1, 2), 3);
+        graph.set_edge_time(&Edge::new(1, 2), 5);
+        graph.set_edge_weight(&Edge::new(1, 2), 7);
+        graph.add_keyed_edge((1, 4), 9).unwrap();
+
+        assert!(graph.contract_edge(&0, &1, |_, _| ()).is_ok());
+        assert!(!graph.contains_vertex(&1));
+        assert_eq!(graph.n_edges(), 11);
+        assert!([2, 3, 4].iter().all(|r| graph.has_edge(&0, r)));
+        // The moved edges keep their kind, time, weight and keys
+        assert_eq!(graph.edge_kind(&Edge::new(0, 2)), Some(3));
+        assert_eq!(graph.edge_time(&Edge::new(0, 2)), Some(5));
+        assert_eq!(graph.edge_weight(&Edge::new(0, 2)), Some(7));
+        assert!(graph.edge_keys(&Edge::new(0, 4)).contains(&9));
+
+        // 4 is also below 3
+        assert!(matches!(
+            graph.contract_edge(&0, &4, |_, _| ()),
+            Err(crate::graph::GraphError::WouldCycle)
+        ));
+        assert_eq!(graph.n_edges(), 11);
+        // Failures are found before anything changes
+        let before = graph.clone();
+        assert!(graph.contract_edge(&0, &99, |_, _| ()).is_err());
+        assert!(graph.reindex(&0, 2).is_err());
+        assert!(graph.reindex(&99, 100).is_err());
+        assert_eq!(graph, before);
+
+        assert!(graph.contract_edge(&0, &3, |_, _| ()).is_ok());
+        assert!(graph.contract_edge(&0, &4, |_, _| ()).is_ok());
+        assert!(graph.validate().is_ok());
+        assert_eq!(graph.topological_order().len(), graph.len());
+        assert_eq!(graph.get_roots(), [0].into());
+    }
+
+    #[test]
+    fn test_insert_between_splits_edge() {
+        use crate::edge::Edge;
+
+        let mut graph: BullDag<(), usize> = crate::generate::chain(3);
+        graph.enable_journal();
+        let mut middle: Vertex<(), usize> = Vertex::new((), 10);
+        middle.add_edge(&Edge::new(10, 0));
+
+        assert!(graph.insert_between(middle, &1, &2).is_ok());
+        assert_eq!(graph.topological_order(), vec![0, 1, 10, 2]);
+        assert_eq!(graph.get_vertex(10).unwrap().get_sources(), vec![&1]);
+        assert!(graph.validate().is_ok());
+
+        assert!(matches!(
+            graph.insert_between(Vertex::new((), 11), &0, &2),
+            Err(crate::graph::GraphError::NoEdges)
+        ));
+        (0..4).for_each(|_| {
+            graph.undo();
+        });
+        assert!(graph.has_edge(&1, &2) && !graph.contains_vertex(&10));
+    }
+
+    #[test]
+    fn test_reindex_moves_edges_and_tags() {
+        let mut graph: BullDag<(), usize> = crate::generate::binary_tree(3);
+        graph.set_epoch(&1, 7);
+        graph.add_conflict(&1, &2);
+
+        assert!(graph.reindex(&1, 100).is_ok());
+        assert!(!graph.contains_vertex(&1));
+        assert!(graph.has_edge(&0, &100));
+        assert!(graph.has_edge(&100, &3) && graph.has_edge(&100, &4));
+        assert_eq!(graph.get_vertex(3).unwrap().get_sources(), vec![&100]);
+        assert_eq!(graph.epoch_of(&100), Some(7));
+        assert_eq!(graph.conflicts_of(&2), [100].into());
+        assert!(graph.validate().is_ok());
+
+        assert!(matches!(
+            graph.reindex(&1, 200),
+            Err(crate::graph::GraphError::NonExistentVertex)
+        ));
+        assert!(matches!(
+            graph.reindex(&100, 2),
+            Err(crate::graph::GraphError::VertexExists)
+        ));
+    }
+
+    #[test]
+    fn test_upsert_keeps_edges() {
+        let mut graph: BullDag<Vec<u8>, usize> = crate::generate::chain(3);
+        graph.enable_journal();
+        (0..4).for_each(|ix| graph.upsert(ix, || vec![9], |data| data.push(ix as u8)));
+
+        assert_eq!(graph.get_vertex(1).unwrap().get_data(), vec![1]);
+        assert_eq!(graph.get_vertex(3).unwrap().get_data(), vec![9]);
+        assert!(graph.has_edge(&0, &1) && graph.has_edge(&1, &2));
+        assert_eq!(graph.n_edges(), 2);
+
+        // The insert and each update are undone one at a time
+        assert!(graph.undo());
+        assert!(!graph.contains_vertex(&3));
+        assert!(graph.undo());
+        assert_eq!(graph.get_vertex(2).unwrap().get_data(), Vec::<u8>::new());
+        assert!(graph.undo() && graph.undo());
+        assert!(!graph.undo());
+        assert_eq!(graph.get_vertex(0).unwrap().get_data(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_failed_transaction_leaves_graph_untouched() {
+        use crate::observe::GraphObserver;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        struct Counter(Arc<AtomicUsize>);
+        impl GraphObserver<(), usize> for Counter {
+            fn edge_added(&self, _: &crate::edge::Edge<usize>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let added = Arc::new(AtomicUsize::new(0));
+        let mut graph: BullDag<(), usize> = crate::generate::chain(5);
+        graph.add_observer(Counter(added.clone()));
+        let edges = graph.edges.clone();
+
+        let result = graph.transaction(|tx| {
+            tx.add_edge(&0, &4)?;
+            tx.remove_vertex(&2)?;
+            tx.add_edge(&4, &0)
+        });
+        assert!(matches!(result, Err(crate::graph::GraphError::WouldCycle)));
+        assert_eq!(graph.edges, edges);
+        assert_eq!(added.load(Ordering::SeqCst), 0);
+
+        let result = graph.transaction(|tx| {
+            tx.add_edge(&0, &4)?;
+            tx.remove_vertex(&2)
+        });
+        assert!(result.is_ok());
+        assert_eq!(added.load(Ordering::SeqCst), 1);
+        assert_eq!(graph.n_edges(), 3);
+        assert!(graph.validate().is_ok());
+    }
+
+    #[test]
+    fn test_try_extend_from_edges_reports_each_edge() {
+        let v: Vec<Vertex<(), usize>> = (0..4).map(|i| Vertex::new((), i)).collect();
+        let mut graph: BullDag<(), usize> = BullDag::new();
+        let results = graph.try_extend_from_edges([
+            (&v[0], &v[1]),
+            (&v[1], &v[2]),
+            (&v[2], &v[0]),
+            (&v[0], &v[0]),
+            (&v[2], &v[3]),
+        ]);
+
+        let rejected: Vec<usize> = results
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| matches!(r, Err(crate::graph::GraphError::WouldCycle)))
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(rejected, vec![2, 3]);
+        assert_eq!(results.len(), 5);
+        assert_eq!(graph.n_edges(), 3);
+    }
+
+    #[test]
+    fn test_unchecked_edges_match_checked_ones() {
+        let checked: BullDag<(), usize> = crate::generate::diamond_lattice(20, 20);
+        let vertices: Vec<Vertex<(), usize>> = (0..400).map(|i| Vertex::new((), i)).collect();
+        let edges: Vec<_> = checked
+            .edges
+            .iter()
+            .map(|e| (&vertices[e.get_source()], &vertices[e.get_reference()]))
+            .collect();
+
+        let mut unchecked: BullDag<(), usize> = BullDag::new();
+        unchecked.extend_unchecked(&edges);
+        assert!(unchecked.validate().is_ok());
+        assert_eq!(unchecked, checked);
+
+        // Nothing stops a cycle, but validation finds it
+        unchecked.add_edge_unchecked((&vertices[399], &vertices[0]));
+        assert!(unchecked.validate().is_err());
+    }
+
+    #[test]
+    fn test_cleared_graph_can_be_reused() {
+        let mut graph: BullDag<(), usize> = crate::generate::chain(1000);
+        graph.set_epoch(&0, 1);
+        graph.add_conflict(&1, &2);
+        assert_eq!(graph.topological_order().len(), 1000);
+        let capacity = graph.vertices.capacity();
+
+        assert_eq!(graph.drain_vertices().take(3).count(), 3);
+        assert!(graph.is_empty());
+        assert!(graph.vertices.capacity() >= capacity);
+        assert!(graph.topological_order().is_empty());
+        assert_eq!(graph.epoch_of(&0), None);
+
+        graph.extend(crate::generate::chain::<()>(10).edges);
+        assert_eq!(graph.topological_order(), (0..10).collect::<Vec<_>>());
+        graph.clear();
+        assert!(graph.is_empty() && graph.conflicts.is_empty());
+        assert!(graph.validate().is_ok());
+    }
+
+    #[test]
+    fn test_reserved_graph_does_not_grow() {
+        let mut graph: BullDag<(), usize> = BullDag::with_capacity(1000, 999);
+        let capacities = (graph.vertices.capacity(), graph.edges.capacity());
+        assert!(capacities.0 >= 1000 && capacities.1 >= 999);
+
+        graph.extend(crate::generate::chain::<()>(1000).edges);
+        assert_eq!(graph.len(), 1000);
+        assert_eq!(
+            (graph.vertices.capacity(), graph.edges.capacity()),
+            capacities
+        );
+
+        graph.reserve_edges(capacities.1);
+        assert!(graph.edges.capacity() >= 999 + capacities.1);
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_memory_usage_follows_allocations() {
+        use crate::edge::Edge;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(1111);
+        let mut graph: BullDag<u64, usize> = crate::generate::layered(4, 50, 0.2, &mut rng);
+        graph.invalidate_caches();
+        let report = graph.memory_usage();
+        assert_eq!(report.caches, 0);
+        assert!(report.vertices >= graph.len() * std::mem::size_of::<Vertex<u64, usize>>());
+        assert!(report.edges >= graph.n_edges() * std::mem::size_of::<Edge<usize>>());
+        assert!(report.adjacency >= 2 * graph.n_edges() * std::mem::size_of::<usize>());
+
+        graph.enable_reachability_index();
+        graph.reachability_index();
+        let cached = graph.memory_usage();
+        assert!(cached.caches > 0);
+        assert_eq!(cached.total(), report.total() + cached.caches);
+
+        graph.clear();
+        assert_eq!(graph.memory_usage().vertices, report.vertices);
+        assert_eq!(graph.memory_usage().adjacency, 0);
+    }
+
+    #[cfg(all(feature = "persistent", feature = "random"))]
+    #[test]
+    fn test_persistent_versions_are_independent() {
+        use crate::persistent::ImmutableBullDag;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(1112);
+        let graph: BullDag<(), usize> = crate::generate::random(60, 0.1, &mut rng);
+        let base = ImmutableBullDag::from(&graph);
+        assert_eq!(base.to_dag(), graph);
+
+        let v: Vec<Vertex<(), usize>> = (0..62).map(|i| Vertex::new((), i)).collect();
+        let mut versions = vec![base.clone()];
+        for i in 0..60 {
+            let next = versions[i].add_edge(&(&v[i], &v[60 + i % 2])).unwrap();
+            versions.push(next);
+        }
+        let pruned = versions[60].remove_vertex(&60);
+
+        assert_eq!(versions[0].n_edges(), graph.n_edges());
+        assert_eq!(versions[30].n_edges(), graph.n_edges() + 30);
+        assert!(versions[60].add_edge(&(&v[61], &v[0])).is_err());
+        assert!(pruned.add_edge(&(&v[61], &v[1])).is_err());
+        assert_eq!(pruned.len(), 61);
+        assert!(pruned.to_dag().validate().is_ok());
+        assert!(versions[60].to_dag().validate().is_ok());
+        assert_eq!(base.to_dag(), graph);
+    }
+
+    #[test]
+    fn test_graph_error_is_a_std_error() {
+        use crate::graph::GraphError;
+        use std::error::Error;
+
+        fn connect(graph: &mut BullDag<(), usize>) -> Result<(), Box<dyn Error + Send + Sync>> {
+            graph.add_edge_ix(&0, &1)?;
+            graph.add_edge_ix(&1, &0)?;
+            Ok(())
+        }
+        let mut graph: BullDag<(), usize> = crate::generate::chain(2);
+        let e = connect(&mut graph).unwrap_err();
+        assert_eq!(e.to_string(), "the edge would create a cycle");
+        assert!(e.source().is_none());
+
+        // The wrapped error is passed through
+        #[derive(Debug)]
+        struct ReadError(std::io::Error);
+        impl std::fmt::Display for ReadError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "could not read the graph")
+            }
+        }
+        impl Error for ReadError {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+        let io = std::io::Error::other("disk on fire");
+        let e = GraphError::other(ReadError(io));
+        assert_eq!(e.to_string(), "could not read the graph");
+        assert_eq!(e.source().unwrap().to_string(), "disk on fire");
+        assert!(matches!(e.clone(), GraphError::Other(_)));
+    }
+
+    #[test]
+    fn test_edges_can_be_given_by_index() {
+        use crate::edge::Edge;
+        use crate::graph::GraphError;
+
+        let mut graph: BullDag<(), usize> = BullDag::new();
+        graph.add_vertices(&(0..4).map(|i| Vertex::new((), i)).collect::<Vec<_>>());
+        let results = graph.try_extend_from_edges(vec![
+            Edge::new(0, 1),
+            Edge::new(1, 2),
+            Edge::new(2, 0),
+            Edge::new(7, 0),
+            Edge::new(2, 7),
+        ]);
+        assert!(results[0].is_ok() && results[1].is_ok());
+        assert!(matches!(results[2], Err(GraphError::WouldCycle)));
+        assert!(matches!(results[3], Err(GraphError::NonExistentSource)));
+        assert!(matches!(results[4], Err(GraphError::NonExistentReference)));
+
+        graph.extend_from_edges([(2, 3), (1, 3)]);
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.n_edges(), 4);
+        assert_eq!(graph.topological_order(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_any_hashable_key_is_an_index() {
+        use std::sync::Arc;
+
+        let mut by_pair: BullDag<(), (u64, u64)> = BullDag::new();
+        by_pair.add_edge((Vertex::new((), (0, 1)), Vertex::new((), (1, 0))));
+        assert!(by_pair.has_edge(&(0, 1), &(1, 0)));
+
+        let mut by_hash: BullDag<(), [u8; 20]> = BullDag::new();
+        by_hash.add_edge((Vertex::new((), [0; 20]), Vertex::new((), [1; 20])));
+        assert_eq!(by_hash.get_roots(), [[0; 20]].into());
+
+        let source: Arc<str> = Arc::from("source");
+        let reference: Arc<str> = Arc::from("reference");
+        let mut by_name: BullDag<(), Arc<str>> = BullDag::new();
+        by_name.add_edge((
+            Vertex::new((), source.clone()),
+            Vertex::new((), reference.clone()),
+        ));
+        assert_eq!(by_name.topological_order(), vec![source, reference]);
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_auto_indices_are_unique() {
+        use uuid::Uuid;
+
+        let mut graph: BullDag<usize, Uuid> = BullDag::new();
+        let ids: Vec<Uuid> = (0..100).map(|i| graph.add_vertex_auto(i)).collect();
+        ids.windows(2).for_each(|w| graph.add_edge((w[0], w[1])));
+
+        assert_eq!(graph.len(), 100);
+        assert_eq!(graph.n_edges(), 99);
+        assert_eq!(graph.topological_order(), ids);
+        assert_eq!(graph.get_vertex(ids[42]).unwrap().get_data(), 42);
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_interned_graph_matches_dag() {
+        use crate::edge::Edge;
+        use crate::generate;
+        use crate::interned::InternedBullDag;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashMap;
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let dag: BullDag<usize, usize> = generate::random(200, 0.05, &mut rng);
+        let mut interned = InternedBullDag::from(&dag);
+        assert_eq!(interned.len(), dag.len());
+        assert_eq!(interned.n_edges(), dag.n_edges());
+        assert_eq!(interned.get_roots(), dag.get_roots());
+        assert_eq!(interned.get_leaves(), dag.get_leaves());
+        assert_eq!(
+            interned.to_dag().canonical_hash::<DefaultHasher>(),
+            dag.canonical_hash::<DefaultHasher>()
+        );
+
+        let position: HashMap<usize, usize> = interned
+            .topological_order()
+            .into_iter()
+            .enumerate()
+            .map(|(i, ix)| (ix, i))
+            .collect();
+        assert!(dag
+            .edges
+            .iter()
+            .all(|e| position[&e.get_source()] < position[&e.get_reference()]));
+
+        let removed = interned.remove_vertex(0).unwrap();
+        let mut expected = dag.clone();
+        expected.remove_vertex(0);
+        assert_eq!(removed.get_index(), 0);
+        assert_eq!(interned.n_edges(), expected.n_edges());
+        interned.add_vertex(&Vertex::new(7, 1000));
+        interned.add_edge((1000, 1)).unwrap();
+        assert!(interned.remove_edge(&Edge::new(1000, 1)));
+        interned.remove_vertex(1000);
+        assert_eq!(interned.to_dag().get_roots(), expected.get_roots());
+        assert_eq!(interned.to_dag().n_edges(), expected.n_edges());
+    }
+
+    #[test]
+    fn test_interned_graph_is_smaller_for_hash_indices() {
+        use crate::interned::InternedBullDag;
+
+        let mut dag: BullDag<(), [u8; 32]> = BullDag::new();
+        let vertices: Vec<Vertex<(), [u8; 32]>> = (0..1000u32)
+            .map(|i| {
+                let mut ix = [0; 32];
+                ix[..4].copy_from_slice(&i.to_le_bytes());
+                Vertex::new((), ix)
+            })
+            .collect();
+        for (i, v) in vertices.iter().enumerate().skip(1) {
+            for back in [1, 2, 3] {
+                if back <= i {
+                    dag.add_edge_unchecked((&vertices[i - back], v));
+                }
+            }
+        }
+        dag.invalidate_caches();
+        let interned = InternedBullDag::from(&dag);
+        assert_eq!(interned.n_edges(), dag.n_edges());
+        assert!(interned.memory_usage().total() * 3 < dag.memory_usage().total());
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_compacted_slab_is_in_topological_order() {
+        use crate::generate;
+        use crate::interned::InternedBullDag;
+        use crate::vertex::Direction;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(12);
+        let dag: BullDag<usize, usize> = generate::random(300, 0.03, &mut rng);
+        let mut interned = InternedBullDag::from(&dag);
+        (0..300).step_by(7).for_each(|ix| {
+            interned.remove_vertex(ix);
+        });
+        let mut expected = dag.clone();
+        (0..300).step_by(7).for_each(|ix| {
+            expected.remove_vertex(ix);
+        });
+
+        interned.compact();
+        assert_eq!(interned.len(), expected.len());
+        assert_eq!(interned.n_edges(), expected.n_edges());
+        for id in 0..interned.len() as u32 {
+            let ix = interned.index_at(id).unwrap();
+            assert_eq!(interned.id_of(ix), Some(id));
+            assert!(interned
+                .neighbors_at(id, Direction::Reference)
+                .iter()
+                .all(|r| *r > id));
+        }
+        assert!(interned.index_at(interned.len() as u32).is_none());
+        assert_eq!(interned.cumulative_weights(), expected.cumulative_weights());
+    }
+
+    #[cfg(all(feature = "rayon", feature = "random"))]
+    #[test]
+    fn test_par_iterators_match_sequential() {
+        use crate::generate;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use rayon::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(13);
+        let graph: BullDag<usize, usize> = generate::random(500, 0.02, &mut rng);
+        let graph = graph.map_data(|_| 0).par_map_data(|data| data + 1);
+
+        assert_eq!(graph.par_vertices().count(), graph.len());
+        assert_eq!(graph.par_edges().count(), graph.n_edges());
+        assert!(graph.par_vertices().all(|vtx| vtx.get_data() == 1));
+
+        let doubled = graph.par_map_data(|data| data * 2);
+        assert_eq!(doubled.edges, graph.edges);
+        assert_eq!(doubled.get_roots(), graph.get_roots());
+        assert_eq!(doubled.par_find(|data| *data == 2).len(), graph.len());
+        assert!(graph.par_find(|data| *data == 2).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_batch_reachability_matches_single_queries() {
+        use crate::generate;
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(14);
+        let mut graph: BullDag<usize, usize> = generate::random(200, 0.03, &mut rng);
+        let pairs: Vec<(usize, usize)> = (0..2000)
+            .map(|_| (rng.gen_range(0..20), rng.gen_range(0..210)))
+            .collect();
+        let expected: Vec<bool> = pairs
+            .iter()
+            .map(|(from, to)| graph.is_reachable(from, to))
+            .collect();
+        assert!(expected.iter().any(|r| *r) && expected.iter().any(|r| !r));
+
+        assert_eq!(graph.are_reachable(&pairs), expected);
+        #[cfg(feature = "rayon")]
+        assert_eq!(graph.par_are_reachable(&pairs), expected);
+
+        graph.enable_reachability_index();
+        assert_eq!(graph.are_reachable(&pairs), expected);
+        #[cfg(feature = "rayon")]
+        assert_eq!(graph.par_are_reachable(&pairs), expected);
+    }
+
+    #[test]
+    fn test_parallel_edges_keep_their_keys() {
+        use crate::edge::Edge;
+        use crate::graph::GraphError;
+
+        let mut graph: BullDag<(), &str> = BullDag::new();
+        let v: Vec<Vertex<(), &str>> = ["a", "b", "c"]
+            .into_iter()
+            .map(|ix| Vertex::new((), ix))
+            .collect();
+        graph.add_edge((&v[0], &v[1]));
+        assert!(graph.add_keyed_edge((&v[1], &v[2]), 1).is_err());
+
+        graph.enable_multi_edges();
+        graph.add_keyed_edge(("a", "b"), 7).unwrap();
+        graph.add_keyed_edge((&v[1], &v[2]), 1).unwrap();
+        graph.add_keyed_edge((&v[1], &v[2]), 2).unwrap();
+        graph.add_edge(("b", "c"));
+        assert!(matches!(
+            graph.add_keyed_edge(("c", "a"), 3),
+            Err(GraphError::WouldCycle)
+        ));
+        assert_eq!(graph.edge_keys(&Edge::new("a", "b")), vec![0, 7]);
+        assert_eq!(graph.edge_keys(&Edge::new("b", "c")), vec![1, 2]);
+        assert_eq!(graph.n_keyed_edges(), 4);
+
+        graph.checkpoint("before");
+        graph.reindex(&"b", "m").unwrap();
+        assert_eq!(graph.edge_keys(&Edge::new("a", "m")), vec![0, 7]);
+        assert_eq!(graph.edge_keys(&Edge::new("m", "c")), vec![1, 2]);
+
+        let reversed = graph.reverse();
+        assert_eq!(reversed.edge_keys(&Edge::new("c", "m")), vec![1, 2]);
+
+        let json = serde_json::to_string(&graph).unwrap();
+        let decoded: BullDag<(), &str> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.edge_keys(&Edge::new("m", "c")), vec![1, 2]);
+
+        graph.remove_vertex("m");
+        assert_eq!(graph.n_keyed_edges(), 0);
+        assert!(graph.restore("before"));
+        assert_eq!(graph.n_keyed_edges(), 4);
+
+        graph.disable_multi_edges();
+        assert_eq!(graph.n_keyed_edges(), 2);
+        assert_eq!(graph.edge_keys(&Edge::new("b", "c")), vec![0]);
+    }
+
+    #[test]
+    fn test_edge_kinds_filter_traversals() {
+        use crate::edge::Edge;
+        use crate::vertex::Direction;
+
+        let (parent, reference) = (0, 1);
+        let mut graph: BullDag<(), usize> = BullDag::new();
+        graph.add_vertices(&(0..6).map(|i| Vertex::new((), i)).collect::<Vec<_>>());
+        graph.extend_from_edges([(0, 1), (1, 2), (2, 3), (3, 4)]);
+        graph.add_edge_of_kind((0, 5), reference).unwrap();
+        graph.add_edge_of_kind((5, 4), reference).unwrap();
+        assert!(graph.set_edge_kind(&Edge::new(1, 2), reference));
+        assert!(!graph.set_edge_kind(&Edge::new(4, 0), reference));
+
+        assert_eq!(
+            graph.trace_kind(&4, Direction::Source, parent),
+            vec![2, 3, 4]
+        );
+        let mut by_reference = graph.trace_kind(&4, Direction::Source, reference);
+        by_reference.sort();
+        assert_eq!(by_reference, vec![0, 4, 5]);
+        assert_eq!(
+            graph.neighbors_of_kind(&4, Direction::Source, parent),
+            vec![&3]
+        );
+        assert!(graph.trace_kind(&9, Direction::Source, parent).is_empty());
+
+        graph.reindex(&5, 50).unwrap();
+        assert_eq!(graph.edge_kind(&Edge::new(0, 50)), Some(reference));
+        let reversed = graph.reverse();
+        assert_eq!(reversed.edge_kind(&Edge::new(4, 50)), Some(reference));
+        assert_eq!(reversed.edge_kind(&Edge::new(4, 3)), Some(parent));
+
+        let json = serde_json::to_string(&graph).unwrap();
+        let decoded: BullDag<(), usize> = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.edge_kind(&Edge::new(1, 2)), Some(reference));
+
+        graph.remove_edge(&Edge::new(1, 2));
+        graph.add_edge((1, 2));
+        assert_eq!(graph.edge_kind(&Edge::new(1, 2)), Some(parent));
+        assert_eq!(graph.edge_kind(&Edge::new(2, 1)), None);
+    }
+
+    #[test]
+    fn test_time_windows_only_follow_stamped_edges() {
+        use crate::edge::Edge;
+        use crate::vertex::Direction;
+
+        // A chain 0 -> 1 -> ... -> 9 with the edge into i stamped 10 * i,
+        // except for the edge into 5 which has no time
+        let mut graph: BullDag<(), u64> = BullDag::new();
+        graph.add_vertices(&(0..10).map(|i| Vertex::new((), i)).collect::<Vec<_>>());
+        for i in 1..10 {
+            match i {
+                5 => graph.add_edge((4, 5)),
+                _ => graph.add_edge_at((i - 1, i), 10 * i).unwrap(),
+            }
+        }
+        assert!(graph.add_edge_at((9, 0), 100).is_err());
+        assert!(!graph.set_edge_time(&Edge::new(0, 9), 1));
+
+        let recent: Vec<u64> = graph
+            .edges_in_range(60..)
+            .iter()
+            .map(|e| e.get_reference())
+            .collect();
+        assert_eq!(recent, vec![6, 7, 8, 9]);
+        assert_eq!(graph.edges_in_range(..).len(), 8);
+        assert_eq!(graph.edge_time(&Edge::new(4, 5)), None);
+
+        assert_eq!(
+            graph.trace_in_range(&9, Direction::Source, 70..),
+            vec![6, 7, 8, 9]
+        );
+        assert_eq!(graph.trace_in_range(&9, Direction::Source, ..).len(), 5);
+        assert_eq!(
+            graph.trace_in_range(&0, Direction::Reference, 0..20).len(),
+            2
+        );
+
+        graph.set_edge_time(&Edge::new(4, 5), 50);
+        graph.reindex(&5, 55).unwrap();
+        assert_eq!(graph.edge_time(&Edge::new(4, 55)), Some(50));
+        assert_eq!(graph.edge_time(&Edge::new(55, 6)), Some(60));
+        assert_eq!(graph.reverse().edge_time(&Edge::new(6, 55)), Some(60));
+        graph.remove_vertex(55);
+        assert_eq!(graph.edges_in_range(..).len(), 7);
+    }
+
+    #[test]
+    fn test_weighted_shortest_path_prefers_lighter_route() {
+        use crate::edge::Edge;
+
+        // Two routes from 0 to 3: 0 -> 1 -> 3 weighing 2 + 2, and
+        // 0 -> 2 -> 3 with an unweighted edge weighing 1 + 1
+        let mut graph: BullDag<(), u64> = BullDag::new();
+        graph.add_vertices(&(0..5).map(|i| Vertex::new((), i)).collect::<Vec<_>>());
+        graph.add_edge_weighted((0, 1), 2).unwrap();
+        graph.add_edge_weighted((1, 3), 2).unwrap();
+        graph.add_edge((0, 2));
+        graph.add_edge_weighted((2, 3), 1).unwrap();
+        assert!(graph.add_edge_weighted((3, 0), 1).is_err());
+        assert!(!graph.set_edge_weight(&Edge::new(0, 3), 1));
+        assert_eq!(graph.edge_weight(&Edge::new(0, 2)), Some(1));
+        assert_eq!(graph.edge_weight(&Edge::new(0, 3)), None);
+
+        assert_eq!(
+            graph.shortest_path_weighted(&0, &3),
+            Some((vec![0, 2, 3], 2))
+        );
+        assert_eq!(graph.shortest_path_weighted(&0, &0), Some((vec![0], 0)));
+        assert_eq!(graph.shortest_path_weighted(&0, &4), None);
+        assert_eq!(graph.shortest_path_weighted(&3, &0), None);
+
+        graph.set_edge_weight(&Edge::new(0, 2), 10);
+        assert_eq!(
+            graph.shortest_path_weighted(&0, &3),
+            Some((vec![0, 1, 3], 4))
+        );
+
+        graph.reindex(&1, 11).unwrap();
+        assert_eq!(graph.edge_weight(&Edge::new(11, 3)), Some(2));
+        assert_eq!(
+            graph.reverse().shortest_path_weighted(&3, &0),
+            Some((vec![3, 11, 0], 4))
+        );
+        graph.remove_vertex(11);
+        assert_eq!(
+            graph.shortest_path_weighted(&0, &3),
+            Some((vec![0, 2, 3], 11))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_k_shortest_paths_are_distinct_and_sorted() {
+        use crate::generate;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use std::collections::HashSet;
+
+        // A ladder where each step can go straight or cross over, so there
+        // are 2^3 paths from 0 to 9 of weights 5 to 8
+        let mut graph: BullDag<(), u64> = BullDag::new();
+        graph.add_vertices(&(0..10).map(|i| Vertex::new((), i)).collect::<Vec<_>>());
+        for step in 0..4 {
+            let (a, b) = (2 * step, 2 * step + 1);
+            let (c, d) = (a + 2, b + 2);
+            graph.add_edge_weighted((a, c), 1).unwrap();
+            graph.add_edge_weighted((a, d), 2).unwrap();
+            graph.add_edge_weighted((b, c), 1).unwrap();
+            graph.add_edge_weighted((b, d), 2).unwrap();
+        }
+        let paths = graph.k_shortest_paths(&0, &9, 100);
+        assert_eq!(paths.len(), 8);
+        assert_eq!(
+            paths
+                .iter()
+                .map(|(path, _)| path)
+                .collect::<HashSet<_>>()
+                .len(),
+            8
+        );
+        assert!(paths.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert_eq!(paths[0].1, 5);
+        assert_eq!(paths[7].1, 8);
+        assert_eq!(
+            graph.k_shortest_paths(&0, &8, 1),
+            vec![(vec![0, 2, 4, 6, 8], 4)]
+        );
+        assert!(graph.k_shortest_paths(&0, &9, 0).is_empty());
+        assert!(graph.k_shortest_paths(&9, &0, 5).is_empty());
+
+        // The lightest path is always the one shortest_path_weighted finds
+        let mut rng = StdRng::seed_from_u64(1126);
+        let graph: BullDag<(), usize> = generate::random(40, 0.2, &mut rng);
+        for (from, to) in [(0, 39), (3, 30), (10, 20)] {
+            let paths = graph.k_shortest_paths(&from, &to, 5);
+            assert_eq!(
+                paths.first().map(|(_, weight)| *weight),
+                graph
+                    .shortest_path_weighted(&from, &to)
+                    .map(|(_, weight)| weight)
+            );
+            for (path, weight) in &paths {
+                assert_eq!(path.len() as u64 - 1, *weight);
+                assert!(path.windows(2).all(|w| graph.has_edge(&w[0], &w[1])));
+            }
+        }
+    }
+
+    #[test]
+    fn test_all_topological_sorts_are_valid_and_distinct() {
+        use std::collections::{HashMap, HashSet};
+
+        // No edges, so every permutation is an ordering
+        let mut graph: BullDag<(), u64> = BullDag::new();
+        graph.add_vertices(&(0..5).map(|i| Vertex::new((), i)).collect::<Vec<_>>());
+        assert_eq!(graph.count_topological_sorts(usize::MAX), 120);
+        assert_eq!(graph.count_topological_sorts(7), 7);
+
+        // A diamond 0 -> 1, 2 -> 4 has 2 orderings, and the unconnected 3
+        // can go in any of 5 places in each
+        graph.extend_from_edges([(0, 1), (1, 4), (0, 2), (2, 4)]);
+        let sorts: Vec<Vec<u64>> = graph.all_topological_sorts().collect();
+        assert_eq!(sorts.len(), 10);
+        assert_eq!(sorts.iter().collect::<HashSet<_>>().len(), 10);
+        for sort in &sorts {
+            let position: HashMap<u64, usize> =
+                sort.iter().enumerate().map(|(i, ix)| (*ix, i)).collect();
+            assert_eq!(position.len(), 5);
+            assert!(graph
+                .edges
+                .iter()
+                .all(|e| position[&e.get_source()] < position[&e.get_reference()]));
+        }
+
+        // The iterator doesn't see later changes to the graph
+        let mut sorts = graph.all_topological_sorts();
+        graph.add_edge((4, 3));
+        assert_eq!(sorts.next().map(|sort| sort.len()), Some(5));
+        assert_eq!(graph.count_topological_sorts(usize::MAX), 2);
+
+        let empty: BullDag<(), u64> = BullDag::new();
+        assert_eq!(
+            empty.all_topological_sorts().collect::<Vec<_>>(),
+            vec![Vec::<u64>::new()]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_topological_sort_by_takes_smallest_ready_vertex() {
+        use crate::generate;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use std::collections::HashSet;
+
+        let mut rng = StdRng::seed_from_u64(1128);
+        let graph: BullDag<(), usize> = generate::random(60, 0.05, &mut rng);
+        for cmp in [usize::cmp, |a: &usize, b: &usize| b.cmp(a)] {
+            let order = graph.topological_sort_by(cmp);
+            assert_eq!(order.len(), graph.len());
+
+            // Replay the sort, checking each vertex is ready and no other
+            // ready vertex comes before it
+            let mut placed = HashSet::new();
+            for ix in &order {
+                let ready = |ix: &usize| {
+                    !placed.contains(ix)
+                        && graph.vertices[ix]
+                            .get_sources()
+                            .iter()
+                            .all(|s| placed.contains(*s))
+                };
+                assert!(ready(ix));
+                assert!(graph
+                    .vertices
+                    .keys()
+                    .filter(|other| ready(other))
+                    .all(|other| cmp(ix, other).is_le()));
+                placed.insert(*ix);
+            }
+        }
+
+        let empty: BullDag<(), usize> = BullDag::new();
+        assert!(empty.topological_sort_by(usize::cmp).is_empty());
+    }
+
+    #[test]
+    fn test_stable_topological_sort_follows_insertion_order() {
+        // Vertices added newest index first, with edges between every
+        // third one, so insertion order disagrees with index order
+        let build = || {
+            let mut graph: BullDag<(), u64> = BullDag::new();
+            graph.add_vertices(
+                &(0..30)
+                    .rev()
+                    .map(|i| Vertex::new((), i))
+                    .collect::<Vec<_>>(),
+            );
+            for i in 0..27 {
+                graph.add_edge((i, i + 3));
+            }
+            graph
+        };
+        let mut graph = build();
+        let order = graph.topological_sort_stable();
+        assert_eq!(order, build().topological_sort_stable());
+        // Each chain runs to its end before the next one, added later,
+        // starts
+        assert_eq!(&order[..3], &[2, 5, 8]);
+        assert_eq!((order[10], order[20]), (1, 0));
+        assert_eq!(graph.insertion_seq(&29), Some(0));
+
+        graph.reindex(&0, 100).unwrap();
+        assert_eq!(graph.insertion_seq(&100), Some(29));
+        assert_eq!(graph.topological_sort_stable()[20], 100);
+
+        // Numbers aren't reused once a vertex is removed, and survive a
+        // round trip through serde
+        graph.remove_vertex(1);
+        graph.add_vertex(&Vertex::new((), 1));
+        assert_eq!(graph.insertion_seq(&1), Some(31));
+        let json = serde_json::to_string(&graph).unwrap();
+        let restored: BullDag<(), u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            restored.topological_sort_stable(),
+            graph.topological_sort_stable()
+        );
+        assert_eq!(restored.next_sequence, 32);
+
+        graph.clear();
+        assert!(graph.topological_sort_stable().is_empty());
+        assert_eq!(graph.insertion_seq(&29), None);
+
+        // Bulk loads number the vertices in the order they were added too
+        let mut builder = crate::builder::BullDagBuilder::new();
+        for i in (0..30).rev() {
+            builder.add_vertex(Vertex::new((), i));
+        }
+        builder.add_vertex(Vertex::new((), 29));
+        for i in 0..27 {
+            builder.add_edge(i, i + 3);
+        }
+        let mut built: BullDag<(), u64> = builder.build().unwrap();
+        assert_eq!(
+            built.topological_sort_stable(),
+            build().topological_sort_stable()
+        );
+        assert_eq!(built.insertion_seq(&29), Some(0));
+        built.add_vertex(&Vertex::new((), 30));
+        assert_eq!(built.insertion_seq(&30), Some(30));
+        #[cfg(feature = "persistent")]
+        {
+            let version = crate::persistent::ImmutableBullDag::from(&built)
+                .add_vertex(&Vertex::new((), 31))
+                .to_dag();
+            assert_eq!(version.insertion_seq(&31), Some(31));
+            assert_eq!(version.insertion_seq(&29), Some(0));
+        }
+        let shared = crate::concurrent::SharedBullDag::from(built.clone());
+        shared.add_vertex(&Vertex::new((), 31));
+        assert_eq!(shared.to_dag().insertion_seq(&31), Some(31));
+        assert_eq!(shared.to_dag().insertion_seq(&29), Some(0));
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_post_order_iterators_agree_with_trace() {
+        use crate::generate;
+        use crate::vertex::Direction;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        use std::collections::HashMap;
+
+        let mut rng = StdRng::seed_from_u64(1131);
+        let graph: BullDag<(), usize> = generate::random(80, 0.05, &mut rng);
+        for start in [0, 10, 40, 79] {
+            let post: Vec<usize> = graph.post_order(&start).collect();
+            let mut traced = graph.trace(graph.get_vertex(start).unwrap(), Direction::Reference);
+            let mut sorted = post.clone();
+            sorted.sort();
+            traced.sort();
+            assert_eq!(sorted, traced);
+            assert_eq!(post.last(), Some(&start));
+
+            // Each vertex comes after everything reachable from it, and
+            // the reverse puts it before
+            let position: HashMap<usize, usize> =
+                post.iter().enumerate().map(|(i, ix)| (*ix, i)).collect();
+            for ix in &post {
+                for r in graph.vertices[ix].get_references() {
+                    assert!(position[r] < position[ix]);
+                }
+            }
+            let reverse: Vec<usize> = graph.reverse_post_order(&start).collect();
+            assert_eq!(reverse, post.iter().rev().copied().collect::<Vec<_>>());
+        }
+
+        // Lazy, so taking the first vertex only searches down to a leaf
+        let first = graph.post_order(&0).next().unwrap();
+        assert!(graph.get_leaves().contains(&first));
+        assert_eq!(graph.post_order(&1000).count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_random_walk_follows_edges_to_the_end() {
+        use crate::generate;
+        use crate::vertex::Direction;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(1132);
+        let graph: BullDag<(), usize> = generate::random(60, 0.08, &mut rng);
+        for start in [0, 20, 59] {
+            for direction in [Direction::Reference, Direction::Source] {
+                let path: Vec<usize> = graph.random_walk(&start, direction, &mut rng).collect();
+                assert_eq!(path[0], start);
+                assert!(path.windows(2).all(|w| match direction {
+                    Direction::Reference => graph.has_edge(&w[0], &w[1]),
+                    Direction::Source => graph.has_edge(&w[1], &w[0]),
+                }));
+                let end = path.last().unwrap();
+                match direction {
+                    Direction::Reference => assert!(graph.get_leaves().contains(end)),
+                    Direction::Source => assert!(graph.get_roots().contains(end)),
+                }
+            }
+        }
+
+        // A strong bias always takes the heaviest reference
+        let weights = graph.cumulative_weights();
+        let path: Vec<usize> = graph
+            .random_walk(&0, Direction::Reference, &mut rng)
+            .biased(100.0)
+            .collect();
+        for w in path.windows(2) {
+            let heaviest = graph.vertices[&w[0]]
+                .get_references()
+                .into_iter()
+                .map(|r| weights[r])
+                .max();
+            assert_eq!(Some(weights[&w[1]]), heaviest);
+        }
+
+        // The same seed walks the same path on a separately built copy
+        let copy: BullDag<(), usize> = generate::random(60, 0.08, &mut StdRng::seed_from_u64(1132));
+        let walk = |graph: &BullDag<(), usize>| {
+            graph
+                .random_walk(&0, Direction::Reference, &mut StdRng::seed_from_u64(5))
+                .collect::<Vec<usize>>()
+        };
+        assert_eq!(walk(&graph), walk(&copy));
+        assert_eq!(
+            graph
+                .random_walk(&1000, Direction::Reference, &mut rng)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_readding_a_vertex_is_seen_as_an_update() {
+        use crate::observe::GraphObserver;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Updates(Mutex<Vec<(&'static str, usize, usize)>>);
+
+        impl GraphObserver<usize, &'static str> for Arc<Updates> {
+            fn vertex_updated(&self, index: &&'static str, data: &usize, previous: &usize) {
+                self.0.lock().unwrap().push((*index, *data, *previous));
+            }
+        }
+
+        let updates = Arc::new(Updates::default());
+        let mut graph: BullDag<usize, &'static str> = BullDag::new();
+        graph.add_observer(updates.clone());
+        graph.enable_history();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        graph.add_edge((&v1, &v2));
+        graph.next_version();
+
+        graph.add_vertex(&Vertex::new(50, "source"));
+        assert_eq!(*updates.0.lock().unwrap(), vec![("source", 50, 5)]);
+        assert!(graph.has_edge(&"source", &"reference"));
+
+        let before = graph.at_version(0).unwrap();
+        assert_eq!(before.get_vertex(&"source").unwrap().get_data(), 5);
+        let after = graph.at_version(1).unwrap();
+        assert_eq!(after.get_vertex(&"source").unwrap().get_data(), 50);
+        assert_eq!(after.n_edges(), 1);
+    }
+
+    #[test]
+    fn test_replayed_log_keeps_data_updates() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Shared(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let log = Shared::default();
+        let mut graph: BullDag<usize, String> = BullDag::new();
+        graph.log_ops_to(log.clone());
+        let ix = |i: usize| i.to_string();
+        for i in 0..4 {
+            graph.add_vertex(&Vertex::new(i, ix(i)));
+        }
+        graph.extend_from_edges([(ix(0), ix(1)), (ix(1), ix(2)), (ix(2), ix(3))]);
+
+        graph.upsert(ix(0), || 0, |data| *data += 10);
+        graph.add_vertex(&Vertex::new(20, ix(1)));
+        graph.entry(ix(2)).and_modify(|data| *data = 30);
+        graph.contract_edge(&ix(2), &ix(3), |a, b| a + b).unwrap();
+        let mut changed = graph.clone();
+        changed.get_vertex_mut(ix(1)).unwrap().set_data(21);
+        graph.apply(graph.diff(&changed));
+
+        let replayed: BullDag<usize, String> =
+            BullDag::replay_log(&log.0.lock().unwrap()[..]).unwrap();
+        assert!(replayed == graph);
+        assert_eq!(replayed.get_vertex(ix(0)).unwrap().get_data(), 10);
+        assert_eq!(replayed.get_vertex(ix(1)).unwrap().get_data(), 21);
+        assert_eq!(replayed.get_vertex(ix(2)).unwrap().get_data(), 33);
+    }
+
+    #[test]
+    fn test_undo_restores_replaced_data() {
+        let mut graph: BullDag<usize, &str> = BullDag::new();
+        graph.enable_journal();
+        let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+        let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+        graph.add_edge((&v1, &v2));
+
+        graph.upsert("source", || 0, |data| *data += 1);
+        graph.add_vertex(&Vertex::new(40, "reference"));
+        graph.entry("reference").and_modify(|data| *data = 41);
+        let mut changed = graph.clone();
+        changed.get_vertex_mut("source").unwrap().set_data(60);
+        graph.apply(graph.diff(&changed));
+        let data = |graph: &BullDag<usize, &str>| {
+            (
+                graph.get_vertex("source").unwrap().get_data(),
+                graph.get_vertex("reference").unwrap().get_data(),
+            )
+        };
+        assert_eq!(data(&graph), (60, 41));
+
+        for expected in [(6, 41), (6, 40), (6, 4), (5, 4)] {
+            assert!(graph.undo());
+            assert_eq!(data(&graph), expected);
+        }
+        assert_eq!(graph.n_edges(), 1);
+        while graph.redo() {}
+        assert_eq!(data(&graph), (60, 41));
+    }
+
+    #[test]
+    #[cfg(feature = "random")]
+    fn test_seeded_tip_selection_is_reproducible() {
+        use crate::tips::{RandomWalk, TipSelector, UniformRandom, WeightedByReferences};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        // Graphs built separately iterate their sets in different orders
+        let wide = || {
+            let mut graph: BullDag<(), usize> = BullDag::new();
+            (0..30).for_each(|ix| {
+                graph.add_vertex(&Vertex::new((), ix));
+            });
+            graph
+        };
+        let lattice = crate::generate::diamond_lattice::<()>;
+        let selectors: [Box<dyn TipSelector<(), usize>>; 3] = [
+            Box::new(UniformRandom),
+            Box::new(WeightedByReferences),
+            Box::new(RandomWalk::new(0.5)),
+        ];
+        for selector in &selectors {
+            let select = |graph: &BullDag<(), usize>| {
+                let mut rng = StdRng::seed_from_u64(7);
+                (0..20)
+                    .map(|_| selector.select_tip(graph, &mut rng).unwrap())
+                    .collect::<Vec<usize>>()
+            };
+            assert_eq!(select(&wide()), select(&wide()));
+            assert_eq!(select(&lattice(5, 5)), select(&lattice(5, 5)));
+        }
+    }
+
+    #[test]
+    fn test_undo_restores_everything_removed() {
+        use crate::edge::Edge;
+
+        let mut graph: BullDag<(), usize> = crate::generate::diamond_lattice(3, 3);
+        graph.enable_multi_edges();
+        let (into, out_of) = (Edge::new(1, 4), Edge::new(4, 5));
+        graph.set_edge_kind(&into, 2);
+        graph.set_edge_time(&into, 10);
+        graph.set_edge_weight(&out_of, 7);
+        graph.add_keyed_edge((4, 5), 3).unwrap();
+        graph.set_epoch(&4, 1);
+        graph.add_conflict(&4, &2);
+        let before = graph.clone();
+        let order = graph.topological_sort_stable();
+
+        graph.enable_journal();
+        graph.remove_edge(&out_of);
+        assert!(graph.undo());
+        assert_eq!(graph.edge_weight(&out_of), Some(7));
+        assert_eq!(graph.edge_keys(&out_of), vec![0, 3]);
+
+        graph.remove_vertex(4);
+        assert!(graph.undo());
+        assert_eq!(graph.edge_kind(&into), Some(2));
+        assert_eq!(graph.edge_time(&into), Some(10));
+        assert_eq!(graph.edge_weight(&out_of), Some(7));
+        assert_eq!(graph.edge_keys(&out_of), vec![0, 3]);
+        assert_eq!(graph.epoch_of(&4), Some(1));
+        assert!(graph.has_conflict(&2, &4));
+        assert_eq!(graph.insertion_seq(&4), before.insertion_seq(&4));
+        assert_eq!(graph.topological_sort_stable(), order);
+        assert!(graph == before && graph.get_roots() == before.get_roots());
+
+        assert!(graph.redo());
+        assert!(!graph.contains_vertex(&4));
+        assert!(graph.undo());
+        assert_eq!(graph.edge_keys(&out_of), vec![0, 3]);
+    }
 }
@@ -0,0 +1,64 @@
+/// Builds a [`BullDag`](crate::graph::BullDag) from a terse literal syntax,
+/// each source vertex is followed by the list of vertices it references.
+/// Vertices get `T::default()` as their data unless it is given in a
+/// leading `data` block. As with `add_edge`, an edge that would create a
+/// cycle is not added.
+///
+/// Example
+///
+/// ```
+/// use bulldag::dag;
+/// use bulldag::graph::BullDag;
+///
+/// let graph: BullDag<usize, &str> = dag! {
+///     "a" => ["b", "c"],
+///     "b" => ["d"],
+///     "e" => [],
+/// };
+/// assert!(graph.len() == 5);
+/// assert!(graph.n_edges() == 3);
+///
+/// let graph: BullDag<usize, &str> = dag! {
+///     data: { "a" => 1, "b" => 2, "c" => 3 },
+///     "a" => ["b", "c"],
+///     "b" => ["c"],
+/// };
+/// assert!(graph.get_vertex("b").unwrap().get_data() == 2);
+/// assert!(graph.n_edges() == 3);
+/// ```
+#[macro_export]
+macro_rules! dag {
+    (@vertex $graph:ident, $src:expr, [$($reference:expr),*]) => {{
+        let source = $src;
+        if $graph.get_vertex(source.clone()).is_none() {
+            $graph.add_vertex(&$crate::vertex::Vertex::new(
+                ::std::default::Default::default(),
+                source.clone(),
+            ));
+        }
+        $(
+            $graph.extend(::std::iter::once($crate::edge::Edge::new(
+                source.clone(),
+                $reference,
+            )));
+        )*
+    }};
+    (data: { $($ix:expr => $data:expr),* $(,)? } $(, $src:expr => [$($reference:expr),* $(,)?])* $(,)?) => {{
+        let mut graph = $crate::graph::BullDag::new();
+        $(
+            graph.add_vertex(&$crate::vertex::Vertex::new($data, $ix));
+        )*
+        $(
+            $crate::dag!(@vertex graph, $src, [$($reference),*]);
+        )*
+        graph
+    }};
+    ($($src:expr => [$($reference:expr),* $(,)?]),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut graph = $crate::graph::BullDag::new();
+        $(
+            $crate::dag!(@vertex graph, $src, [$($reference),*]);
+        )*
+        graph
+    }};
+}
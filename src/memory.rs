@@ -0,0 +1,120 @@
+use crate::edge::Edge;
+use crate::epoch::EpochCheckpoint;
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Vertex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::mem::size_of;
+
+/// An estimate of the bytes a graph takes up, returned by
+/// [`BullDag::memory_usage`]. Each field counts the space allocated for
+/// a part of the graph, used or not, so it follows the graph's share of
+/// RSS rather than its length. Memory that the data or the indices own
+/// themselves, like the contents of a `String`, is not counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MemoryReport {
+    /// The map from each index to its vertex, including the data
+    pub vertices: usize,
+    /// The sets of sources and references of every vertex
+    pub adjacency: usize,
+    /// The set of edges, their kinds, times and weights, and their keys if
+    /// multi-edges are enabled
+    pub edges: usize,
+    /// The roots, leaves, epochs, epoch checkpoints, conflicts and
+    /// insertion order
+    pub bookkeeping: usize,
+    /// The cached topological order and the reachability index, which are
+    /// rebuilt on demand
+    pub caches: usize,
+}
+
+impl MemoryReport {
+    pub fn total(&self) -> usize {
+        self.vertices + self.adjacency + self.edges + self.bookkeeping + self.caches
+    }
+}
+
+/// The bytes allocated for a hash table of entries of type `E` with room
+/// for `capacity` of them, following the layout of the standard library's
+/// tables: a power of two number of buckets, at most 7/8 of which are
+/// used, each with a control byte, plus a group of trailing control bytes
+pub(crate) fn table_bytes<E>(capacity: usize) -> usize {
+    let buckets = match capacity {
+        0 => return 0,
+        1..=3 => 4,
+        4..=7 => 8,
+        _ => (capacity * 8 / 7).next_power_of_two(),
+    };
+    buckets * (size_of::<E>() + 1) + 16
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Estimates the memory the graph uses, see [`MemoryReport`]
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let before = graph.memory_usage();
+    /// assert!(before.total() == 0);
+    ///
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    /// let after = graph.memory_usage();
+    /// assert!(after.vertices > 0 && after.adjacency > 0 && after.edges > 0);
+    /// ```
+    pub fn memory_usage(&self) -> MemoryReport {
+        let conflicts: usize = table_bytes::<(Ix, HashSet<Ix>)>(self.conflicts.capacity())
+            + self
+                .conflicts
+                .values()
+                .map(|set| table_bytes::<Ix>(set.capacity()))
+                .sum::<usize>();
+        // A B-tree set of keys is counted as just its keys
+        let edge_keys: usize = self.edge_keys.as_ref().map_or(0, |keys| {
+            keys.heap_bytes()
+                + keys
+                    .values()
+                    .map(|keys| keys.len() * size_of::<u64>())
+                    .sum::<usize>()
+        });
+        let checkpoints: usize = self.epoch_checkpoints.capacity()
+            * size_of::<EpochCheckpoint<Ix>>()
+            + self
+                .epoch_checkpoints
+                .iter()
+                .map(|checkpoint| table_bytes::<Ix>(checkpoint.roots.capacity()))
+                .sum::<usize>();
+
+        MemoryReport {
+            vertices: table_bytes::<(Ix, Vertex<T, Ix>)>(self.vertices.capacity()),
+            adjacency: self.vertices.values().map(Vertex::adjacency_bytes).sum(),
+            edges: table_bytes::<Edge<Ix>>(self.edges.capacity())
+                + self.edge_kinds.heap_bytes()
+                + self.edge_times.heap_bytes()
+                + self.edge_weights.heap_bytes()
+                + edge_keys,
+            bookkeeping: table_bytes::<Ix>(self.roots.capacity())
+                + table_bytes::<Ix>(self.leaves.capacity())
+                + table_bytes::<(Ix, u64)>(self.epochs.capacity())
+                + table_bytes::<(Ix, u64)>(self.sequence.capacity())
+                + checkpoints
+                + conflicts,
+            caches: self.topo_order.get().map_or(0, |order| order.heap_bytes())
+                + self
+                    .reachability
+                    .as_ref()
+                    .and_then(|index| index.get())
+                    .map_or(0, |index| index.heap_bytes()),
+        }
+    }
+}
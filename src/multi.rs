@@ -0,0 +1,162 @@
+use crate::edge::{Edge, EdgeMap, IntoEdgeSpec};
+use crate::graph::{BullDag, GraphError, GraphResult};
+use crate::index::Index;
+use crate::observe::Event;
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Lets the graph hold parallel edges, several edges from one vertex to
+    /// another told apart by a `u64` key such as a port number. Every edge
+    /// already in the graph, and every edge added without a key from now
+    /// on, gets the key 0. Does nothing if multi-edges are already enabled.
+    ///
+    /// Parallel edges only differ in their keys: traversals, orders,
+    /// equality, hashes, diffs, exports, observers and the journal all see
+    /// each pair of connected vertices as one edge.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::edge::Edge;
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.enable_multi_edges();
+    /// graph.add_keyed_edge((&v1, &v2), 1).unwrap();
+    /// graph.add_keyed_edge((&v1, &v2), 2).unwrap();
+    ///
+    /// let edge = Edge::new("source", "reference");
+    /// assert!(graph.n_edges() == 1 && graph.n_keyed_edges() == 2);
+    /// assert!(graph.edge_keys(&edge) == vec![1, 2]);
+    ///
+    /// assert!(graph.remove_keyed_edge(&edge, 1));
+    /// assert!(graph.has_edge(&"source", &"reference"));
+    /// assert!(graph.remove_keyed_edge(&edge, 2));
+    /// assert!(!graph.has_edge(&"source", &"reference"));
+    /// ```
+    pub fn enable_multi_edges(&mut self) {
+        if self.edge_keys.is_some() {
+            return;
+        }
+        let mut keys = EdgeMap::default();
+        for e in &self.edges {
+            keys.insert(e, BTreeSet::from([0]));
+        }
+        self.edge_keys = Some(keys);
+        self.notify(Event::MultiEdgesSet(true));
+    }
+
+    /// Drops every key, leaving one edge between each pair of vertices
+    /// that had any
+    pub fn disable_multi_edges(&mut self) {
+        if self.edge_keys.take().is_some() {
+            self.notify(Event::MultiEdgesSet(false));
+        }
+    }
+
+    pub fn multi_edges_enabled(&self) -> bool {
+        self.edge_keys.is_some()
+    }
+
+    /// Adds an edge with the given key, like [`BullDag::try_add_edge`]. If
+    /// the vertices are already connected the key is added to the edge's
+    /// keys, otherwise the new edge has only this key. Fails with
+    /// `GraphError::Other` if multi-edges are not enabled.
+    pub fn add_keyed_edge(&mut self, edge: impl IntoEdgeSpec<T, Ix>, key: u64) -> GraphResult {
+        if self.edge_keys.is_none() {
+            return Err(GraphError::other("multi-edges are not enabled"));
+        }
+        let spec = edge.into_edge_spec();
+        let e = spec.edge();
+        let existed = self.edges.contains(&e);
+        self.try_add_edge(spec)?;
+
+        if let Some(keys) = self.edge_keys_mut(&e) {
+            if !existed {
+                keys.clear();
+            }
+            keys.insert(key);
+            let keys = keys.clone();
+            self.notify(Event::EdgeKeysSet(&e, &keys));
+        }
+        Ok(())
+    }
+
+    /// Removes one key of an edge, and the edge itself along with its last
+    /// key. Returns false if the edge doesn't have the key.
+    pub fn remove_keyed_edge(&mut self, edge: &Edge<Ix>, key: u64) -> bool {
+        let Some(keys) = self.edge_keys_mut(edge) else {
+            return false;
+        };
+        if !keys.remove(&key) {
+            return false;
+        }
+        match keys.is_empty() {
+            true => {
+                self.remove_edge(edge);
+            }
+            false => {
+                let keys = keys.clone();
+                self.notify(Event::EdgeKeysSet(edge, &keys));
+            }
+        }
+        true
+    }
+
+    /// Returns the keys of an edge in increasing order, `[0]` for an edge of
+    /// a graph without multi-edges and nothing if there is no such edge
+    pub fn edge_keys(&self, edge: &Edge<Ix>) -> Vec<u64> {
+        if !self.edges.contains(edge) {
+            return vec![];
+        }
+        match &self.edge_keys {
+            Some(keys) => keys
+                .get(edge)
+                .map_or(vec![], |keys| keys.iter().copied().collect()),
+            None => vec![0],
+        }
+    }
+
+    /// Returns the number of edges counting each parallel edge, where
+    /// [`BullDag::n_edges`] counts the pairs of connected vertices
+    pub fn n_keyed_edges(&self) -> usize {
+        match &self.edge_keys {
+            Some(keys) => keys.values().map(|keys| keys.len()).sum(),
+            None => self.n_edges(),
+        }
+    }
+
+    fn edge_keys_mut(&mut self, edge: &Edge<Ix>) -> Option<&mut BTreeSet<u64>> {
+        self.edge_keys.as_mut()?.get_mut(edge)
+    }
+
+    /// Gives a new edge the key 0 if multi-edges are enabled
+    pub(crate) fn add_default_edge_key(&mut self, edge: &Edge<Ix>) {
+        if let Some(keys) = self.edge_keys.as_mut() {
+            if keys.get(edge).is_none() {
+                keys.insert(edge, BTreeSet::from([0]));
+            }
+        }
+    }
+
+    /// Drops the keys of a removed edge
+    pub(crate) fn remove_edge_keys(&mut self, edge: &Edge<Ix>) -> Option<BTreeSet<u64>> {
+        self.edge_keys.as_mut()?.remove(edge)
+    }
+
+    /// Replaces the keys of an edge that is in the graph, if multi-edges
+    /// are enabled
+    pub(crate) fn set_edge_keys(&mut self, edge: &Edge<Ix>, new: BTreeSet<u64>) {
+        if let Some(keys) = self.edge_keys_mut(edge) {
+            keys.clone_from(&new);
+            self.notify(Event::EdgeKeysSet(edge, &new));
+        }
+    }
+}
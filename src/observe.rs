@@ -0,0 +1,349 @@
+use crate::edge::Edge;
+use crate::graph::{BullDag, GraphError};
+use crate::index::Index;
+use crate::vertex::Vertex;
+use std::any::Any;
+use std::collections::BTreeSet;
+use std::fmt::{self, Debug};
+
+/// Receives notifications about changes to a [`BullDag`], register one
+/// with [`BullDag::add_observer`]. Every method is a no-op by default so
+/// an observer only implements the events it cares about.
+pub trait GraphObserver<T: Clone + Debug, Ix: Index + Debug>: Send + Sync {
+    /// Called after a new vertex is added to the graph
+    fn vertex_added(&self, _vertex: &Vertex<T, Ix>) {}
+    /// Called after the data of a vertex already in the graph is replaced
+    /// or updated, with the data it had before
+    fn vertex_updated(&self, _index: &Ix, _data: &T, _previous: &T) {}
+    /// Called after a new edge is added to the graph
+    fn edge_added(&self, _edge: &Edge<Ix>) {}
+    /// Called when an edge is not added, with the reason
+    fn edge_rejected(&self, _edge: &Edge<Ix>, _reason: &GraphError) {}
+    /// Called after an edge is removed, including each edge removed along
+    /// with a vertex
+    fn edge_removed(&self, _edge: &Edge<Ix>) {}
+    /// Called after a vertex, and with it all of its edges, is removed
+    fn vertex_removed(&self, _vertex: &Vertex<T, Ix>) {}
+    /// Called after the kind of an edge is set
+    fn edge_kind_set(&self, _edge: &Edge<Ix>, _kind: u32) {}
+    /// Called after an edge is stamped with a time
+    fn edge_time_set(&self, _edge: &Edge<Ix>, _at: u64) {}
+    /// Called after the weight of an edge is set
+    fn edge_weight_set(&self, _edge: &Edge<Ix>, _weight: u64) {}
+    /// Called after keys are added to or removed from an edge, with every
+    /// key it has now. An edge that loses its last key is removed instead.
+    fn edge_keys_set(&self, _edge: &Edge<Ix>, _keys: &BTreeSet<u64>) {}
+    /// Called after multi-edges are enabled or disabled
+    fn multi_edges_set(&self, _enabled: bool) {}
+    /// Called after a vertex is tagged with an epoch
+    fn epoch_set(&self, _index: &Ix, _epoch: u64) {}
+    /// Called after a conflict between two vertices is declared
+    fn conflict_added(&self, _a: &Ix, _b: &Ix) {}
+    /// Called after a conflict is withdrawn, but not when it is dropped
+    /// along with one of its vertices
+    fn conflict_removed(&self, _a: &Ix, _b: &Ix) {}
+}
+
+/// A change to the graph, passed to the observers
+pub(crate) enum Event<'a, T: Clone + Debug, Ix: Index + Debug> {
+    VertexAdded(&'a Vertex<T, Ix>),
+    VertexUpdated {
+        index: &'a Ix,
+        data: &'a T,
+        previous: &'a T,
+    },
+    EdgeAdded(&'a Edge<Ix>),
+    EdgeRejected(&'a Edge<Ix>, &'a GraphError),
+    EdgeRemoved(&'a Edge<Ix>),
+    VertexRemoved(&'a Vertex<T, Ix>),
+    EdgeKindSet(&'a Edge<Ix>, u32),
+    EdgeTimeSet(&'a Edge<Ix>, u64),
+    EdgeWeightSet(&'a Edge<Ix>, u64),
+    EdgeKeysSet(&'a Edge<Ix>, &'a BTreeSet<u64>),
+    MultiEdgesSet(bool),
+    EpochSet(&'a Ix, u64),
+    ConflictAdded(&'a Ix, &'a Ix),
+    ConflictRemoved(&'a Ix, &'a Ix),
+}
+
+type Dispatch<T, Ix> = fn(&(dyn Any + Send + Sync), &Event<'_, T, Ix>);
+
+/// The observers registered with a graph. They belong to that graph
+/// instance only: clones and deserialized graphs start without any.
+///
+/// Each observer is stored type-erased next to a function that knows its
+/// concrete type, rather than as a `Box<dyn GraphObserver<T, Ix>>`, so
+/// that holding observers doesn't make the drop of every `BullDag`
+/// require its indices to outlive it (which would break graphs that
+/// borrow their indices, e.g. `BullDag<T, &str>`).
+pub(crate) struct Observers<T: Clone + Debug, Ix: Index + Debug>(
+    Vec<(Box<dyn Any + Send + Sync>, Dispatch<T, Ix>)>,
+);
+
+impl<T: Clone + Debug, Ix: Index + Debug> Default for Observers<T, Ix> {
+    fn default() -> Self {
+        Observers(vec![])
+    }
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> Clone for Observers<T, Ix> {
+    fn clone(&self) -> Self {
+        Observers::default()
+    }
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> Debug for Observers<T, Ix> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+fn dispatch<T, Ix, O>(observer: &(dyn Any + Send + Sync), event: &Event<'_, T, Ix>)
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+    O: GraphObserver<T, Ix> + 'static,
+{
+    let Some(observer) = observer.downcast_ref::<O>() else {
+        return;
+    };
+    match event {
+        Event::VertexAdded(vertex) => observer.vertex_added(vertex),
+        Event::VertexUpdated {
+            index,
+            data,
+            previous,
+        } => observer.vertex_updated(index, data, previous),
+        Event::EdgeAdded(edge) => observer.edge_added(edge),
+        Event::EdgeRejected(edge, reason) => observer.edge_rejected(edge, reason),
+        Event::EdgeRemoved(edge) => observer.edge_removed(edge),
+        Event::VertexRemoved(vertex) => observer.vertex_removed(vertex),
+        Event::EdgeKindSet(edge, kind) => observer.edge_kind_set(edge, *kind),
+        Event::EdgeTimeSet(edge, at) => observer.edge_time_set(edge, *at),
+        Event::EdgeWeightSet(edge, weight) => observer.edge_weight_set(edge, *weight),
+        Event::EdgeKeysSet(edge, keys) => observer.edge_keys_set(edge, keys),
+        Event::MultiEdgesSet(enabled) => observer.multi_edges_set(*enabled),
+        Event::EpochSet(index, epoch) => observer.epoch_set(index, *epoch),
+        Event::ConflictAdded(a, b) => observer.conflict_added(a, b),
+        Event::ConflictRemoved(a, b) => observer.conflict_removed(a, b),
+    }
+}
+
+impl<T: Clone + Debug, Ix: Index + Debug> Observers<T, Ix> {
+    pub(crate) fn notify(&self, event: Event<'_, T, Ix>) {
+        self.0
+            .iter()
+            .for_each(|(observer, dispatch)| dispatch(observer.as_ref(), &event));
+    }
+}
+
+/// Adapts a closure to a single [`GraphObserver`] event
+struct OnVertexAdded<F>(F);
+struct OnEdgeAdded<F>(F);
+struct OnEdgeRejected<F>(F);
+struct OnVertexRemoved<F>(F);
+
+impl<T, Ix, F> GraphObserver<T, Ix> for OnVertexAdded<F>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+    F: Fn(&Vertex<T, Ix>) + Send + Sync,
+{
+    fn vertex_added(&self, vertex: &Vertex<T, Ix>) {
+        (self.0)(vertex)
+    }
+}
+
+impl<T, Ix, F> GraphObserver<T, Ix> for OnEdgeAdded<F>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+    F: Fn(&Edge<Ix>) + Send + Sync,
+{
+    fn edge_added(&self, edge: &Edge<Ix>) {
+        (self.0)(edge)
+    }
+}
+
+impl<T, Ix, F> GraphObserver<T, Ix> for OnEdgeRejected<F>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+    F: Fn(&Edge<Ix>, &GraphError) + Send + Sync,
+{
+    fn edge_rejected(&self, edge: &Edge<Ix>, reason: &GraphError) {
+        (self.0)(edge, reason)
+    }
+}
+
+impl<T, Ix, F> GraphObserver<T, Ix> for OnVertexRemoved<F>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+    F: Fn(&Vertex<T, Ix>) + Send + Sync,
+{
+    fn vertex_removed(&self, vertex: &Vertex<T, Ix>) {
+        (self.0)(vertex)
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + 'static,
+    Ix: Index + Debug + 'static,
+{
+    /// Registers an observer to be notified of changes to this graph
+    pub fn add_observer<O: GraphObserver<T, Ix> + 'static>(&mut self, observer: O) {
+        self.observers
+            .0
+            .push((Box::new(observer), dispatch::<T, Ix, O>));
+    }
+
+    /// Calls `f` after a new vertex is added
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// let added = Arc::new(AtomicUsize::new(0));
+    /// let counter = added.clone();
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// graph.on_vertex_added(move |_| {
+    ///     counter.fetch_add(1, Ordering::SeqCst);
+    /// });
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    /// assert!(added.load(Ordering::SeqCst) == 2);
+    /// ```
+    pub fn on_vertex_added(&mut self, f: impl Fn(&Vertex<T, Ix>) + Send + Sync + 'static) {
+        self.add_observer(OnVertexAdded(f));
+    }
+
+    /// Calls `f` after a new edge is added
+    pub fn on_edge_added(&mut self, f: impl Fn(&Edge<Ix>) + Send + Sync + 'static) {
+        self.add_observer(OnEdgeAdded(f));
+    }
+
+    /// Calls `f` when an edge is not added because it would create a cycle
+    /// or one of its vertices is missing
+    pub fn on_edge_rejected(&mut self, f: impl Fn(&Edge<Ix>, &GraphError) + Send + Sync + 'static) {
+        self.add_observer(OnEdgeRejected(f));
+    }
+
+    /// Calls `f` after a vertex is removed
+    pub fn on_vertex_removed(&mut self, f: impl Fn(&Vertex<T, Ix>) + Send + Sync + 'static) {
+        self.add_observer(OnVertexRemoved(f));
+    }
+
+    /// Removes every registered observer
+    pub fn clear_observers(&mut self) {
+        self.observers.0.clear();
+    }
+}
+
+/// A change to a graph, as sent to the receivers returned by
+/// [`BullDag::subscribe`]
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub enum GraphEvent<Ix: Index + Debug> {
+    VertexAdded(Ix),
+    /// The data of the vertex was replaced or updated, its edges are
+    /// unchanged
+    VertexUpdated(Ix),
+    EdgeAdded(Edge<Ix>),
+    EdgeRejected {
+        edge: Edge<Ix>,
+        reason: GraphError,
+    },
+    /// The edge was removed, either on its own or as one of the edges of
+    /// a removed vertex, in which case a `Pruned` event follows
+    EdgeRemoved(Edge<Ix>),
+    /// The vertex was removed along with all of its edges, by
+    /// [`BullDag::remove_vertex`] or one of the methods built on it such
+    /// as [`BullDag::prune_below_depth`]
+    Pruned(Ix),
+}
+
+/// Forwards every event to a broadcast channel
+#[cfg(feature = "tokio")]
+struct Broadcaster<Ix: Index + Debug>(tokio::sync::broadcast::Sender<GraphEvent<Ix>>);
+
+#[cfg(feature = "tokio")]
+impl<T, Ix> GraphObserver<T, Ix> for Broadcaster<Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Send + Sync,
+{
+    // Sending only fails when every receiver has been dropped, which is
+    // not the graph's concern.
+    fn vertex_added(&self, vertex: &Vertex<T, Ix>) {
+        let _ = self.0.send(GraphEvent::VertexAdded(vertex.get_index()));
+    }
+
+    fn vertex_updated(&self, index: &Ix, _data: &T, _previous: &T) {
+        let _ = self.0.send(GraphEvent::VertexUpdated(index.clone()));
+    }
+
+    fn edge_added(&self, edge: &Edge<Ix>) {
+        let _ = self.0.send(GraphEvent::EdgeAdded(edge.clone()));
+    }
+
+    fn edge_rejected(&self, edge: &Edge<Ix>, reason: &GraphError) {
+        let _ = self.0.send(GraphEvent::EdgeRejected {
+            edge: edge.clone(),
+            reason: reason.clone(),
+        });
+    }
+
+    fn edge_removed(&self, edge: &Edge<Ix>) {
+        let _ = self.0.send(GraphEvent::EdgeRemoved(edge.clone()));
+    }
+
+    fn vertex_removed(&self, vertex: &Vertex<T, Ix>) {
+        let _ = self.0.send(GraphEvent::Pruned(vertex.get_index()));
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + 'static,
+    Ix: Index + Debug + Send + Sync + 'static,
+{
+    /// Returns a receiver of every subsequent change to the graph, with
+    /// room for 1024 events, see [`BullDag::subscribe_with_capacity`]
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::observe::GraphEvent;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let mut events = graph.subscribe();
+    /// graph.add_vertex(&Vertex::new(5, "source"));
+    ///
+    /// assert!(matches!(events.try_recv(), Ok(GraphEvent::VertexAdded("source"))));
+    /// ```
+    pub fn subscribe(&mut self) -> tokio::sync::broadcast::Receiver<GraphEvent<Ix>> {
+        self.subscribe_with_capacity(1024)
+    }
+
+    /// Returns a receiver of every subsequent change to the graph. A
+    /// receiver that falls more than `capacity` events behind skips the
+    /// oldest ones and gets a `Lagged` error, as with any tokio broadcast
+    /// channel.
+    pub fn subscribe_with_capacity(
+        &mut self,
+        capacity: usize,
+    ) -> tokio::sync::broadcast::Receiver<GraphEvent<Ix>> {
+        let (sender, receiver) = tokio::sync::broadcast::channel(capacity);
+        self.add_observer(Broadcaster(sender));
+        receiver
+    }
+}
@@ -0,0 +1,107 @@
+use crate::edge::Edge;
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::interop::AdjacencyList;
+use crate::vertex::Vertex;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::Debug;
+
+/// Deterministic counterparts of the graph's queries and exports. The
+/// graph keeps its vertices and edges in hash maps and sets, whose
+/// iteration order changes from run to run, so these sort by index to give
+/// the same result for the same graph every time, however it was built.
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    /// Returns every vertex index in ascending order
+    pub fn sorted_indices(&self) -> Vec<Ix> {
+        let mut indices: Vec<Ix> = self.vertices.keys().cloned().collect();
+        indices.sort();
+        indices
+    }
+
+    /// Returns every vertex in ascending order of index
+    pub fn sorted_vertices(&self) -> Vec<&Vertex<T, Ix>> {
+        let mut vertices: Vec<&Vertex<T, Ix>> = self.vertices.values().collect();
+        vertices.sort_by_key(|vtx| vtx.get_index());
+        vertices
+    }
+
+    /// Returns every edge in ascending order of source, then reference
+    pub fn sorted_edges(&self) -> Vec<Edge<Ix>> {
+        let mut edges: Vec<Edge<Ix>> = self.edges.iter().cloned().collect();
+        edges.sort_by_key(|e| (e.get_source(), e.get_reference()));
+        edges
+    }
+
+    /// Returns every vertex index, each after all of its sources, choosing
+    /// the smallest index whenever more than one vertex is ready, so the
+    /// order only depends on the graph's vertices and edges
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, u32> = BullDag::new();
+    /// let v: Vec<Vertex<usize, u32>> = (0..4).map(|i| Vertex::new(0, i)).collect();
+    /// graph.extend_from_edges(&[(&v[3], &v[1]), (&v[2], &v[0]), (&v[0], &v[1])]);
+    ///
+    /// assert!(graph.sorted_topological_order() == vec![2, 0, 3, 1]);
+    /// ```
+    pub fn sorted_topological_order(&self) -> Vec<Ix> {
+        let mut in_degree: HashMap<&Ix, usize> = self
+            .vertices
+            .iter()
+            .map(|(ix, vtx)| (ix, vtx.n_sources()))
+            .collect();
+        let mut ready: BinaryHeap<Reverse<&Ix>> = in_degree
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(ix, _)| Reverse(*ix))
+            .collect();
+        let mut order = Vec::with_capacity(self.vertices.len());
+
+        while let Some(Reverse(ix)) = ready.pop() {
+            order.push(ix.clone());
+            if let Some(vtx) = self.vertices.get(ix) {
+                for r in vtx.get_references() {
+                    if let Some(d) = in_degree.get_mut(r) {
+                        *d -= 1;
+                        if *d == 0 {
+                            ready.push(Reverse(r));
+                        }
+                    }
+                }
+            }
+        }
+
+        order
+    }
+
+    /// Converts the graph to an [`AdjacencyList`] with its vertices and
+    /// edges sorted by index
+    pub fn to_sorted_adjacency_list(&self) -> AdjacencyList<T, Ix> {
+        let mut list = AdjacencyList::from(self);
+        list.vertices.sort_by(|a, b| a.index.cmp(&b.index));
+        list.edges.sort();
+        list
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Serialize,
+    Ix: Index + Debug + Ord + Serialize,
+{
+    /// Serializes the graph in the [`AdjacencyList`] format with its
+    /// vertices and edges sorted, so that equal graphs always serialize to
+    /// the same string
+    pub fn to_sorted_adjacency_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&self.to_sorted_adjacency_list())
+    }
+}
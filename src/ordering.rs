@@ -0,0 +1,359 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Direction;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+
+/// A consensus rule that puts every vertex of a graph in one canonical
+/// total order and picks the tip to build on. The rule is passed in to
+/// [`BullDag::canonical_order`] and [`BullDag::selected_tip`], so it can be
+/// swapped without changing the graph.
+pub trait OrderingRule<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns every vertex of the graph, each after all of its sources
+    fn order(&self, graph: &BullDag<T, Ix>) -> Vec<Ix>;
+
+    /// Returns the tip new vertices should reference, by default the last
+    /// leaf in the order, or `None` for an empty graph
+    fn selected_tip(&self, graph: &BullDag<T, Ix>) -> Option<Ix> {
+        let leaves = graph.get_leaves();
+        self.order(graph)
+            .into_iter()
+            .rev()
+            .find(|ix| leaves.contains(ix))
+    }
+}
+
+/// FNV-1a, a hash whose output only depends on the bytes written to it,
+/// unlike the randomly keyed `DefaultHasher`
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The key ties are broken by, the hash of the index and then the index
+/// itself should two hashes collide
+fn tiebreak<Ix: Hash + Ord + Clone>(ix: &Ix) -> (u64, Ix) {
+    let mut hasher = Fnv(0xcbf29ce484222325);
+    ix.hash(&mut hasher);
+    (hasher.finish(), ix.clone())
+}
+
+/// A vertex ready to be ordered, compared with the caller's comparator
+/// reversed so that the max-heap of ready vertices pops the smallest
+struct ByComparator<'a, Ix, F> {
+    ix: &'a Ix,
+    cmp: &'a F,
+}
+
+impl<Ix, F: Fn(&Ix, &Ix) -> Ordering> PartialEq for ByComparator<'_, Ix, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<Ix, F: Fn(&Ix, &Ix) -> Ordering> Eq for ByComparator<'_, Ix, F> {}
+
+impl<Ix, F: Fn(&Ix, &Ix) -> Ordering> PartialOrd for ByComparator<'_, Ix, F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Ix, F: Fn(&Ix, &Ix) -> Ordering> Ord for ByComparator<'_, Ix, F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.cmp)(other.ix, self.ix)
+    }
+}
+
+/// Orders the vertices topologically, taking the vertex whose index has
+/// the smallest hash whenever more than one is ready. Hashing rather than
+/// comparing the indices themselves keeps producers from getting ahead in
+/// the order by choosing small indices. The hash is FNV-1a over the bytes
+/// the index's `Hash` impl writes, which is the same from run to run but,
+/// as those bytes can be native endian, not always across platforms.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::ordering::HashTiebreak;
+/// use bulldag::vertex::Vertex;
+///
+/// let mut graph: BullDag<usize, &str> = BullDag::new();
+/// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+/// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+/// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+/// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3)]);
+///
+/// let order = graph.canonical_order(&HashTiebreak);
+/// assert!(order[0] == "source" && order.len() == 3);
+/// assert!(order == graph.canonical_order(&HashTiebreak));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HashTiebreak;
+
+impl HashTiebreak {
+    /// Kahn's algorithm over `vertices` only, with `ready` as a min-heap
+    fn order_of<T, Ix>(graph: &BullDag<T, Ix>, vertices: &HashSet<Ix>) -> Vec<Ix>
+    where
+        T: Clone + Debug,
+        Ix: Index + Debug + Ord,
+    {
+        let mut in_degree: HashMap<&Ix, usize> = vertices
+            .iter()
+            .map(|ix| {
+                let sources = graph.vertices[ix].get_sources();
+                (
+                    ix,
+                    sources
+                        .into_iter()
+                        .filter(|s| vertices.contains(*s))
+                        .count(),
+                )
+            })
+            .collect();
+        let mut ready: BinaryHeap<Reverse<((u64, Ix), &Ix)>> = in_degree
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(ix, _)| Reverse((tiebreak(*ix), *ix)))
+            .collect();
+        let mut order = Vec::with_capacity(vertices.len());
+
+        while let Some(Reverse((_, ix))) = ready.pop() {
+            order.push(ix.clone());
+            for r in graph.vertices[ix].get_references() {
+                if let Some(d) = in_degree.get_mut(r) {
+                    *d -= 1;
+                    if *d == 0 {
+                        ready.push(Reverse((tiebreak(r), r)));
+                    }
+                }
+            }
+        }
+
+        order
+    }
+}
+
+impl<T, Ix> OrderingRule<T, Ix> for HashTiebreak
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    fn order(&self, graph: &BullDag<T, Ix>) -> Vec<Ix> {
+        let all: HashSet<Ix> = graph.vertices.keys().cloned().collect();
+        HashTiebreak::order_of(graph, &all)
+    }
+}
+
+/// GHOST fork choice: starting from the heaviest root, repeatedly follow
+/// the reference with the most vertices at or after it, until reaching a
+/// tip. Ties go to the smaller hash, as with [`HashTiebreak`]. Vertices are
+/// ordered along that chain, each chain vertex preceded by whatever of its
+/// past hasn't been ordered yet, and the vertices off the chain's past
+/// come last. Each group is ordered with [`HashTiebreak`].
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::ordering::{HeaviestSubtree, OrderingRule};
+/// use bulldag::vertex::Vertex;
+///
+/// // "a" has a heavier subtree than "b", although "b" has the longer chain
+/// let mut graph: BullDag<usize, &str> = BullDag::new();
+/// let v: Vec<Vertex<usize, &str>> = ["genesis", "a", "a1", "a2", "a3", "b", "b1", "b2"]
+///     .into_iter()
+///     .map(|ix| Vertex::new(0, ix))
+///     .collect();
+/// graph.extend_from_edges(&[
+///     (&v[0], &v[1]), (&v[1], &v[2]), (&v[1], &v[3]), (&v[1], &v[4]),
+///     (&v[0], &v[5]), (&v[5], &v[6]), (&v[6], &v[7]),
+/// ]);
+///
+/// assert!(HeaviestSubtree.selected_tip(&graph).unwrap().starts_with('a'));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeaviestSubtree;
+
+impl HeaviestSubtree {
+    fn chain<T, Ix>(graph: &BullDag<T, Ix>) -> Vec<Ix>
+    where
+        T: Clone + Debug,
+        Ix: Index + Debug + Ord,
+    {
+        let weights = graph.cumulative_weights();
+        let heaviest = |candidates: Vec<&Ix>| {
+            candidates
+                .into_iter()
+                .max_by_key(|ix| (weights[*ix], Reverse(tiebreak(*ix))))
+                .cloned()
+        };
+        let mut chain: Vec<Ix> = vec![];
+        let mut next = heaviest(graph.roots.iter().collect());
+        while let Some(ix) = next {
+            next = heaviest(graph.vertices[&ix].get_references());
+            chain.push(ix);
+        }
+        chain
+    }
+}
+
+impl<T, Ix> OrderingRule<T, Ix> for HeaviestSubtree
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    fn order(&self, graph: &BullDag<T, Ix>) -> Vec<Ix> {
+        let mut ordered: HashSet<Ix> = HashSet::with_capacity(graph.len());
+        let mut order = Vec::with_capacity(graph.len());
+        for ix in HeaviestSubtree::chain(graph) {
+            let past: HashSet<Ix> =
+                graph.reachable(std::slice::from_ref(&ix), Direction::Source, |s| {
+                    ordered.contains(s)
+                });
+            for ix in HashTiebreak::order_of(graph, &past) {
+                ordered.insert(ix.clone());
+                order.push(ix);
+            }
+        }
+        let rest: HashSet<Ix> = graph
+            .vertices
+            .keys()
+            .filter(|ix| !ordered.contains(*ix))
+            .cloned()
+            .collect();
+        order.extend(HashTiebreak::order_of(graph, &rest));
+        order
+    }
+
+    fn selected_tip(&self, graph: &BullDag<T, Ix>) -> Option<Ix> {
+        HeaviestSubtree::chain(graph).pop()
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns every vertex, each after all of its sources, taking the
+    /// smallest vertex by `cmp` whenever more than one is ready. Vertices
+    /// `cmp` finds equal come in no particular order, so for an order that
+    /// is the same on every run `cmp` should only find a vertex equal to
+    /// itself.
+    ///
+    /// Example
+    ///
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4)]);
+    ///
+    /// let order = graph.topological_sort_by(|a, b| b.cmp(a));
+    /// assert!(order == vec!["source", "reference_2", "reference_1", "ref_reference"]);
+    ///
+    /// // By data, e.g. a timestamp, rather than by index
+    /// let order = graph.topological_sort_by(|a, b| {
+    ///     let (a, b) = (graph.get_vertex(*a).unwrap(), graph.get_vertex(*b).unwrap());
+    ///     a.get_data().cmp(&b.get_data())
+    /// });
+    /// assert!(order == vec!["source", "reference_2", "reference_1", "ref_reference"]);
+    /// ```
+    pub fn topological_sort_by(&self, cmp: impl Fn(&Ix, &Ix) -> Ordering) -> Vec<Ix> {
+        let mut in_degree: HashMap<&Ix, usize> = self
+            .vertices
+            .iter()
+            .map(|(ix, vtx)| (ix, vtx.n_sources()))
+            .collect();
+        let mut ready: BinaryHeap<ByComparator<Ix, _>> = in_degree
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(ix, _)| ByComparator { ix: *ix, cmp: &cmp })
+            .collect();
+        let mut order = Vec::with_capacity(self.len());
+
+        while let Some(ByComparator { ix, .. }) = ready.pop() {
+            order.push(ix.clone());
+            for r in self.vertices[ix].get_references() {
+                if let Some(d) = in_degree.get_mut(r) {
+                    *d -= 1;
+                    if *d == 0 {
+                        ready.push(ByComparator { ix: r, cmp: &cmp });
+                    }
+                }
+            }
+        }
+
+        order
+    }
+
+    /// Returns the number of vertices added to the graph before the vertex
+    /// at `ix`, counting removed vertices too, or `None` if there is no
+    /// such vertex. A vertex keeps its number when reindexed.
+    pub fn insertion_seq(&self, ix: &Ix) -> Option<u64> {
+        self.sequence.get(ix).copied()
+    }
+
+    /// Returns every vertex, each after all of its sources, taking the
+    /// vertex added earliest whenever more than one is ready. The order
+    /// only depends on the edges and the order vertices were added in, so
+    /// graphs built the same way give the same order. Vertices without an
+    /// [`BullDag::insertion_seq`] come after the ones with one that are
+    /// ready at the same time, in no particular order.
+    ///
+    /// Example
+    ///
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// graph.add_vertex(&v3);
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4)]);
+    ///
+    /// let order = graph.topological_sort_stable();
+    /// assert!(order == vec!["source", "reference_2", "reference_1", "ref_reference"]);
+    /// ```
+    pub fn topological_sort_stable(&self) -> Vec<Ix> {
+        self.topological_sort_by(|a, b| {
+            let seq = |ix| self.insertion_seq(ix).unwrap_or(u64::MAX);
+            seq(a).cmp(&seq(b))
+        })
+    }
+
+    /// Returns every vertex in the total order the rule gives
+    pub fn canonical_order(&self, rule: &impl OrderingRule<T, Ix>) -> Vec<Ix> {
+        rule.order(self)
+    }
+
+    /// Returns the tip the rule selects
+    pub fn selected_tip(&self, rule: &impl OrderingRule<T, Ix>) -> Option<Ix> {
+        rule.selected_tip(self)
+    }
+}
@@ -0,0 +1,90 @@
+use crate::edge::Edge;
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::reachability::group_by_source;
+use crate::vertex::{Direction, Vertex};
+use rayon::prelude::*;
+use std::fmt::Debug;
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Sync,
+    Ix: Index + Debug + Sync,
+{
+    /// A parallel iterator over the vertices in the graph, in no particular
+    /// order, run on rayon's global thread pool
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    /// use rayon::prelude::*;
+    ///
+    /// let mut graph: BullDag<usize, usize> = BullDag::new();
+    /// let vertices: Vec<Vertex<usize, usize>> = (0..100).map(|i| Vertex::new(i, i)).collect();
+    /// vertices.windows(2).for_each(|w| graph.add_edge((&w[0], &w[1])));
+    ///
+    /// let total: usize = graph.par_vertices().map(|vtx| vtx.get_data()).sum();
+    /// assert!(total == 4950);
+    /// assert!(graph.par_edges().filter(|e| e.get_source() % 2 == 0).count() == 50);
+    /// ```
+    pub fn par_vertices(&self) -> impl ParallelIterator<Item = &Vertex<T, Ix>> + '_ {
+        self.vertices.par_iter().map(|(_, vtx)| vtx)
+    }
+
+    /// A parallel iterator over the edges in the graph, in no particular
+    /// order
+    pub fn par_edges(&self) -> impl ParallelIterator<Item = &Edge<Ix>> + '_ {
+        self.edges.par_iter()
+    }
+
+    /// A parallel version of [`BullDag::find`]
+    pub fn par_find(&self, pred: impl Fn(&T) -> bool + Sync + Send) -> Vec<&Vertex<T, Ix>> {
+        self.par_vertices().filter(|vtx| pred(vtx.data())).collect()
+    }
+
+    /// A parallel version of [`BullDag::map_data`]
+    pub fn par_map_data<U>(&self, f: impl Fn(&T) -> U + Sync + Send) -> BullDag<U, Ix>
+    where
+        U: Clone + Debug + Send,
+        Ix: Send,
+    {
+        self.with_vertices(
+            self.vertices
+                .par_iter()
+                .map(|(ix, vtx)| (ix.clone(), vtx.with_data(f(vtx.data()))))
+                .collect(),
+        )
+    }
+
+    /// A parallel version of [`BullDag::are_reachable`], which spreads the
+    /// lookups or, without the index, the traversals over rayon's global
+    /// thread pool
+    pub fn par_are_reachable(&self, pairs: &[(Ix, Ix)]) -> Vec<bool>
+    where
+        Ix: Send,
+    {
+        if let Some(index) = self.reachability_index() {
+            return pairs
+                .par_iter()
+                .map(|(from, to)| index.is_reachable(from, to))
+                .collect();
+        }
+
+        let mut answers = vec![false; pairs.len()];
+        let found: Vec<(usize, bool)> = group_by_source(pairs)
+            .into_par_iter()
+            .flat_map_iter(|(from, positions)| {
+                let future =
+                    self.reachable(std::slice::from_ref(from), Direction::Reference, |_| false);
+                positions
+                    .into_iter()
+                    .map(move |i| (i, future.contains(&pairs[i].1)))
+            })
+            .collect();
+        for (i, answer) in found {
+            answers[i] = answer;
+        }
+        answers
+    }
+}
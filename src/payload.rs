@@ -0,0 +1,169 @@
+use crate::graph::GraphError;
+use crate::index::Index;
+use crate::store::{GraphStore, MemoryStore};
+use crate::vertex::Vertex;
+use std::fmt::{self, Debug};
+use std::sync::{Arc, OnceLock};
+
+/// Fetches vertex payloads that are not kept in memory, e.g. from disk or
+/// over the network, by a key of the loader's choosing
+pub trait PayloadLoader<T>: Send + Sync {
+    type Key;
+
+    fn load(&self, key: &Self::Key) -> Result<T, GraphError>;
+}
+
+/// A store can load the payloads it holds, keyed by vertex index
+impl<T, Ix> PayloadLoader<T> for MemoryStore<T, Ix>
+where
+    T: Clone + Send + Sync,
+    Ix: Index + Send + Sync,
+{
+    type Key = Ix;
+
+    fn load(&self, key: &Ix) -> Result<T, GraphError> {
+        self.get_vertex(key)?.ok_or(GraphError::NonExistentVertex)
+    }
+}
+
+#[cfg(feature = "sled")]
+impl<T, Ix> PayloadLoader<T> for crate::store::SledStore<T, Ix>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+    Ix: Index + serde::Serialize + serde::de::DeserializeOwned,
+{
+    type Key = Ix;
+
+    fn load(&self, key: &Ix) -> Result<T, GraphError> {
+        self.get_vertex(key)?.ok_or(GraphError::NonExistentVertex)
+    }
+}
+
+type Load<T> = Arc<dyn Fn() -> Result<T, GraphError> + Send + Sync>;
+
+#[derive(Clone)]
+enum Repr<T> {
+    Inline(T),
+    Lazy {
+        load: Load<T>,
+        loaded: Arc<OnceLock<T>>,
+    },
+}
+
+/// Vertex data that is either held inline or fetched through a
+/// [`PayloadLoader`] the first time it is asked for. Use it as the data of
+/// a graph, e.g. `BullDag<Payload<Block>, Ix>`, to keep only the topology
+/// and the payload handles in memory.
+///
+/// A lazy payload is loaded at most once: the loaded value is shared by
+/// every clone of the handle, including the clones `Vertex::get_data`
+/// returns. A failed load is not cached, so it is retried on the next call.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::{BullDag, GraphError};
+/// use bulldag::payload::{Payload, PayloadLoader};
+/// use bulldag::vertex::Vertex;
+/// use std::sync::Arc;
+///
+/// struct Disk;
+///
+/// impl PayloadLoader<String> for Disk {
+///     type Key = u64;
+///
+///     fn load(&self, key: &u64) -> Result<String, GraphError> {
+///         Ok(format!("block {key}"))
+///     }
+/// }
+///
+/// let disk = Arc::new(Disk);
+/// let mut graph: BullDag<Payload<String>, u64> = BullDag::new();
+/// let genesis = Vertex::new(Payload::inline("genesis".to_string()), 0);
+/// let block = Vertex::new(Payload::lazy(disk.clone(), 1), 1);
+/// graph.add_edge(&(&genesis, &block));
+///
+/// let vertex = graph.get_vertex(1).unwrap();
+/// assert!(!vertex.get_data().is_loaded());
+/// assert!(vertex.load_data().unwrap() == "block 1");
+/// assert!(vertex.get_data().is_loaded());
+/// ```
+#[derive(Clone)]
+pub struct Payload<T> {
+    repr: Repr<T>,
+}
+
+impl<T: Clone> Payload<T> {
+    pub fn inline(data: T) -> Payload<T> {
+        Payload {
+            repr: Repr::Inline(data),
+        }
+    }
+
+    /// A payload that is fetched from `loader` under `key` when first
+    /// asked for
+    pub fn lazy<L>(loader: Arc<L>, key: L::Key) -> Payload<T>
+    where
+        L: PayloadLoader<T> + ?Sized + 'static,
+        L::Key: Send + Sync + 'static,
+    {
+        Payload {
+            repr: Repr::Lazy {
+                load: Arc::new(move || loader.load(&key)),
+                loaded: Arc::new(OnceLock::new()),
+            },
+        }
+    }
+
+    /// Returns the data, loading it first if it hasn't been yet
+    pub fn get(&self) -> Result<T, GraphError> {
+        match &self.repr {
+            Repr::Inline(data) => Ok(data.clone()),
+            Repr::Lazy { load, loaded } => {
+                if let Some(data) = loaded.get() {
+                    return Ok(data.clone());
+                }
+                let data = load()?;
+                Ok(loaded.get_or_init(|| data).clone())
+            }
+        }
+    }
+
+    /// Whether the data is in memory, i.e. it is inline or has been loaded
+    pub fn is_loaded(&self) -> bool {
+        match &self.repr {
+            Repr::Inline(_) => true,
+            Repr::Lazy { loaded, .. } => loaded.get().is_some(),
+        }
+    }
+}
+
+impl<T: Debug> Debug for Payload<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            Repr::Inline(data) => f.debug_tuple("Inline").field(data).finish(),
+            Repr::Lazy { loaded, .. } => match loaded.get() {
+                Some(data) => f.debug_tuple("Loaded").field(data).finish(),
+                None => f.write_str("Unloaded"),
+            },
+        }
+    }
+}
+
+impl<T: Clone> From<T> for Payload<T> {
+    fn from(data: T) -> Self {
+        Payload::inline(data)
+    }
+}
+
+impl<T, Ix> Vertex<Payload<T>, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns the vertex's data, loading it first if it hasn't been yet,
+    /// see [`Payload::get`]
+    pub fn load_data(&self) -> Result<T, GraphError> {
+        self.get_data().get()
+    }
+}
@@ -0,0 +1,263 @@
+use crate::edge::Edge;
+use crate::graph::{BullDag, GraphError};
+use crate::index::Index;
+use crate::vertex::{Direction, Vertex};
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+/// An immutable graph where every change returns a new graph and leaves
+/// the old one as it was. The vertices, edges, roots and leaves are kept
+/// in hash array mapped tries from the `im` crate, so the new graph shares
+/// everything but the few paths it changed with the old one: cloning is
+/// O(1) and a change copies O(log n) of the tries, plus the vertices it
+/// touches. Keeping every version of a graph, e.g. to simulate competing
+/// forks from a common point, then costs little more than the graph.
+///
+/// Example
+///
+/// ```
+/// use bulldag::persistent::ImmutableBullDag;
+/// use bulldag::vertex::Vertex;
+///
+/// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+/// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+/// let v3: Vertex<usize, &str> = Vertex::new(3, "fork");
+///
+/// let base = ImmutableBullDag::new().add_edge(&(&v1, &v2)).unwrap();
+/// let fork = base.add_edge(&(&v1, &v3)).unwrap();
+///
+/// assert!(base.len() == 2 && fork.len() == 3);
+/// assert!(!base.contains_vertex(&"fork"));
+/// assert!(fork.add_edge(&(&v3, &v1)).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ImmutableBullDag<T: Clone + Debug, Ix: Index + Debug> {
+    roots: im::HashSet<Ix>,
+    leaves: im::HashSet<Ix>,
+    vertices: im::HashMap<Ix, Vertex<T, Ix>>,
+    edges: im::HashSet<Edge<Ix>>,
+    /// The insertion order, see [`BullDag::insertion_seq`]
+    sequence: im::HashMap<Ix, u64>,
+    next_sequence: u64,
+}
+
+impl<T, Ix> Default for ImmutableBullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, Ix> ImmutableBullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    pub fn new() -> ImmutableBullDag<T, Ix> {
+        ImmutableBullDag {
+            roots: im::HashSet::new(),
+            leaves: im::HashSet::new(),
+            vertices: im::HashMap::new(),
+            edges: im::HashSet::new(),
+            sequence: im::HashMap::new(),
+            next_sequence: 0,
+        }
+    }
+
+    /// Returns a graph with the vertex added. If a vertex with the same
+    /// index is already in the graph only its data is replaced, its edges
+    /// are kept. Any sources or references the vertex has are ignored.
+    pub fn add_vertex(&self, vertex: &Vertex<T, Ix>) -> ImmutableBullDag<T, Ix> {
+        let mut graph = self.clone();
+        let ix = vertex.get_index();
+        match graph.vertices.get_mut(&ix) {
+            Some(vtx) => vtx.set_data(vertex.get_data()),
+            None => {
+                graph
+                    .vertices
+                    .insert(ix.clone(), Vertex::new(vertex.get_data(), ix.clone()));
+                graph.roots.insert(ix.clone());
+                graph.leaves.insert(ix.clone());
+                graph.sequence.insert(ix, graph.next_sequence);
+                graph.next_sequence += 1;
+            }
+        }
+        graph
+    }
+
+    /// Returns a graph with the edge added, along with either of its
+    /// vertices that isn't in the graph yet, or `GraphError::WouldCycle`
+    /// if the edge would create a cycle
+    pub fn add_edge(
+        &self,
+        edge: &(&Vertex<T, Ix>, &Vertex<T, Ix>),
+    ) -> Result<ImmutableBullDag<T, Ix>, GraphError> {
+        let (source, reference) = (edge.0.get_index(), edge.1.get_index());
+        if source == reference || self.is_reachable(&reference, &source) {
+            return Err(GraphError::WouldCycle);
+        }
+
+        let mut graph = self.clone();
+        for vtx in [edge.0, edge.1] {
+            if !graph.contains_vertex(&vtx.get_index()) {
+                graph = graph.add_vertex(vtx);
+            }
+        }
+        let e: Edge<Ix> = edge.into();
+        if graph.edges.insert(e.clone()).is_some() {
+            return Ok(graph);
+        }
+        if let Some(vtx) = graph.vertices.get_mut(&source) {
+            vtx.add_edge(&e);
+        }
+        if let Some(vtx) = graph.vertices.get_mut(&reference) {
+            vtx.add_edge(&e);
+        }
+        graph.leaves.remove(&source);
+        graph.roots.remove(&reference);
+        Ok(graph)
+    }
+
+    /// Returns a graph without the edge. A source left without references
+    /// becomes a leaf, and a reference left without sources becomes a
+    /// root.
+    pub fn remove_edge(&self, edge: &Edge<Ix>) -> ImmutableBullDag<T, Ix> {
+        let mut graph = self.clone();
+        if graph.edges.remove(edge).is_none() {
+            return graph;
+        }
+        let (source, reference) = (edge.get_source(), edge.get_reference());
+        if let Some(vtx) = graph.vertices.get_mut(&source) {
+            vtx.remove_reference(&reference);
+            if vtx.n_references() == 0 {
+                graph.leaves.insert(source);
+            }
+        }
+        if let Some(vtx) = graph.vertices.get_mut(&reference) {
+            vtx.remove_source(&edge.get_source());
+            if vtx.n_sources() == 0 {
+                graph.roots.insert(reference);
+            }
+        }
+        graph
+    }
+
+    /// Returns a graph without the vertex and every edge it is part of
+    pub fn remove_vertex(&self, target: &Ix) -> ImmutableBullDag<T, Ix> {
+        let Some(vtx) = self.vertices.get(target) else {
+            return self.clone();
+        };
+        let mut graph = self.clone();
+        for s in vtx.get_sources() {
+            graph = graph.remove_edge(&Edge::new(s.clone(), target.clone()));
+        }
+        for r in vtx.get_references() {
+            graph = graph.remove_edge(&Edge::new(target.clone(), r.clone()));
+        }
+        graph.vertices.remove(target);
+        graph.roots.remove(target);
+        graph.leaves.remove(target);
+        graph.sequence.remove(target);
+        graph
+    }
+
+    pub fn get_vertex(&self, target: &Ix) -> Option<&Vertex<T, Ix>> {
+        self.vertices.get(target)
+    }
+
+    pub fn contains_vertex(&self, target: &Ix) -> bool {
+        self.vertices.contains_key(target)
+    }
+
+    pub fn has_edge(&self, source: &Ix, reference: &Ix) -> bool {
+        self.edges
+            .contains(&Edge::new(source.clone(), reference.clone()))
+    }
+
+    /// Whether `to` can be reached from `from` by following references. A
+    /// vertex is reachable from itself.
+    pub fn is_reachable(&self, from: &Ix, to: &Ix) -> bool {
+        let mut visited: HashSet<&Ix> = HashSet::new();
+        let mut stack: Vec<&Ix> = vec![from];
+        while let Some(ix) = stack.pop() {
+            if ix == to {
+                return self.contains_vertex(to);
+            }
+            if !visited.insert(ix) {
+                continue;
+            }
+            if let Some(vtx) = self.vertices.get(ix) {
+                stack.extend(vtx.neighbors(Direction::Reference));
+            }
+        }
+        false
+    }
+
+    pub fn get_roots(&self) -> HashSet<Ix> {
+        self.roots.iter().cloned().collect()
+    }
+
+    pub fn get_leaves(&self) -> HashSet<Ix> {
+        self.leaves.iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn n_edges(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Copies the graph into a mutable [`BullDag`]
+    pub fn to_dag(&self) -> BullDag<T, Ix> {
+        let mut graph = BullDag::new();
+        graph.roots = self.get_roots();
+        graph.leaves = self.get_leaves();
+        graph.vertices = self
+            .vertices
+            .iter()
+            .map(|(ix, vtx)| (ix.clone(), vtx.clone()))
+            .collect();
+        graph.edges = self.edges.iter().cloned().collect();
+        graph.sequence = self
+            .sequence
+            .iter()
+            .map(|(ix, seq)| (ix.clone(), *seq))
+            .collect();
+        graph.next_sequence = self.next_sequence;
+        graph
+    }
+}
+
+impl<T, Ix> From<&BullDag<T, Ix>> for ImmutableBullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn from(graph: &BullDag<T, Ix>) -> Self {
+        ImmutableBullDag {
+            roots: graph.roots.iter().cloned().collect(),
+            leaves: graph.leaves.iter().cloned().collect(),
+            vertices: graph
+                .vertices
+                .iter()
+                .map(|(ix, vtx)| (ix.clone(), vtx.clone()))
+                .collect(),
+            edges: graph.edges.iter().cloned().collect(),
+            sequence: graph
+                .sequence
+                .iter()
+                .map(|(ix, seq)| (ix.clone(), *seq))
+                .collect(),
+            next_sequence: graph.next_sequence,
+        }
+    }
+}
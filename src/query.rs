@@ -0,0 +1,156 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::{Direction, Vertex};
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Debug;
+
+type Filter<'a, T, Ix> = Box<dyn Fn(&Vertex<T, Ix>) -> bool + 'a>;
+
+/// A breadth first search described step by step, started with
+/// [`BullDag::query`]. By default it follows references, has no depth
+/// limit and returns every vertex reached other than the ones it started
+/// from. The depth of a vertex is the number of edges on the shortest path
+/// to it from any of the starting vertices, which are at depth 0.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::vertex::Vertex;
+///
+/// let mut graph: BullDag<usize, &str> = BullDag::new();
+/// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+/// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+/// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+/// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+/// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4)]);
+///
+/// let found = graph
+///     .query()
+///     .from("source")
+///     .via_references()
+///     .max_depth(1)
+///     .filter(|vtx| vtx.get_data() > 3)
+///     .indices();
+/// assert!(found == vec!["reference_1"]);
+///
+/// let neighbors_of_neighbors = graph.query().from("source").min_depth(2).indices();
+/// assert!(neighbors_of_neighbors == vec!["ref_reference"]);
+/// ```
+pub struct Query<'a, T: Clone + Debug, Ix: Index + Debug> {
+    graph: &'a BullDag<T, Ix>,
+    start: Vec<Ix>,
+    direction: Direction,
+    min_depth: usize,
+    max_depth: Option<usize>,
+    filters: Vec<Filter<'a, T, Ix>>,
+}
+
+impl<'a, T, Ix> Query<'a, T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Adds a vertex to start from, starting vertices that aren't in the
+    /// graph are ignored
+    pub fn from(mut self, ix: Ix) -> Self {
+        self.start.push(ix);
+        self
+    }
+
+    pub fn via_references(mut self) -> Self {
+        self.direction = Direction::Reference;
+        self
+    }
+
+    pub fn via_sources(mut self) -> Self {
+        self.direction = Direction::Source;
+        self
+    }
+
+    /// Returns only vertices at least `depth` edges away, 0 includes the
+    /// starting vertices
+    pub fn min_depth(mut self, depth: usize) -> Self {
+        self.min_depth = depth;
+        self
+    }
+
+    /// Stops the search `depth` edges away from the starting vertices
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Returns only vertices that match `pred`. The search still goes on
+    /// through vertices that don't. Each call adds a filter that must
+    /// match as well as the previous ones.
+    pub fn filter(mut self, pred: impl Fn(&Vertex<T, Ix>) -> bool + 'a) -> Self {
+        self.filters.push(Box::new(pred));
+        self
+    }
+
+    /// Runs the query, returning the matching vertices in the order they
+    /// were reached
+    pub fn collect(self) -> Vec<&'a Vertex<T, Ix>> {
+        let graph = self.graph;
+        let mut discovered: HashSet<&Ix> = HashSet::new();
+        let mut queue: VecDeque<(&Vertex<T, Ix>, usize)> = VecDeque::new();
+        for ix in &self.start {
+            if let Some((ix, vtx)) = graph.vertices.get_key_value(ix) {
+                if discovered.insert(ix) {
+                    queue.push_back((vtx, 0));
+                }
+            }
+        }
+
+        let mut found = vec![];
+        while let Some((vtx, depth)) = queue.pop_front() {
+            if depth >= self.min_depth && self.filters.iter().all(|pred| pred(vtx)) {
+                found.push(vtx);
+            }
+            if self.max_depth.is_some_and(|max| depth >= max) {
+                continue;
+            }
+            for next in vtx.neighbors(self.direction) {
+                if let Some((ix, next)) = graph.vertices.get_key_value(next) {
+                    if discovered.insert(ix) {
+                        queue.push_back((next, depth + 1));
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Runs the query, returning the indices of the matching vertices in
+    /// the order they were reached
+    pub fn indices(self) -> Vec<Ix> {
+        self.collect()
+            .into_iter()
+            .map(|vtx| vtx.get_index())
+            .collect()
+    }
+
+    /// Runs the query, returning the number of matching vertices
+    pub fn count(self) -> usize {
+        self.collect().len()
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Starts a [`Query`] of the graph
+    pub fn query(&self) -> Query<'_, T, Ix> {
+        Query {
+            graph: self,
+            start: vec![],
+            direction: Direction::Reference,
+            min_depth: 1,
+            max_depth: None,
+            filters: vec![],
+        }
+    }
+}
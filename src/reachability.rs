@@ -0,0 +1,276 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::memory::table_bytes;
+use crate::observe::Event;
+use crate::vertex::{Direction, Vertex};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::OnceLock;
+
+/// For every vertex, the set of vertices it can be reached from, stored as
+/// a bitset over a dense numbering of the vertices. Answering whether one
+/// vertex is reachable from another is then a single bit lookup, at the
+/// cost of a bit per pair of vertices.
+///
+/// A graph keeps its index up to date once it is enabled with
+/// [`BullDag::enable_reachability_index`]. New vertices and edges are
+/// folded into the index as they are added, while removing anything drops
+/// the index to be rebuilt on the next query.
+#[derive(Debug, Clone)]
+pub struct ReachabilityIndex<Ix: Index> {
+    ids: HashMap<Ix, usize>,
+    ancestors: Vec<Vec<u64>>,
+}
+
+fn has_bit(bits: &[u64], id: usize) -> bool {
+    bits.get(id / 64)
+        .is_some_and(|word| word & (1 << (id % 64)) != 0)
+}
+
+fn set_bit(bits: &mut Vec<u64>, id: usize) {
+    if bits.len() <= id / 64 {
+        bits.resize(id / 64 + 1, 0);
+    }
+    bits[id / 64] |= 1 << (id % 64);
+}
+
+fn union(bits: &mut Vec<u64>, other: &[u64]) {
+    if bits.len() < other.len() {
+        bits.resize(other.len(), 0);
+    }
+    bits.iter_mut().zip(other).for_each(|(a, b)| *a |= b);
+}
+
+impl<Ix: Index + Debug> ReachabilityIndex<Ix> {
+    /// Builds the index for every vertex of `graph`, visiting the vertices
+    /// in topological order so each one's set is the union of its sources'
+    pub fn new<T: Clone + Debug>(graph: &BullDag<T, Ix>) -> ReachabilityIndex<Ix> {
+        let order = graph.topological_order();
+        let mut index = ReachabilityIndex {
+            ids: HashMap::with_capacity(order.len()),
+            ancestors: Vec::with_capacity(order.len()),
+        };
+        for ix in order {
+            let id = index.insert(&ix);
+            for source in graph.vertices[&ix].get_sources() {
+                index.inherit(id, index.ids[source]);
+            }
+        }
+        index
+    }
+
+    /// The bytes allocated for the numbering and the bitsets
+    pub(crate) fn heap_bytes(&self) -> usize {
+        table_bytes::<(Ix, usize)>(self.ids.capacity())
+            + self.ancestors.capacity() * std::mem::size_of::<Vec<u64>>()
+            + self
+                .ancestors
+                .iter()
+                .map(|bits| bits.capacity() * std::mem::size_of::<u64>())
+                .sum::<usize>()
+    }
+
+    fn insert(&mut self, ix: &Ix) -> usize {
+        let id = self.ancestors.len();
+        self.ids.insert(ix.clone(), id);
+        self.ancestors.push(vec![]);
+        id
+    }
+
+    /// Adds `source` and everything it is reachable from to `id`'s set
+    fn inherit(&mut self, id: usize, source: usize) {
+        let inherited = std::mem::take(&mut self.ancestors[source]);
+        union(&mut self.ancestors[id], &inherited);
+        set_bit(&mut self.ancestors[id], source);
+        self.ancestors[source] = inherited;
+    }
+
+    /// Whether `to` can be reached from `from` by following references. A
+    /// vertex is reachable from itself. False if either is not indexed.
+    pub fn is_reachable(&self, from: &Ix, to: &Ix) -> bool {
+        match (self.ids.get(from), self.ids.get(to)) {
+            (Some(from), Some(to)) => from == to || has_bit(&self.ancestors[*to], *from),
+            _ => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// The dense id of every indexed vertex
+    pub(crate) fn ids(&self) -> &HashMap<Ix, usize> {
+        &self.ids
+    }
+
+    /// The ids of every vertex that `id` can be reached from, not
+    /// including `id` itself. Ids run from 0 up to the number of vertices.
+    pub(crate) fn ancestor_ids(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        self.ancestors[id]
+            .iter()
+            .enumerate()
+            .flat_map(|(word, bits)| {
+                (0..64)
+                    .filter(move |bit| bits & (1 << bit) != 0)
+                    .map(move |bit| word * 64 + bit)
+            })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Applies a change to the graph, returning false if the index can no
+    /// longer be kept up to date and has to be rebuilt. `vertices` are the
+    /// graph's vertices after the change.
+    pub(crate) fn update<T: Clone + Debug>(
+        &mut self,
+        event: &Event<'_, T, Ix>,
+        vertices: &HashMap<Ix, Vertex<T, Ix>>,
+    ) -> bool {
+        match event {
+            Event::VertexAdded(vertex) => {
+                self.insert(&vertex.get_index());
+                true
+            }
+            Event::EdgeAdded(edge) => {
+                let (Some(&source), Some(_)) = (
+                    self.ids.get(&edge.get_source()),
+                    self.ids.get(&edge.get_reference()),
+                ) else {
+                    return false;
+                };
+                // Everything at or below the reference that couldn't
+                // already be reached from the source now can be. Below a
+                // vertex that already could, so could everything else.
+                let mut stack = vec![edge.get_reference()];
+                while let Some(ix) = stack.pop() {
+                    let id = self.ids[&ix];
+                    if has_bit(&self.ancestors[id], source) {
+                        continue;
+                    }
+                    self.inherit(id, source);
+                    if let Some(vtx) = vertices.get(&ix) {
+                        stack.extend(vtx.neighbors(Direction::Reference).into_iter().cloned());
+                    }
+                }
+                true
+            }
+            Event::EdgeRemoved(_) | Event::VertexRemoved(_) => false,
+            // Neither data nor tags change what reaches what
+            _ => true,
+        }
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Starts keeping a [`ReachabilityIndex`] of the graph, which
+    /// [`BullDag::is_reachable`] and the cycle check on every new edge use
+    /// instead of traversing the graph. The index is built on the first
+    /// query rather than here. Does nothing if it is already enabled.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// graph.enable_reachability_index();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v2, &v3)]);
+    ///
+    /// assert!(graph.is_reachable(&"source", &"ref_reference"));
+    /// assert!(!graph.is_reachable(&"ref_reference", &"source"));
+    /// ```
+    pub fn enable_reachability_index(&mut self) {
+        if self.reachability.is_none() {
+            self.reachability = Some(OnceLock::new());
+        }
+    }
+
+    /// Stops keeping the index and drops it
+    pub fn disable_reachability_index(&mut self) {
+        self.reachability = None;
+    }
+
+    /// Returns the index, building it first if it has been dropped, or
+    /// `None` if it isn't enabled
+    pub fn reachability_index(&self) -> Option<&ReachabilityIndex<Ix>> {
+        self.reachability
+            .as_ref()
+            .map(|index| index.get_or_init(|| ReachabilityIndex::new(self)))
+    }
+
+    /// Whether `to` can be reached from `from` by following references,
+    /// i.e. whether `from` is a direct or indirect source of `to`. A vertex
+    /// is reachable from itself. Looks the answer up in the
+    /// [`ReachabilityIndex`] if it is enabled and traverses the graph from
+    /// `from` otherwise.
+    pub fn is_reachable(&self, from: &Ix, to: &Ix) -> bool {
+        match self.reachability_index() {
+            Some(index) => index.is_reachable(from, to),
+            None => self
+                .reachable(std::slice::from_ref(from), Direction::Reference, |_| false)
+                .contains(to),
+        }
+    }
+
+    /// Answers [`BullDag::is_reachable`] for every pair of `(from, to)`, in
+    /// the order given. Looks each pair up in the [`ReachabilityIndex`] if
+    /// it is enabled, and otherwise traverses the graph once for each
+    /// distinct `from` and answers every pair starting there from that one
+    /// traversal. For many queries from many different vertices enabling
+    /// the index is faster.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// graph.extend_from_edges([(&v1, &v2), (&v1, &v3)]);
+    ///
+    /// let answers = graph.are_reachable(&[
+    ///     ("source", "reference_1"),
+    ///     ("reference_1", "reference_2"),
+    ///     ("source", "reference_2"),
+    /// ]);
+    /// assert!(answers == vec![true, false, true]);
+    /// ```
+    pub fn are_reachable(&self, pairs: &[(Ix, Ix)]) -> Vec<bool> {
+        if let Some(index) = self.reachability_index() {
+            return pairs
+                .iter()
+                .map(|(from, to)| index.is_reachable(from, to))
+                .collect();
+        }
+
+        let mut answers = vec![false; pairs.len()];
+        for (from, positions) in group_by_source(pairs) {
+            let future =
+                self.reachable(std::slice::from_ref(from), Direction::Reference, |_| false);
+            for i in positions {
+                answers[i] = future.contains(&pairs[i].1);
+            }
+        }
+        answers
+    }
+}
+
+/// The positions of the pairs starting from each distinct vertex
+pub(crate) fn group_by_source<Ix: Index>(pairs: &[(Ix, Ix)]) -> Vec<(&Ix, Vec<usize>)> {
+    let mut groups: HashMap<&Ix, Vec<usize>> = HashMap::new();
+    for (i, (from, _)) in pairs.iter().enumerate() {
+        groups.entry(from).or_default().push(i);
+    }
+    groups.into_iter().collect()
+}
@@ -0,0 +1,117 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Display};
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Renders the graph as an indented tree from each root, similar to
+    /// `cargo tree`, using the `Debug` representation of the indices. A
+    /// vertex reachable along more than one path is expanded the first time
+    /// it is printed and marked with `(*)` afterwards. Siblings are sorted
+    /// by their label so the output is stable.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(1, "a");
+    /// let v2: Vertex<usize, &str> = Vertex::new(2, "b");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "c");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v3)]);
+    ///
+    /// let expected = "\
+    /// \"a\"
+    /// ├── \"b\"
+    /// │   └── \"c\"
+    /// └── \"c\" (*)
+    /// ";
+    /// assert_eq!(graph.render_ascii(), expected);
+    /// ```
+    pub fn render_ascii(&self) -> String {
+        self.render_with(|ix| format!("{:?}", ix))
+    }
+
+    fn render_with(&self, label: impl Fn(&Ix) -> String) -> String {
+        let mut out = String::new();
+        let mut expanded: HashSet<&Ix> = HashSet::new();
+
+        let mut roots: Vec<(String, &Ix)> = self.roots.iter().map(|ix| (label(ix), ix)).collect();
+        roots.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (root_label, root) in roots {
+            out.push_str(&root_label);
+            out.push('\n');
+            expanded.insert(root);
+
+            // (vertex, prefix for its children) pairs still to expand, plus
+            // the children of each still waiting to be printed
+            let mut stack: Vec<(String, Vec<(String, &Ix)>)> =
+                vec![(String::new(), self.sorted_references(root, &label))];
+
+            while let Some((prefix, children)) = stack.last_mut() {
+                let (child_label, child) = match children.pop() {
+                    Some(child) => child,
+                    None => {
+                        stack.pop();
+                        continue;
+                    }
+                };
+                let is_last = children.is_empty();
+                let prefix = prefix.clone();
+                let branch = if is_last { "└── " } else { "├── " };
+
+                out.push_str(&prefix);
+                out.push_str(branch);
+                out.push_str(&child_label);
+
+                if !expanded.insert(child) {
+                    out.push_str(" (*)\n");
+                    continue;
+                }
+                out.push('\n');
+
+                let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+                stack.push((child_prefix, self.sorted_references(child, &label)));
+            }
+        }
+
+        out
+    }
+
+    /// References of `ix` with their labels, in reverse label order so that
+    /// popping from the end yields them in order
+    fn sorted_references<'a>(
+        &'a self,
+        ix: &Ix,
+        label: &impl Fn(&Ix) -> String,
+    ) -> Vec<(String, &'a Ix)> {
+        let mut references: Vec<(String, &Ix)> = self
+            .vertices
+            .get(ix)
+            .map(|vtx| vtx.get_references())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| (label(r), r))
+            .collect();
+        references.sort_by(|a, b| b.0.cmp(&a.0));
+        references
+    }
+}
+
+/// Displays the graph as an indented tree using the `Display`
+/// representation of the indices, see [`BullDag::render_ascii`]
+impl<T, Ix> Display for BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render_with(|ix| ix.to_string()))
+    }
+}
@@ -0,0 +1,62 @@
+use crate::edge::{Edge, EdgeMap};
+use crate::graph::BullDag;
+use crate::index::Index;
+use std::fmt::Debug;
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns a copy of the graph with the direction of every edge
+    /// flipped, so its roots are the leaves of this graph and the other way
+    /// around. Epochs, conflicts and the kinds, times, weights and keys of
+    /// edges are copied over, but observers, checkpoints and history are
+    /// not.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3)]);
+    ///
+    /// let reversed = graph.reverse();
+    /// assert!(reversed.has_edge(&"reference_1", &"source"));
+    /// assert!(!reversed.has_edge(&"source", &"reference_1"));
+    /// assert!(reversed.get_roots() == graph.get_leaves());
+    /// assert!(reversed.get_leaves() == graph.get_roots());
+    /// ```
+    pub fn reverse(&self) -> BullDag<T, Ix> {
+        let mut graph = self.copy_structure();
+        graph.reverse_in_place();
+        graph
+    }
+
+    /// Flips the direction of every edge of the graph. Like
+    /// [`BullDag::restore`], the change is made directly, so neither
+    /// observers nor the version history see it, and the undo and redo
+    /// history is cleared.
+    pub fn reverse_in_place(&mut self) {
+        self.vertices.values_mut().for_each(|vtx| vtx.reverse());
+        self.edges = self
+            .edges
+            .iter()
+            .map(|edge| Edge::new(edge.get_reference(), edge.get_source()))
+            .collect();
+        std::mem::swap(&mut self.roots, &mut self.leaves);
+        self.edge_keys = self.edge_keys.take().map(EdgeMap::reversed);
+        self.edge_kinds = std::mem::take(&mut self.edge_kinds).reversed();
+        self.edge_times = std::mem::take(&mut self.edge_times).reversed();
+        self.edge_weights = std::mem::take(&mut self.edge_weights).reversed();
+        self.invalidate_caches();
+        if self.journal.is_some() {
+            self.disable_journal();
+            self.enable_journal();
+        }
+    }
+}
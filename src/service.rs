@@ -0,0 +1,230 @@
+use crate::graph::{BullDag, GraphError, GraphResult};
+use crate::index::Index;
+use crate::vertex::{Direction, Vertex};
+use std::fmt::Debug;
+use tokio::sync::{mpsc, oneshot};
+
+/// A request to a [`GraphService`], each carries the channel its reply is
+/// sent on. Usually sent through a [`GraphHandle`] rather than directly.
+#[derive(Debug)]
+pub enum Command<T: Clone + Debug, Ix: Index + Debug> {
+    AddVertex {
+        vertex: Vertex<T, Ix>,
+        reply: oneshot::Sender<()>,
+    },
+    AddEdge {
+        source: Vertex<T, Ix>,
+        reference: Vertex<T, Ix>,
+        reply: oneshot::Sender<GraphResult>,
+    },
+    GetVertex {
+        index: Ix,
+        reply: oneshot::Sender<Option<Vertex<T, Ix>>>,
+    },
+    TopoSort {
+        reply: oneshot::Sender<Vec<Ix>>,
+    },
+    Trace {
+        index: Ix,
+        direction: Direction,
+        reply: oneshot::Sender<Option<Vec<Ix>>>,
+    },
+}
+
+/// A task that owns a [`BullDag`] and applies [`Command`]s to it one at a
+/// time, so any number of tasks and threads can share the graph through
+/// cloned [`GraphHandle`]s without a lock.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::service::GraphService;
+/// use bulldag::vertex::{Direction, Vertex};
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// runtime.block_on(async {
+///     let (handle, task) = GraphService::spawn(BullDag::<usize, &'static str>::new(), 32);
+///
+///     let v1 = Vertex::new(5, "source");
+///     let v2 = Vertex::new(4, "reference");
+///     handle.add_edge(v1, v2).await.unwrap();
+///
+///     assert!(handle.trace("source", Direction::Reference).await.unwrap().len() == 2);
+///     drop(handle);
+///
+///     let graph = task.await.unwrap();
+///     assert!(graph.n_edges() == 1);
+/// });
+/// ```
+#[derive(Debug)]
+pub struct GraphService<T: Clone + Debug, Ix: Index + Debug> {
+    graph: BullDag<T, Ix>,
+    receiver: mpsc::Receiver<Command<T, Ix>>,
+}
+
+/// A cloneable handle for sending commands to a [`GraphService`]. Every
+/// method fails with `GraphError::Other` once the service has stopped.
+#[derive(Debug)]
+pub struct GraphHandle<T: Clone + Debug, Ix: Index + Debug> {
+    sender: mpsc::Sender<Command<T, Ix>>,
+}
+
+impl<T, Ix> Clone for GraphHandle<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn clone(&self) -> Self {
+        GraphHandle {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T, Ix> GraphService<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Creates a service around `graph`, with room for `buffer` queued
+    /// commands, and a handle to it. The service does nothing until
+    /// [`GraphService::run`] is polled.
+    pub fn new(graph: BullDag<T, Ix>, buffer: usize) -> (GraphService<T, Ix>, GraphHandle<T, Ix>) {
+        let (sender, receiver) = mpsc::channel(buffer);
+        (GraphService { graph, receiver }, GraphHandle { sender })
+    }
+
+    /// Processes commands until every handle has been dropped, then
+    /// returns the graph
+    pub async fn run(mut self) -> BullDag<T, Ix> {
+        while let Some(command) = self.receiver.recv().await {
+            self.apply(command);
+        }
+        self.graph
+    }
+
+    // A failed reply only means the caller stopped waiting for it.
+    fn apply(&mut self, command: Command<T, Ix>) {
+        match command {
+            Command::AddVertex { vertex, reply } => {
+                if !self.graph.contains_vertex(&vertex.get_index()) {
+                    self.graph.add_vertex(&vertex);
+                }
+                let _ = reply.send(());
+            }
+            Command::AddEdge {
+                source,
+                reference,
+                reply,
+            } => {
+                let source = self
+                    .graph
+                    .get_vertex(source.get_index())
+                    .cloned()
+                    .unwrap_or(source);
+                let reference = self
+                    .graph
+                    .get_vertex(reference.get_index())
+                    .cloned()
+                    .unwrap_or(reference);
+                let _ = reply.send(self.graph.try_add_edge((&source, &reference)));
+            }
+            Command::GetVertex { index, reply } => {
+                let _ = reply.send(self.graph.get_vertex(index).cloned());
+            }
+            Command::TopoSort { reply } => {
+                let _ = reply.send(self.graph.topological_order());
+            }
+            Command::Trace {
+                index,
+                direction,
+                reply,
+            } => {
+                let trace = self
+                    .graph
+                    .get_vertex(index)
+                    .map(|vtx| self.graph.trace(vtx, direction));
+                let _ = reply.send(trace);
+            }
+        }
+    }
+}
+
+impl<T, Ix> GraphService<T, Ix>
+where
+    T: Clone + Debug + Send + 'static,
+    Ix: Index + Debug + Send + 'static,
+{
+    /// Spawns a service around `graph` on the current tokio runtime,
+    /// returning a handle to it and the task, which resolves to the graph
+    /// once every handle has been dropped
+    pub fn spawn(
+        graph: BullDag<T, Ix>,
+        buffer: usize,
+    ) -> (GraphHandle<T, Ix>, tokio::task::JoinHandle<BullDag<T, Ix>>) {
+        let (service, handle) = GraphService::new(graph, buffer);
+        (handle, tokio::spawn(service.run()))
+    }
+}
+
+fn stopped() -> GraphError {
+    GraphError::other("the graph service has stopped")
+}
+
+impl<T, Ix> GraphHandle<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    async fn request<R>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<R>) -> Command<T, Ix>,
+    ) -> Result<R, GraphError> {
+        let (reply, response) = oneshot::channel();
+        self.sender
+            .send(command(reply))
+            .await
+            .map_err(|_| stopped())?;
+        response.await.map_err(|_| stopped())
+    }
+
+    /// Adds a vertex if there isn't one with the same index already
+    pub async fn add_vertex(&self, vertex: Vertex<T, Ix>) -> Result<(), GraphError> {
+        self.request(|reply| Command::AddVertex { vertex, reply })
+            .await
+    }
+
+    /// Adds an edge, and its vertices if they are missing, failing with
+    /// `GraphError::WouldCycle` if it would create a cycle
+    pub async fn add_edge(&self, source: Vertex<T, Ix>, reference: Vertex<T, Ix>) -> GraphResult {
+        self.request(|reply| Command::AddEdge {
+            source,
+            reference,
+            reply,
+        })
+        .await?
+    }
+
+    pub async fn get_vertex(&self, index: Ix) -> Result<Option<Vertex<T, Ix>>, GraphError> {
+        self.request(|reply| Command::GetVertex { index, reply })
+            .await
+    }
+
+    /// Returns every vertex index, each after all of its sources
+    pub async fn topological_sort(&self) -> Result<Vec<Ix>, GraphError> {
+        self.request(|reply| Command::TopoSort { reply }).await
+    }
+
+    /// Traces the graph from a vertex, see [`BullDag::trace`]. Fails with
+    /// `GraphError::NonExistentVertex` if the vertex is missing.
+    pub async fn trace(&self, index: Ix, direction: Direction) -> Result<Vec<Ix>, GraphError> {
+        self.request(|reply| Command::Trace {
+            index,
+            direction,
+            reply,
+        })
+        .await?
+        .ok_or(GraphError::NonExistentVertex)
+    }
+}
@@ -0,0 +1,207 @@
+use crate::concurrent::SharedBullDag;
+use crate::graph::BullDag;
+use crate::index::Index;
+use std::fmt::Debug;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// An immutable, shareable view of a graph at a point in time, returned by
+/// [`BullDag::snapshot`]. The vertices and edges are copied once when the
+/// snapshot is taken, leaving out the journal, checkpoints and version
+/// history, and cloning the snapshot after that only bumps a reference count,
+/// so one snapshot can be handed to any number of queries and threads
+/// while the writer keeps mutating the original graph. Every read-only
+/// `BullDag` method is available through `Deref`.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::vertex::Vertex;
+///
+/// let mut graph: BullDag<usize, &str> = BullDag::new();
+/// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+/// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+/// graph.add_edge(&(&v1, &v2));
+///
+/// let snapshot = graph.snapshot();
+/// let reader = snapshot.clone();
+/// graph.add_vertex(&Vertex::new(3, "new"));
+///
+/// assert!(graph.len() == 3);
+/// assert!(reader.len() == 2);
+/// assert!(reader.get_vertex("new").is_none());
+/// ```
+#[derive(Debug)]
+pub struct GraphSnapshot<T: Clone + Debug, Ix: Index + Debug> {
+    graph: Arc<BullDag<T, Ix>>,
+}
+
+impl<T, Ix> Clone for GraphSnapshot<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn clone(&self) -> Self {
+        GraphSnapshot {
+            graph: Arc::clone(&self.graph),
+        }
+    }
+}
+
+impl<T, Ix> Deref for GraphSnapshot<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    type Target = BullDag<T, Ix>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.graph
+    }
+}
+
+impl<T, Ix> From<BullDag<T, Ix>> for GraphSnapshot<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn from(graph: BullDag<T, Ix>) -> Self {
+        GraphSnapshot {
+            graph: Arc::new(graph),
+        }
+    }
+}
+
+impl<T, Ix> GraphSnapshot<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns the number of snapshot handles sharing this graph
+    pub fn n_readers(&self) -> usize {
+        Arc::strong_count(&self.graph)
+    }
+
+    /// Converts the snapshot back into a mutable graph, without copying it
+    /// if this is the last handle
+    pub fn into_dag(self) -> BullDag<T, Ix> {
+        Arc::try_unwrap(self.graph).unwrap_or_else(|graph| (*graph).clone())
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Takes an immutable snapshot of the graph, see [`GraphSnapshot`]
+    pub fn snapshot(&self) -> GraphSnapshot<T, Ix> {
+        self.copy_structure().into()
+    }
+
+    /// Saves the graph's vertices and edges under `label`, replacing any
+    /// earlier checkpoint with the same label, so the graph can later be
+    /// rolled back to this point with [`BullDag::restore`]. The checkpoint
+    /// can be queried in the meantime through [`BullDag::get_checkpoint`].
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    /// graph.checkpoint("epoch-1");
+    ///
+    /// graph.add_edge(&(&v2, &Vertex::new(3, "orphan")));
+    /// assert!(graph.len() == 3);
+    ///
+    /// assert!(graph.restore("epoch-1"));
+    /// assert!(graph.len() == 2);
+    /// assert!(graph.get_leaves().contains("reference"));
+    /// ```
+    pub fn checkpoint(&mut self, label: impl Into<String>) {
+        let graph = self.copy_structure();
+        self.checkpoints.insert(label.into(), graph);
+    }
+
+    /// Returns the graph as it was saved under `label`
+    pub fn get_checkpoint(&self, label: &str) -> Option<&BullDag<T, Ix>> {
+        self.checkpoints.get(label)
+    }
+
+    /// Rolls the vertices and edges back to the checkpoint saved under
+    /// `label`, returning false if there is none. The checkpoint is kept so
+    /// it can be restored again. Neither observers nor the version history
+    /// see the rollback, and the undo and redo history is cleared since it
+    /// no longer applies.
+    pub fn restore(&mut self, label: &str) -> bool {
+        let Some(checkpoint) = self.checkpoints.get(label) else {
+            return false;
+        };
+        let restored = checkpoint.copy_structure();
+
+        // Everything but the structure belongs to this graph and is kept,
+        // destructured in full so that a new field has to be sorted into
+        // one or the other
+        let BullDag {
+            roots: _,
+            leaves: _,
+            vertices: _,
+            edges: _,
+            epochs: _,
+            epoch_checkpoints: _,
+            conflicts: _,
+            sequence: _,
+            next_sequence: _,
+            edge_keys: _,
+            edge_kinds: _,
+            edge_times: _,
+            edge_weights: _,
+            observers,
+            journal,
+            checkpoints,
+            history,
+            topo_order: _,
+            reachability,
+        } = std::mem::replace(self, restored);
+        self.observers = observers;
+        self.checkpoints = checkpoints;
+        self.history = history;
+        self.reachability = reachability;
+        self.invalidate_caches();
+        if journal.is_some() {
+            self.enable_journal();
+        }
+        true
+    }
+
+    /// Drops the checkpoint saved under `label`, returning it if there was
+    /// one
+    pub fn remove_checkpoint(&mut self, label: &str) -> Option<BullDag<T, Ix>> {
+        self.checkpoints.remove(label)
+    }
+
+    /// Returns the labels of every saved checkpoint
+    pub fn checkpoint_labels(&self) -> Vec<&str> {
+        self.checkpoints
+            .keys()
+            .map(|label| label.as_str())
+            .collect()
+    }
+}
+
+impl<T, Ix> SharedBullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Takes a consistent, immutable snapshot of the graph, see
+    /// [`SharedBullDag::to_dag`] and [`GraphSnapshot`]
+    pub fn snapshot(&self) -> GraphSnapshot<T, Ix> {
+        self.to_dag().into()
+    }
+}
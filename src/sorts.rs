@@ -0,0 +1,155 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// Every topological ordering of a graph, one at a time, returned by
+/// [`BullDag::all_topological_sorts`]. The iterator owns a copy of the
+/// graph's structure, so the graph can change while it's in use without
+/// affecting it. A graph with no edges has `n!` orderings, so on anything
+/// but small graphs only take as many as needed.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::vertex::Vertex;
+///
+/// let mut graph: BullDag<usize, &str> = BullDag::new();
+/// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+/// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+/// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+/// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3)]);
+///
+/// let mut sorts: Vec<Vec<&str>> = graph.all_topological_sorts().collect();
+/// sorts.sort();
+/// assert!(sorts == vec![
+///     vec!["source", "reference_1", "reference_2"],
+///     vec!["source", "reference_2", "reference_1"],
+/// ]);
+/// assert!(graph.count_topological_sorts(1) == 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TopologicalSorts<Ix> {
+    /// The vertices in one topological order, referred to by position
+    vertices: Vec<Ix>,
+    references: Vec<Vec<usize>>,
+    /// The number of sources of each vertex not placed yet
+    in_degree: Vec<usize>,
+    placed: Vec<bool>,
+    /// The ordering being built, the last one returned once it's full
+    path: Vec<usize>,
+    started: bool,
+    done: bool,
+}
+
+impl<Ix: Clone> TopologicalSorts<Ix> {
+    /// Returns the first vertex after `after` that isn't placed and has
+    /// all of its sources placed
+    fn next_ready(&self, after: Option<usize>) -> Option<usize> {
+        let start = after.map_or(0, |id| id + 1);
+        (start..self.vertices.len()).find(|&id| !self.placed[id] && self.in_degree[id] == 0)
+    }
+
+    fn place(&mut self, id: usize) {
+        self.placed[id] = true;
+        for &r in &self.references[id] {
+            self.in_degree[r] -= 1;
+        }
+        self.path.push(id);
+    }
+
+    fn unplace(&mut self) -> Option<usize> {
+        let id = self.path.pop()?;
+        self.placed[id] = false;
+        for &r in &self.references[id] {
+            self.in_degree[r] += 1;
+        }
+        Some(id)
+    }
+
+    /// Places the first ready vertex until every vertex is placed, which
+    /// always succeeds as the graph has no cycles
+    fn fill(&mut self) {
+        while let Some(id) = self.next_ready(None) {
+            self.place(id);
+        }
+    }
+}
+
+impl<Ix: Clone> Iterator for TopologicalSorts<Ix> {
+    type Item = Vec<Ix>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.started {
+            // Swap the deepest vertex that can be swapped for the next one
+            // ready in its place, then fill in the rest again
+            loop {
+                let Some(id) = self.unplace() else {
+                    self.done = true;
+                    return None;
+                };
+                if let Some(next) = self.next_ready(Some(id)) {
+                    self.place(next);
+                    break;
+                }
+            }
+        }
+        self.started = true;
+        self.fill();
+        Some(
+            self.path
+                .iter()
+                .map(|&id| self.vertices[id].clone())
+                .collect(),
+        )
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns a [`TopologicalSorts`] iterator over every order the vertices
+    /// can be put in with each vertex after all of its sources. Orderings
+    /// are only built as they're asked for. An empty graph has exactly one,
+    /// with no vertices.
+    pub fn all_topological_sorts(&self) -> TopologicalSorts<Ix> {
+        let vertices = self.topological_order();
+        let ids: HashMap<&Ix, usize> = vertices.iter().enumerate().map(|(i, ix)| (ix, i)).collect();
+        let references = vertices
+            .iter()
+            .map(|ix| {
+                self.vertices[ix]
+                    .get_references()
+                    .into_iter()
+                    .map(|r| ids[r])
+                    .collect()
+            })
+            .collect();
+        let in_degree = vertices
+            .iter()
+            .map(|ix| self.vertices[ix].n_sources())
+            .collect();
+        let n = vertices.len();
+        TopologicalSorts {
+            vertices,
+            references,
+            in_degree,
+            placed: vec![false; n],
+            path: Vec::with_capacity(n),
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Counts the topological orderings of the graph, stopping at `cap` as
+    /// there can be far too many to count them all
+    pub fn count_topological_sorts(&self, cap: usize) -> usize {
+        self.all_topological_sorts().take(cap).count()
+    }
+}
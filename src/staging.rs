@@ -0,0 +1,122 @@
+use crate::edge::Edge;
+use crate::graph::{BullDag, GraphError};
+use crate::index::Index;
+use crate::vertex::Vertex;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// A wrapper around a [`BullDag`] for vertices and edges that arrive out
+/// of order, i.e. an edge that refers to a vertex that has not been added
+/// yet. Such edges are held in an orphan buffer, keyed by the index of the
+/// missing endpoint, and are added to the graph as soon as both of their
+/// vertices are present.
+///
+/// Example
+///
+/// ```
+/// use bulldag::staging::StagingDag;
+/// use bulldag::vertex::Vertex;
+/// use bulldag::edge::Edge;
+///
+/// let mut staging: StagingDag<usize, &str> = StagingDag::new();
+/// staging.add_edge(Edge::new("source", "reference"));
+/// assert!(staging.n_pending() == 1);
+///
+/// staging.add_vertex(Vertex::new(5, "source"));
+/// staging.add_vertex(Vertex::new(4, "reference"));
+/// assert!(staging.n_pending() == 0);
+/// assert!(staging.graph().n_edges() == 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StagingDag<T: Clone + Debug, Ix: Index + Debug> {
+    graph: BullDag<T, Ix>,
+    pending: HashMap<Ix, Vec<Edge<Ix>>>,
+}
+
+impl<T, Ix> Default for StagingDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, Ix> StagingDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Creates a new StagingDag around an empty graph
+    pub fn new() -> StagingDag<T, Ix> {
+        StagingDag::from_graph(BullDag::new())
+    }
+
+    /// Creates a new StagingDag around an existing graph
+    pub fn from_graph(graph: BullDag<T, Ix>) -> StagingDag<T, Ix> {
+        StagingDag {
+            graph,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Adds a vertex to the graph if it is not already present, then
+    /// applies any buffered edges that were waiting on it.
+    pub fn add_vertex(&mut self, vertex: Vertex<T, Ix>) {
+        let index = vertex.get_index();
+        if !self.graph.contains_vertex(&index) {
+            self.graph.add_vertex(&vertex);
+        }
+
+        if let Some(edges) = self.pending.remove(&index) {
+            edges.into_iter().for_each(|e| self.add_edge(e));
+        }
+    }
+
+    /// Adds an edge to the graph if both of its vertices are present,
+    /// otherwise buffers it until the missing vertex arrives.
+    pub fn add_edge(&mut self, edge: Edge<Ix>) {
+        match self
+            .graph
+            .add_edge_ix(&edge.get_source(), &edge.get_reference())
+        {
+            Err(GraphError::NonExistentSource) => self.buffer(edge.get_source(), edge),
+            Err(GraphError::NonExistentReference) => self.buffer(edge.get_reference(), edge),
+            _ => {}
+        }
+    }
+
+    fn buffer(&mut self, missing: Ix, edge: Edge<Ix>) {
+        let edges = self.pending.entry(missing).or_default();
+        if !edges.contains(&edge) {
+            edges.push(edge);
+        }
+    }
+
+    /// Returns the number of edges waiting on a missing vertex
+    pub fn n_pending(&self) -> usize {
+        self.pending.values().map(|edges| edges.len()).sum()
+    }
+
+    /// Returns all of the edges waiting on a missing vertex
+    pub fn pending_edges(&self) -> Vec<&Edge<Ix>> {
+        self.pending.values().flatten().collect()
+    }
+
+    /// Returns the indices of the vertices that buffered edges are waiting on
+    pub fn missing_vertices(&self) -> Vec<&Ix> {
+        self.pending.keys().collect()
+    }
+
+    /// Get a reference to the underlying graph
+    pub fn graph(&self) -> &BullDag<T, Ix> {
+        &self.graph
+    }
+
+    /// Consumes the StagingDag, returning the graph and dropping any
+    /// edges that are still pending
+    pub fn into_inner(self) -> BullDag<T, Ix> {
+        self.graph
+    }
+}
@@ -0,0 +1,151 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+
+/// A summary of the shape of a graph, returned by [`BullDag::stats`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphStats {
+    pub vertices: usize,
+    pub edges: usize,
+    pub roots: usize,
+    pub leaves: usize,
+    /// The number of edges on the longest path from a root to a leaf
+    pub max_depth: usize,
+    /// The most sources any one vertex has
+    pub max_in_degree: usize,
+    /// The most references any one vertex has
+    pub max_out_degree: usize,
+    /// The mean number of edges a vertex is part of, 0 for an empty graph
+    pub average_degree: f64,
+    /// The number of groups of vertices connected by edges in either
+    /// direction
+    pub components: usize,
+}
+
+/// How many vertices have each number of sources and of references,
+/// returned by [`BullDag::degree_distribution`]. Degrees no vertex has are
+/// left out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DegreeDistribution {
+    /// The number of vertices with each number of sources
+    pub in_degree: BTreeMap<usize, usize>,
+    /// The number of vertices with each number of references
+    pub out_degree: BTreeMap<usize, usize>,
+}
+
+/// Finds the group a vertex belongs to, halving the path as it goes
+fn find(parent: &mut [usize], mut id: usize) -> usize {
+    while parent[id] != id {
+        parent[id] = parent[parent[id]];
+        id = parent[id];
+    }
+    id
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Computes a [`GraphStats`] summary of the graph in a single pass over
+    /// its vertices in topological order.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// let v5: Vertex<usize, &str> = Vertex::new(1, "loner");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4)]);
+    /// graph.add_vertex(&v5);
+    ///
+    /// let stats = graph.stats();
+    /// assert!(stats.vertices == 5 && stats.edges == 3);
+    /// assert!(stats.roots == 2 && stats.leaves == 3);
+    /// assert!(stats.max_depth == 2);
+    /// assert!(stats.max_in_degree == 1 && stats.max_out_degree == 2);
+    /// assert!(stats.average_degree == 1.2);
+    /// assert!(stats.components == 2);
+    /// ```
+    pub fn stats(&self) -> GraphStats {
+        let order = self.topological_order();
+        let ids: HashMap<&Ix, usize> = order.iter().enumerate().map(|(i, ix)| (ix, i)).collect();
+        let mut depth = vec![0; order.len()];
+        let mut parent: Vec<usize> = (0..order.len()).collect();
+        let mut components = order.len();
+        let mut stats = GraphStats {
+            vertices: self.len(),
+            edges: self.n_edges(),
+            roots: self.n_roots(),
+            leaves: self.n_leaves(),
+            max_depth: 0,
+            max_in_degree: 0,
+            max_out_degree: 0,
+            average_degree: 0.0,
+            components: 0,
+        };
+
+        for (id, ix) in order.iter().enumerate() {
+            let vtx = &self.vertices[ix];
+            stats.max_in_degree = stats.max_in_degree.max(vtx.n_sources());
+            stats.max_out_degree = stats.max_out_degree.max(vtx.n_references());
+            for source in vtx.get_sources() {
+                let source = ids[source];
+                depth[id] = depth[id].max(depth[source] + 1);
+                let (a, b) = (find(&mut parent, id), find(&mut parent, source));
+                if a != b {
+                    parent[a] = b;
+                    components -= 1;
+                }
+            }
+            stats.max_depth = stats.max_depth.max(depth[id]);
+        }
+
+        if stats.vertices > 0 {
+            stats.average_degree = 2.0 * stats.edges as f64 / stats.vertices as f64;
+        }
+        stats.components = components;
+        stats
+    }
+
+    /// Counts the vertices with each in and out degree, so a few hub
+    /// vertices with far more edges than the rest stand out as a long
+    /// tail
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3)]);
+    ///
+    /// let distribution = graph.degree_distribution();
+    /// assert!(distribution.in_degree == [(0, 1), (1, 2)].into());
+    /// assert!(distribution.out_degree == [(0, 2), (2, 1)].into());
+    /// ```
+    pub fn degree_distribution(&self) -> DegreeDistribution {
+        let mut distribution = DegreeDistribution {
+            in_degree: BTreeMap::new(),
+            out_degree: BTreeMap::new(),
+        };
+        for vtx in self.vertices.values() {
+            *distribution.in_degree.entry(vtx.n_sources()).or_default() += 1;
+            *distribution
+                .out_degree
+                .entry(vtx.n_references())
+                .or_default() += 1;
+        }
+        distribution
+    }
+}
@@ -0,0 +1,352 @@
+use crate::edge::Edge;
+use crate::graph::{BullDag, GraphError, GraphResult};
+use crate::index::Index;
+use crate::vertex::{Direction, Vertex};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+type StoreIter<'a, I> = Box<dyn Iterator<Item = Result<I, GraphError>> + 'a>;
+
+/// Where a [`PersistentBullDag`] keeps its vertex payloads and edges.
+/// Backends report their own failures as `GraphError::Other`.
+///
+/// [`MemoryStore`] keeps everything in memory and, with the `sled` feature,
+/// [`SledStore`] keeps it on disk. Other backends (e.g. rocksdb) can
+/// implement this trait outside of the crate.
+pub trait GraphStore<T, Ix: Index> {
+    /// Returns the payload stored for a vertex
+    fn get_vertex(&self, index: &Ix) -> Result<Option<T>, GraphError>;
+    /// Stores a vertex's payload, replacing any earlier one
+    fn put_vertex(&mut self, index: &Ix, data: &T) -> Result<(), GraphError>;
+    /// Stores an edge, storing one that is already stored does nothing
+    fn put_edge(&mut self, edge: &Edge<Ix>) -> Result<(), GraphError>;
+    /// Iterates over the indices of every stored vertex
+    fn vertices(&self) -> StoreIter<'_, Ix>;
+    /// Iterates over every stored edge
+    fn edges(&self) -> StoreIter<'_, Edge<Ix>>;
+}
+
+/// A [`GraphStore`] that keeps everything in memory, useful for tests and
+/// as a reference for other backends
+#[derive(Debug, Clone)]
+pub struct MemoryStore<T, Ix: Index> {
+    vertices: HashMap<Ix, T>,
+    edges: HashSet<Edge<Ix>>,
+}
+
+impl<T, Ix: Index> Default for MemoryStore<T, Ix> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, Ix: Index> MemoryStore<T, Ix> {
+    pub fn new() -> MemoryStore<T, Ix> {
+        MemoryStore {
+            vertices: HashMap::new(),
+            edges: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Clone, Ix: Index> GraphStore<T, Ix> for MemoryStore<T, Ix> {
+    fn get_vertex(&self, index: &Ix) -> Result<Option<T>, GraphError> {
+        Ok(self.vertices.get(index).cloned())
+    }
+
+    fn put_vertex(&mut self, index: &Ix, data: &T) -> Result<(), GraphError> {
+        self.vertices.insert(index.clone(), data.clone());
+        Ok(())
+    }
+
+    fn put_edge(&mut self, edge: &Edge<Ix>) -> Result<(), GraphError> {
+        self.edges.insert(edge.clone());
+        Ok(())
+    }
+
+    fn vertices(&self) -> StoreIter<'_, Ix> {
+        Box::new(self.vertices.keys().cloned().map(Ok))
+    }
+
+    fn edges(&self) -> StoreIter<'_, Edge<Ix>> {
+        Box::new(self.edges.iter().cloned().map(Ok))
+    }
+}
+
+/// A graph whose structure, i.e. the indices, edges, roots and leaves, is
+/// kept in memory while the vertex payloads are read from and written
+/// through a [`GraphStore`]. Traversals and cycle checks never touch the
+/// store, only reading a vertex's data does.
+///
+/// Example
+///
+/// ```
+/// use bulldag::store::{MemoryStore, PersistentBullDag};
+///
+/// let mut graph: PersistentBullDag<String, u64, _> = PersistentBullDag::new(MemoryStore::new());
+/// graph.add_vertex(&0, &"genesis".to_string()).unwrap();
+/// graph.add_vertex(&1, &"block".to_string()).unwrap();
+/// graph.add_edge_ix(&0, &1).unwrap();
+///
+/// let reopened: PersistentBullDag<String, u64, _> =
+///     PersistentBullDag::open(graph.into_store()).unwrap();
+/// assert!(reopened.n_edges() == 1);
+/// assert!(reopened.get_data(&0).unwrap() == Some("genesis".to_string()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PersistentBullDag<T, Ix: Index + Debug, S> {
+    graph: BullDag<(), Ix>,
+    store: S,
+    _data: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T, Ix, S> PersistentBullDag<T, Ix, S>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+    S: GraphStore<T, Ix>,
+{
+    /// Creates a graph on a store, ignoring anything already in it, see
+    /// [`PersistentBullDag::open`]
+    pub fn new(store: S) -> PersistentBullDag<T, Ix, S> {
+        PersistentBullDag {
+            graph: BullDag::new(),
+            store,
+            _data: std::marker::PhantomData,
+        }
+    }
+
+    /// Creates a graph from the vertices and edges already in a store,
+    /// loading only their indices
+    pub fn open(store: S) -> Result<PersistentBullDag<T, Ix, S>, GraphError> {
+        let mut graph = BullDag::new();
+        for index in store.vertices() {
+            graph.add_vertex(&Vertex::new((), index?));
+        }
+        for edge in store.edges() {
+            let edge = edge?;
+            graph.add_edge_ix(&edge.get_source(), &edge.get_reference())?;
+        }
+        Ok(PersistentBullDag {
+            graph,
+            store,
+            _data: std::marker::PhantomData,
+        })
+    }
+
+    /// Stores a vertex's data, adding the vertex if it is new
+    pub fn add_vertex(&mut self, index: &Ix, data: &T) -> Result<(), GraphError> {
+        self.store.put_vertex(index, data)?;
+        if !self.graph.contains_vertex(index) {
+            self.graph.add_vertex(&Vertex::new((), index.clone()));
+        }
+        Ok(())
+    }
+
+    /// Adds an edge between two existing vertices, see
+    /// [`BullDag::add_edge_ix`]. The edge is only kept in memory if the
+    /// store accepts it.
+    pub fn add_edge_ix(&mut self, source: &Ix, reference: &Ix) -> GraphResult {
+        let n_edges = self.graph.n_edges();
+        self.graph.add_edge_ix(source, reference)?;
+        let edge = Edge::new(source.clone(), reference.clone());
+        if self.graph.n_edges() > n_edges {
+            if let Err(e) = self.store.put_edge(&edge) {
+                self.graph.remove_edge(&edge);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a vertex's data from the store
+    pub fn get_data(&self, index: &Ix) -> Result<Option<T>, GraphError> {
+        if !self.contains_vertex(index) {
+            return Ok(None);
+        }
+        self.store.get_vertex(index)
+    }
+
+    /// Returns the vertex with its edges and the data read from the store
+    pub fn get_vertex(&self, index: &Ix) -> Result<Option<Vertex<T, Ix>>, GraphError> {
+        let Some(vtx) = self.graph.get_vertex(index.clone()) else {
+            return Ok(None);
+        };
+        let data = self
+            .store
+            .get_vertex(index)?
+            .ok_or(GraphError::NonExistentVertex)?;
+        let mut vertex = Vertex::new(data, index.clone());
+        vtx.get_sources()
+            .into_iter()
+            .for_each(|s| vertex.add_edge(&Edge::new(s.clone(), index.clone())));
+        vtx.get_references()
+            .into_iter()
+            .for_each(|r| vertex.add_edge(&Edge::new(index.clone(), r.clone())));
+        Ok(Some(vertex))
+    }
+
+    pub fn contains_vertex(&self, index: &Ix) -> bool {
+        self.graph.contains_vertex(index)
+    }
+
+    /// Returns every vertex reachable from `index` in the given direction,
+    /// including `index` itself, or an empty `Vec` if it is missing
+    pub fn trace(&self, index: &Ix, direction: Direction) -> Vec<Ix> {
+        self.graph
+            .get_vertex(index.clone())
+            .map(|vtx| self.graph.trace(vtx, direction))
+            .unwrap_or_default()
+    }
+
+    pub fn get_roots(&self) -> HashSet<Ix> {
+        self.graph.get_roots()
+    }
+
+    pub fn get_leaves(&self) -> HashSet<Ix> {
+        self.graph.get_leaves()
+    }
+
+    pub fn len(&self) -> usize {
+        self.graph.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.graph.is_empty()
+    }
+
+    pub fn n_edges(&self) -> usize {
+        self.graph.n_edges()
+    }
+
+    /// The in-memory structure of the graph, for any query that doesn't
+    /// need the vertex data
+    pub fn structure(&self) -> &BullDag<(), Ix> {
+        &self.graph
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// Reads every payload from the store into an in-memory [`BullDag`]
+    pub fn to_dag(&self) -> Result<BullDag<T, Ix>, GraphError> {
+        let mut graph = BullDag::new();
+        for index in self.graph.vertices.keys() {
+            let data = self
+                .store
+                .get_vertex(index)?
+                .ok_or(GraphError::NonExistentVertex)?;
+            graph.add_vertex(&Vertex::new(data, index.clone()));
+        }
+        for edge in self.graph.edges.iter() {
+            graph.add_edge_ix(&edge.get_source(), &edge.get_reference())?;
+        }
+        Ok(graph)
+    }
+}
+
+/// A [`GraphStore`] in a sled database, so that a [`PersistentBullDag`]
+/// survives restarts. Vertex payloads and edges are kept as JSON in two
+/// trees of the database, and a payload is only read from disk when it is
+/// asked for.
+///
+/// Example
+///
+/// ```
+/// use bulldag::store::{PersistentBullDag, SledStore};
+///
+/// let db = sled::Config::new().temporary(true).open().unwrap();
+/// let store: SledStore<String, u64> = SledStore::from_db(&db).unwrap();
+/// let mut graph = PersistentBullDag::new(store);
+/// graph.add_vertex(&0, &"genesis".to_string()).unwrap();
+/// graph.add_vertex(&1, &"block".to_string()).unwrap();
+/// graph.add_edge_ix(&0, &1).unwrap();
+///
+/// let reopened: PersistentBullDag<String, u64, _> =
+///     PersistentBullDag::open(SledStore::from_db(&db).unwrap()).unwrap();
+/// assert!(reopened.n_edges() == 1);
+/// ```
+#[cfg(feature = "sled")]
+#[derive(Debug, Clone)]
+pub struct SledStore<T, Ix> {
+    vertices: sled::Tree,
+    edges: sled::Tree,
+    _types: std::marker::PhantomData<fn() -> (T, Ix)>,
+}
+
+#[cfg(feature = "sled")]
+fn sled_error(e: impl std::error::Error + Send + Sync + 'static) -> GraphError {
+    GraphError::other(e)
+}
+
+#[cfg(feature = "sled")]
+impl<T, Ix> SledStore<T, Ix> {
+    /// Opens, or creates, the database at `path`
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<SledStore<T, Ix>, GraphError> {
+        SledStore::from_db(&sled::open(path).map_err(sled_error)?)
+    }
+
+    /// Uses the `vertices` and `edges` trees of an open database
+    pub fn from_db(db: &sled::Db) -> Result<SledStore<T, Ix>, GraphError> {
+        Ok(SledStore {
+            vertices: db.open_tree("vertices").map_err(sled_error)?,
+            edges: db.open_tree("edges").map_err(sled_error)?,
+            _types: std::marker::PhantomData,
+        })
+    }
+
+    /// Writes everything stored so far to disk, sled otherwise does so
+    /// periodically in the background
+    pub fn flush(&self) -> Result<(), GraphError> {
+        self.vertices.flush().map_err(sled_error)?;
+        self.edges.flush().map_err(sled_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sled")]
+impl<T, Ix> GraphStore<T, Ix> for SledStore<T, Ix>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+    Ix: Index + serde::Serialize + serde::de::DeserializeOwned,
+{
+    fn get_vertex(&self, index: &Ix) -> Result<Option<T>, GraphError> {
+        let key = serde_json::to_vec(index).map_err(sled_error)?;
+        match self.vertices.get(key).map_err(sled_error)? {
+            Some(data) => serde_json::from_slice(&data).map_err(sled_error),
+            None => Ok(None),
+        }
+    }
+
+    fn put_vertex(&mut self, index: &Ix, data: &T) -> Result<(), GraphError> {
+        let key = serde_json::to_vec(index).map_err(sled_error)?;
+        let data = serde_json::to_vec(data).map_err(sled_error)?;
+        self.vertices.insert(key, data).map_err(sled_error)?;
+        Ok(())
+    }
+
+    fn put_edge(&mut self, edge: &Edge<Ix>) -> Result<(), GraphError> {
+        let key = serde_json::to_vec(edge).map_err(sled_error)?;
+        self.edges.insert(key, &[]).map_err(sled_error)?;
+        Ok(())
+    }
+
+    fn vertices(&self) -> StoreIter<'_, Ix> {
+        Box::new(self.vertices.iter().keys().map(|key| {
+            let key = key.map_err(sled_error)?;
+            serde_json::from_slice(&key).map_err(sled_error)
+        }))
+    }
+
+    fn edges(&self) -> StoreIter<'_, Edge<Ix>> {
+        Box::new(self.edges.iter().keys().map(|key| {
+            let key = key.map_err(sled_error)?;
+            serde_json::from_slice(&key).map_err(sled_error)
+        }))
+    }
+}
@@ -0,0 +1,54 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Direction;
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+/// Inventory queries for gossip style synchronization between replicas.
+/// Rather than exchanging entire graphs, nodes advertise their frontier
+/// and answer "given your tips, here is what you're missing" with
+/// [`BullDag::missing_ancestors`].
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns true if the vertex at `ix` is in the graph
+    pub fn have(&self, ix: &Ix) -> bool {
+        self.vertices.contains_key(ix)
+    }
+
+    /// Returns the current frontier of the graph, i.e. the leaves, which
+    /// is what a node advertises to its peers
+    pub fn frontier(&self) -> Vec<Ix> {
+        self.leaves.iter().cloned().collect()
+    }
+
+    /// Returns `tips` and all of their ancestors that are not in `known`,
+    /// in topological order so that the receiver can add them as they
+    /// arrive. A known vertex is assumed to come with all of its own
+    /// ancestors, so the search stops there. Tips that are not in this
+    /// graph are ignored.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    /// use std::collections::HashSet;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "genesis");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "block_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "block_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "block_3");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v2, &v3), (&v3, &v4)]);
+    ///
+    /// let known: HashSet<&str> = ["genesis", "block_1"].into_iter().collect();
+    /// let missing = graph.missing_ancestors(&graph.frontier(), &known);
+    /// assert!(missing == vec!["block_2", "block_3"]);
+    /// ```
+    pub fn missing_ancestors(&self, tips: &[Ix], known: &HashSet<Ix>) -> Vec<Ix> {
+        let missing = self.reachable(tips, Direction::Source, |ix| known.contains(ix));
+        self.topological_order_of(&missing)
+    }
+}
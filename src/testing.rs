@@ -0,0 +1,114 @@
+use crate::edge::Edge;
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Vertex;
+use proptest::collection::{hash_set, vec};
+use proptest::prelude::*;
+use std::fmt::Debug;
+use std::ops::Range;
+
+/// A vertex with arbitrary data and index, and no edges
+impl<T, Ix> Arbitrary for Vertex<T, Ix>
+where
+    T: Arbitrary + Clone + Debug + 'static,
+    Ix: Arbitrary + Index + Debug + 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<T>(), any::<Ix>())
+            .prop_map(|(data, index)| Vertex::new(data, index))
+            .boxed()
+    }
+}
+
+impl<Ix> Arbitrary for Edge<Ix>
+where
+    Ix: Arbitrary + Index + Debug + 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (any::<Ix>(), any::<Ix>())
+            .prop_map(|(source, reference)| Edge::new(source, reference))
+            .boxed()
+    }
+}
+
+/// The size of the graphs generated by `any::<BullDag<T, Ix>>()`
+#[derive(Debug, Clone)]
+pub struct DagParams {
+    /// How many vertices to generate, fewer if some indices collide
+    pub vertices: Range<usize>,
+    /// How many edges to try, fewer are added when duplicates are drawn
+    pub edges: Range<usize>,
+}
+
+impl Default for DagParams {
+    fn default() -> Self {
+        DagParams {
+            vertices: 0..32,
+            edges: 0..64,
+        }
+    }
+}
+
+/// A random acyclic graph: the vertices are put in an arbitrary order and
+/// every edge goes from an earlier vertex to a later one, so no edge can
+/// close a cycle
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use proptest::prelude::*;
+/// use proptest::test_runner::TestRunner;
+///
+/// let mut runner = TestRunner::default();
+/// runner
+///     .run(&any::<BullDag<u8, u16>>(), |graph| {
+///         for root in graph.get_roots() {
+///             prop_assert!(graph.get_vertex(root).unwrap().n_sources() == 0);
+///         }
+///         Ok(())
+///     })
+///     .unwrap();
+/// ```
+impl<T, Ix> Arbitrary for BullDag<T, Ix>
+where
+    T: Arbitrary + Clone + Debug + 'static,
+    Ix: Arbitrary + Index + Debug + 'static,
+{
+    type Parameters = DagParams;
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(params: DagParams) -> Self::Strategy {
+        let edges = params.edges;
+        hash_set(any::<Ix>(), params.vertices)
+            .prop_flat_map(move |indices| {
+                let n = indices.len();
+                let pairs = match n {
+                    0 => vec((Just(0), Just(0)), 0..1).boxed(),
+                    _ => vec((0..n, 0..n), edges.clone()).boxed(),
+                };
+                (
+                    Just(indices.into_iter().collect::<Vec<Ix>>()),
+                    vec(any::<T>(), n),
+                    pairs,
+                )
+            })
+            .prop_map(|(indices, data, pairs)| {
+                let mut graph = BullDag::new();
+                for (index, data) in indices.iter().zip(data) {
+                    graph.add_vertex(&Vertex::new(data, index.clone()));
+                }
+                for (a, b) in pairs.into_iter().filter(|(a, b)| a != b) {
+                    let _ = graph.add_edge_ix(&indices[a.min(b)], &indices[a.max(b)]);
+                }
+                graph
+            })
+            .boxed()
+    }
+}
@@ -0,0 +1,104 @@
+use crate::edge::{Edge, IntoEdgeSpec};
+use crate::graph::{BullDag, GraphResult};
+use crate::index::Index;
+use crate::observe::Event;
+use crate::vertex::Direction;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::ops::RangeBounds;
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Adds an edge stamped with the time `at`, like
+    /// [`BullDag::try_add_edge`]. The unit is up to the caller, e.g. seconds
+    /// since the Unix epoch, as long as every edge of the graph uses the
+    /// same one. If the vertices are already connected the edge's time is
+    /// replaced. Edges added without a time have none and are left out of
+    /// every time window.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::edge::Edge;
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::{Direction, Vertex};
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// graph.add_edge_at((&v1, &v2), 100).unwrap();
+    /// graph.add_edge_at((&v2, &v3), 160).unwrap();
+    ///
+    /// // What was referenced in the last minute, as of 170
+    /// assert!(graph.edges_in_range(110..=170) == vec![Edge::new("reference_1", "reference_2")]);
+    /// assert!(graph.trace_in_range(&"source", Direction::Reference, 110..).len() == 1);
+    /// assert!(graph.trace_in_range(&"source", Direction::Reference, ..).len() == 3);
+    /// ```
+    pub fn add_edge_at(&mut self, edge: impl IntoEdgeSpec<T, Ix>, at: u64) -> GraphResult {
+        let spec = edge.into_edge_spec();
+        let e = spec.edge();
+        self.try_add_edge(spec)?;
+        self.set_edge_time(&e, at);
+        Ok(())
+    }
+
+    /// Stamps an edge with the time `at`, returning false if there is no
+    /// such edge
+    pub fn set_edge_time(&mut self, edge: &Edge<Ix>, at: u64) -> bool {
+        if !self.edges.contains(edge) {
+            return false;
+        }
+        self.edge_times.insert(edge, at);
+        self.notify(Event::EdgeTimeSet(edge, at));
+        true
+    }
+
+    /// Returns the time an edge was stamped with, or `None` if it has none
+    /// or there is no such edge
+    pub fn edge_time(&self, edge: &Edge<Ix>) -> Option<u64> {
+        self.edge_times.get(edge).copied()
+    }
+
+    /// Returns every edge stamped with a time in `range`, the earliest
+    /// first. Goes over every stamped edge.
+    pub fn edges_in_range(&self, range: impl RangeBounds<u64>) -> Vec<Edge<Ix>> {
+        let mut edges: Vec<(u64, Edge<Ix>)> = self
+            .edge_times
+            .iter()
+            .filter(|(_, at)| range.contains(*at))
+            .map(|(edge, at)| (*at, edge))
+            .collect();
+        edges.sort_by_key(|(at, _)| *at);
+        edges.into_iter().map(|(_, edge)| edge).collect()
+    }
+
+    /// Like [`BullDag::trace`], but only follows edges stamped with a time
+    /// in `range`. Returns the vertices reached, the vertex at `ix` last,
+    /// or nothing if there is no such vertex.
+    pub fn trace_in_range(
+        &self,
+        ix: &Ix,
+        direction: Direction,
+        range: impl RangeBounds<u64>,
+    ) -> Vec<Ix> {
+        if !self.contains_vertex(ix) {
+            return vec![];
+        }
+        let mut stack = vec![];
+        self.post_order_where(
+            ix.clone(),
+            direction,
+            &mut HashSet::new(),
+            &mut stack,
+            |source, reference| {
+                self.edge_times
+                    .between(source, reference)
+                    .is_some_and(|at| range.contains(at))
+            },
+        );
+        stack
+    }
+}
@@ -0,0 +1,322 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Direction;
+use rand::{Rng, RngCore};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+/// A strategy for choosing which tips (leaves, i.e. vertices that nothing
+/// references yet) a new vertex should reference. Strategies take the
+/// random number generator as a trait object so that they can be boxed
+/// and swapped at runtime. The strategies here sort their candidates
+/// before drawing from them, so a seeded generator makes the same choices
+/// on any copy of the graph.
+pub trait TipSelector<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Selects a single tip, returns `None` if the graph is empty
+    fn select_tip(&self, graph: &BullDag<T, Ix>, rng: &mut dyn RngCore) -> Option<Ix>;
+
+    /// Runs the selection `n` times and returns the distinct tips chosen,
+    /// which can be fewer than `n` when the same tip is selected twice.
+    fn select_tips(&self, graph: &BullDag<T, Ix>, n: usize, rng: &mut dyn RngCore) -> HashSet<Ix> {
+        (0..n).filter_map(|_| self.select_tip(graph, rng)).collect()
+    }
+}
+
+/// Selects any tip with equal probability
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::vertex::Vertex;
+/// use bulldag::tips::{TipSelector, UniformRandom};
+///
+/// let mut graph: BullDag<usize, &str> = BullDag::new();
+/// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+/// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+/// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+/// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3)]);
+///
+/// let tip = UniformRandom.select_tip(&graph, &mut rand::thread_rng());
+/// assert!(graph.get_leaves().contains(&tip.unwrap()));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UniformRandom;
+
+impl<T, Ix> TipSelector<T, Ix> for UniformRandom
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    fn select_tip(&self, graph: &BullDag<T, Ix>, rng: &mut dyn RngCore) -> Option<Ix> {
+        if graph.leaves.is_empty() {
+            return None;
+        }
+
+        let leaves = sorted(&graph.leaves);
+        let choice = rng.gen_range(0..leaves.len());
+        Some(leaves[choice].clone())
+    }
+}
+
+/// Selects a tip with probability proportional to the number of vertices
+/// that reach it through their references (its ancestors, plus one for
+/// the tip itself), favouring tips that build on more of the graph's
+/// history over lazy ones. The ancestors of every tip are counted in one
+/// pass over the graph, shared by all the draws of a
+/// [`TipSelector::select_tips`] call.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeightedByReferences;
+
+impl<T, Ix> TipSelector<T, Ix> for WeightedByReferences
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    fn select_tip(&self, graph: &BullDag<T, Ix>, rng: &mut dyn RngCore) -> Option<Ix> {
+        let weighted = WeightedByReferences::weights(graph);
+        weighted_choice(&weighted, rng)
+    }
+
+    fn select_tips(&self, graph: &BullDag<T, Ix>, n: usize, rng: &mut dyn RngCore) -> HashSet<Ix> {
+        let weighted = WeightedByReferences::weights(graph);
+        (0..n)
+            .filter_map(|_| weighted_choice(&weighted, rng))
+            .collect()
+    }
+}
+
+impl WeightedByReferences {
+    /// Every tip, in order, next to its ancestor count
+    fn weights<T, Ix>(graph: &BullDag<T, Ix>) -> Vec<(Ix, f64)>
+    where
+        T: Clone + Debug,
+        Ix: Index + Debug + Ord,
+    {
+        if graph.leaves.is_empty() {
+            return vec![];
+        }
+
+        let ancestors = graph.reach_counts(Direction::Source);
+        sorted(&graph.leaves)
+            .into_iter()
+            .map(|ix| (ix.clone(), ancestors.get(ix).copied().unwrap_or(1) as f64))
+            .collect()
+    }
+}
+
+/// Markov Chain Monte Carlo tip selection: starts a random walk at a
+/// uniformly chosen root and steps from a vertex to one of its references
+/// until it reaches a leaf. Each step favours references with a higher
+/// cumulative weight (see [`BullDag::cumulative_weights`]) with probability
+/// proportional to `exp(alpha * weight)`. An `alpha` of `0.0` is an unbiased
+/// walk, larger values make the walk follow the heaviest branch. The
+/// weights are computed once per call, and shared by all the walks of a
+/// [`TipSelector::select_tips`] call.
+#[derive(Debug, Clone, Copy)]
+pub struct RandomWalk {
+    pub alpha: f64,
+}
+
+impl RandomWalk {
+    pub fn new(alpha: f64) -> RandomWalk {
+        RandomWalk { alpha }
+    }
+}
+
+impl<T, Ix> TipSelector<T, Ix> for RandomWalk
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    fn select_tip(&self, graph: &BullDag<T, Ix>, rng: &mut dyn RngCore) -> Option<Ix> {
+        self.walk(graph, &graph.cumulative_weights(), rng)
+    }
+
+    fn select_tips(&self, graph: &BullDag<T, Ix>, n: usize, rng: &mut dyn RngCore) -> HashSet<Ix> {
+        let cumulative = graph.cumulative_weights();
+        (0..n)
+            .filter_map(|_| self.walk(graph, &cumulative, rng))
+            .collect()
+    }
+}
+
+impl RandomWalk {
+    /// Walks from a random root to a tip, biased by the given cumulative
+    /// weights
+    fn walk<T, Ix>(
+        &self,
+        graph: &BullDag<T, Ix>,
+        cumulative: &HashMap<Ix, u64>,
+        rng: &mut dyn RngCore,
+    ) -> Option<Ix>
+    where
+        T: Clone + Debug,
+        Ix: Index + Debug + Ord,
+    {
+        if graph.roots.is_empty() {
+            return None;
+        }
+
+        let roots = sorted(&graph.roots);
+        let root = roots[rng.gen_range(0..roots.len())];
+        let mut path = graph.random_walk(root, Direction::Reference, rng);
+        path.bias = Some((self.alpha, Cow::Borrowed(cumulative)));
+        path.last()
+    }
+}
+
+/// A path sampled one step at a time, returned by
+/// [`BullDag::random_walk`]. Each step moves from a vertex to one of its
+/// neighbors, chosen uniformly unless the walk is [`RandomPath::biased`],
+/// until it reaches a vertex with none. The neighbors are sorted before
+/// each draw, so a seeded generator walks the same path on any copy of
+/// the graph.
+pub struct RandomPath<'a, T: Clone + Debug, Ix: Index + Debug> {
+    graph: &'a BullDag<T, Ix>,
+    next: Option<Ix>,
+    direction: Direction,
+    rng: &'a mut dyn RngCore,
+    /// The `alpha` of the bias and the cumulative weight of every vertex
+    bias: Option<(f64, Cow<'a, HashMap<Ix, u64>>)>,
+}
+
+impl<T, Ix> RandomPath<'_, T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Favours neighbors with a higher cumulative weight (see
+    /// [`BullDag::cumulative_weights`]) with probability proportional to
+    /// `exp(alpha * weight)`, like [`RandomWalk`]. The weights of the whole
+    /// graph are computed up front.
+    pub fn biased(mut self, alpha: f64) -> Self {
+        self.bias = Some((alpha, Cow::Owned(self.graph.cumulative_weights())));
+        self
+    }
+}
+
+impl<T, Ix> Iterator for RandomPath<'_, T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    type Item = Ix;
+
+    fn next(&mut self) -> Option<Ix> {
+        let current = self.next.take()?;
+        let mut neighbors = self.graph.vertices[&current].neighbors(self.direction);
+        neighbors.sort_unstable();
+        self.next = match &self.bias {
+            _ if neighbors.is_empty() => None,
+            None => {
+                let choice = self.rng.gen_range(0..neighbors.len());
+                Some(neighbors[choice].clone())
+            }
+            Some((alpha, cumulative)) => {
+                let weights: Vec<(Ix, f64)> = neighbors
+                    .into_iter()
+                    .map(|ix| (ix.clone(), cumulative.get(ix).copied().unwrap_or(1) as f64))
+                    .collect();
+
+                // Subtract the heaviest weight before exponentiating so large
+                // weights and alphas don't overflow to infinity.
+                let max = weights.iter().map(|(_, w)| *w).fold(f64::MIN, f64::max);
+                let biased: Vec<(Ix, f64)> = weights
+                    .into_iter()
+                    .map(|(ix, w)| (ix, (alpha * (w - max)).exp()))
+                    .collect();
+
+                weighted_choice(&biased, self.rng)
+            }
+        };
+        Some(current)
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug + Ord,
+{
+    /// Returns a [`RandomPath`] from `start` following `direction`, towards
+    /// the leaves when following references and the roots when following
+    /// sources. The path starts with `start` and is empty if there is no
+    /// such vertex.
+    ///
+    /// Example
+    ///
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::{Direction, Vertex};
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4)]);
+    ///
+    /// let mut rng = rand::thread_rng();
+    /// let path: Vec<&str> = graph.random_walk(&"source", Direction::Reference, &mut rng).collect();
+    /// assert!(path[0] == "source");
+    /// assert!(graph.get_leaves().contains(path.last().unwrap()));
+    ///
+    /// let path: Vec<&str> = graph
+    ///     .random_walk(&"ref_reference", Direction::Source, &mut rng)
+    ///     .biased(1.0)
+    ///     .collect();
+    /// assert!(path == vec!["ref_reference", "reference_1", "source"]);
+    /// ```
+    pub fn random_walk<'a>(
+        &'a self,
+        start: &Ix,
+        direction: Direction,
+        rng: &'a mut dyn RngCore,
+    ) -> RandomPath<'a, T, Ix> {
+        RandomPath {
+            graph: self,
+            next: self.contains_vertex(start).then(|| start.clone()),
+            direction,
+            rng,
+            bias: None,
+        }
+    }
+}
+
+/// The items of a set in order, so that drawing the `n`th doesn't depend
+/// on the order the set happens to iterate in
+fn sorted<Ix: Ord>(set: &HashSet<Ix>) -> Vec<&Ix> {
+    let mut items: Vec<&Ix> = set.iter().collect();
+    items.sort_unstable();
+    items
+}
+
+/// Picks an item with probability proportional to its weight, falling
+/// back to a uniform choice if every weight is zero.
+fn weighted_choice<Ix: Clone>(items: &[(Ix, f64)], rng: &mut dyn RngCore) -> Option<Ix> {
+    if items.is_empty() {
+        return None;
+    }
+
+    let total: f64 = items.iter().map(|(_, w)| *w).sum();
+    if total <= 0.0 || !total.is_finite() {
+        let choice = rng.gen_range(0..items.len());
+        return Some(items[choice].0.clone());
+    }
+
+    let mut target = rng.gen_range(0.0..total);
+    for (ix, weight) in items.iter() {
+        if target < *weight {
+            return Some(ix.clone());
+        }
+        target -= weight;
+    }
+
+    items.last().map(|(ix, _)| ix.clone())
+}
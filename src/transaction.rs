@@ -0,0 +1,119 @@
+use crate::graph::{BullDag, GraphError, GraphResult};
+use crate::index::Index;
+use crate::journal::GraphOp;
+use crate::vertex::Vertex;
+use std::fmt::Debug;
+
+/// Changes staged by [`BullDag::transaction`]. Each change is checked
+/// against, and made to, a copy of the graph, and only once every change
+/// has succeeded are they made to the graph itself.
+pub struct Transaction<T: Clone + Debug, Ix: Index + Debug> {
+    staged: BullDag<T, Ix>,
+    ops: Vec<GraphOp<T, Ix>>,
+}
+
+impl<T, Ix> Transaction<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn stage(&mut self, op: GraphOp<T, Ix>) -> GraphResult {
+        op.apply(&mut self.staged)?;
+        self.ops.push(op);
+        Ok(())
+    }
+
+    /// Adds a vertex, or replaces the data of the vertex already at its
+    /// index. Its sources and references are ignored.
+    pub fn add_vertex(&mut self, vertex: &Vertex<T, Ix>) -> GraphResult {
+        self.stage(GraphOp::AddVertex {
+            index: vertex.get_index(),
+            data: vertex.get_data(),
+        })
+    }
+
+    /// See [`BullDag::add_edge_ix`]
+    pub fn add_edge(&mut self, source: &Ix, reference: &Ix) -> GraphResult {
+        self.stage(GraphOp::AddEdge {
+            source: source.clone(),
+            reference: reference.clone(),
+        })
+    }
+
+    /// Removes an edge, failing with `NoEdges` if there is no such edge
+    pub fn remove_edge(&mut self, source: &Ix, reference: &Ix) -> GraphResult {
+        self.stage(GraphOp::RemoveEdge {
+            source: source.clone(),
+            reference: reference.clone(),
+        })
+    }
+
+    /// Removes a vertex with all of its edges, failing with
+    /// `NonExistentVertex` if there is no such vertex
+    pub fn remove_vertex(&mut self, ix: &Ix) -> GraphResult {
+        self.stage(GraphOp::RemoveVertex { index: ix.clone() })
+    }
+
+    /// Returns the graph as it will be if the transaction succeeds
+    pub fn graph(&self) -> &BullDag<T, Ix> {
+        &self.staged
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Runs `f` with a [`Transaction`] to stage changes through, then
+    /// makes every staged change to the graph if `f` returns `Ok`, or none
+    /// of them if it returns an error. Observers and the journal see the
+    /// changes one at a time, and only once they are made. The graph is
+    /// copied to stage the changes on, so a transaction costs as much as a
+    /// [`BullDag::checkpoint`].
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::{BullDag, GraphError};
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// let result = graph.transaction(|tx| {
+    ///     tx.add_vertex(&Vertex::new(3, "new"))?;
+    ///     tx.add_edge(&"reference", &"new")?;
+    ///     tx.add_edge(&"new", &"source")?;
+    ///     Ok(())
+    /// });
+    /// assert!(matches!(result, Err(GraphError::WouldCycle)));
+    /// assert!(graph.len() == 2);
+    ///
+    /// let result = graph.transaction(|tx| {
+    ///     tx.add_vertex(&Vertex::new(3, "new"))?;
+    ///     tx.add_edge(&"reference", &"new")?;
+    ///     tx.remove_vertex(&"source")?;
+    ///     Ok(tx.graph().len())
+    /// });
+    /// assert!(result.unwrap() == 2);
+    /// assert!(graph.get_roots().contains("reference"));
+    /// ```
+    pub fn transaction<R>(
+        &mut self,
+        f: impl FnOnce(&mut Transaction<T, Ix>) -> Result<R, GraphError>,
+    ) -> Result<R, GraphError> {
+        let mut tx = Transaction {
+            staged: self.copy_structure(),
+            ops: vec![],
+        };
+        let result = f(&mut tx)?;
+
+        // Each op succeeded on an identical copy, so it can't fail here
+        for op in tx.ops {
+            op.apply(self)?;
+        }
+        Ok(result)
+    }
+}
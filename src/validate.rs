@@ -0,0 +1,308 @@
+use crate::edge::Edge;
+use crate::graph::BullDag;
+use crate::index::Index;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+use std::io::Read;
+
+/// A broken invariant found by [`BullDag::validate`]. The graph methods
+/// never produce these, but a deserialized payload can contain anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphViolation<Ix: Index + Debug> {
+    /// A vertex is stored under a different index than its own
+    IndexMismatch { key: Ix, index: Ix },
+    /// The edge's source is not in the graph
+    NonExistentSource(Edge<Ix>),
+    /// The edge's reference is not in the graph
+    NonExistentReference(Edge<Ix>),
+    /// The edge is in the edge set, but its source doesn't list the
+    /// reference or its reference doesn't list the source
+    MissingAdjacency(Edge<Ix>),
+    /// A vertex lists a source or reference that has no edge in the edge set
+    UnrecordedEdge(Edge<Ix>),
+    /// The vertex is marked as a root but has sources, or the other way round
+    RootMismatch(Ix),
+    /// The vertex is marked as a leaf but has references, or the other way
+    /// round
+    LeafMismatch(Ix),
+    /// The edges contain at least one cycle, holds every vertex that is on
+    /// a cycle or that can only be reached through one
+    Cycle(Vec<Ix>),
+    /// An insertion number is kept for a vertex that is not in the graph
+    DanglingSequence(Ix),
+    /// An epoch is kept for a vertex that is not in the graph
+    DanglingEpoch(Ix),
+    /// `a` is listed as conflicting with `b`, but one of them is not in
+    /// the graph
+    DanglingConflict { a: Ix, b: Ix },
+    /// `a` is listed as conflicting with `b`, but `b` isn't listed as
+    /// conflicting with `a`
+    AsymmetricConflict { a: Ix, b: Ix },
+    /// A kind is kept for an edge that is not in the graph
+    DanglingEdgeKind(Edge<Ix>),
+    /// A time is kept for an edge that is not in the graph
+    DanglingEdgeTime(Edge<Ix>),
+    /// A weight is kept for an edge that is not in the graph
+    DanglingEdgeWeight(Edge<Ix>),
+    /// Keys are kept for an edge that is not in the graph
+    DanglingEdgeKeys(Edge<Ix>),
+}
+
+/// The ways a `from_*_checked` constructor can fail
+#[derive(Debug)]
+pub enum CheckedError<Ix: Index + Debug> {
+    /// The payload could not be deserialized at all
+    Malformed(serde_json::Error),
+    /// The payload deserialized into a graph that breaks its invariants
+    Invalid(Vec<GraphViolation<Ix>>),
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Checks that the vertices, edges, roots and leaves agree with each
+    /// other, that the graph is acyclic, and that the insertion numbers,
+    /// epochs, conflicts and edge kinds, times, weights and keys are only
+    /// kept for vertices and edges in the graph, returning every violation
+    /// found
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    ///
+    /// assert!(graph.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<GraphViolation<Ix>>> {
+        let mut violations = vec![];
+
+        for (key, vtx) in self.vertices.iter() {
+            let index = vtx.get_index();
+            if &index != key {
+                violations.push(GraphViolation::IndexMismatch {
+                    key: key.clone(),
+                    index,
+                });
+            }
+        }
+
+        for edge in self.edges.iter() {
+            let source = self.vertices.get(&edge.get_source());
+            let reference = self.vertices.get(&edge.get_reference());
+            match (source, reference) {
+                (None, _) => violations.push(GraphViolation::NonExistentSource(edge.clone())),
+                (_, None) => violations.push(GraphViolation::NonExistentReference(edge.clone())),
+                (Some(source), Some(reference)) => {
+                    if !source.is_reference(&edge.get_reference())
+                        || !reference.is_source(&edge.get_source())
+                    {
+                        violations.push(GraphViolation::MissingAdjacency(edge.clone()));
+                    }
+                }
+            }
+        }
+
+        let mut unrecorded = HashSet::new();
+        for (ix, vtx) in self.vertices.iter() {
+            let outgoing = vtx
+                .get_references()
+                .into_iter()
+                .map(|r| Edge::new(ix.clone(), r.clone()));
+            let incoming = vtx
+                .get_sources()
+                .into_iter()
+                .map(|s| Edge::new(s.clone(), ix.clone()));
+            for edge in outgoing.chain(incoming) {
+                if !self.edges.contains(&edge) && unrecorded.insert(edge.clone()) {
+                    violations.push(GraphViolation::UnrecordedEdge(edge));
+                }
+            }
+        }
+
+        for (ix, vtx) in self.vertices.iter() {
+            if (vtx.n_sources() == 0) != self.roots.contains(ix) {
+                violations.push(GraphViolation::RootMismatch(ix.clone()));
+            }
+            if (vtx.n_references() == 0) != self.leaves.contains(ix) {
+                violations.push(GraphViolation::LeafMismatch(ix.clone()));
+            }
+        }
+        for ix in self.roots.iter() {
+            if !self.vertices.contains_key(ix) {
+                violations.push(GraphViolation::RootMismatch(ix.clone()));
+            }
+        }
+        for ix in self.leaves.iter() {
+            if !self.vertices.contains_key(ix) {
+                violations.push(GraphViolation::LeafMismatch(ix.clone()));
+            }
+        }
+
+        let cyclic = self.cyclic_vertices();
+        if !cyclic.is_empty() {
+            violations.push(GraphViolation::Cycle(cyclic));
+        }
+
+        violations.extend(self.dangling_tags());
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Panics, listing every violation, if the graph breaks any of the
+    /// invariants checked by [`BullDag::validate`]: acyclicity, roots and
+    /// leaves matching the vertices without sources and references, the
+    /// edge set matching every vertex's sources and references, no edge to
+    /// a missing vertex, symmetric conflicts, and no tag of a missing
+    /// vertex or edge. Meant for fuzz targets and test suites,
+    /// which should call it after every mutation of the graph under test.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// graph.add_edge(&(&v1, &v2));
+    /// graph.check_invariants();
+    /// graph.remove_vertex("source");
+    /// graph.check_invariants();
+    /// ```
+    #[track_caller]
+    pub fn check_invariants(&self) {
+        if let Err(violations) = self.validate() {
+            panic!("graph invariants violated: {violations:?}");
+        }
+    }
+
+    /// The insertion numbers, epochs, conflicts and edge tags that refer to
+    /// a vertex or an edge that is not in the graph, and the conflicts only
+    /// listed one way
+    fn dangling_tags(&self) -> Vec<GraphViolation<Ix>> {
+        let mut violations = vec![];
+        let missing = |ix: &Ix| !self.vertices.contains_key(ix);
+
+        for ix in self.sequence.keys().filter(|ix| missing(ix)) {
+            violations.push(GraphViolation::DanglingSequence(ix.clone()));
+        }
+        for ix in self.epochs.keys().filter(|ix| missing(ix)) {
+            violations.push(GraphViolation::DanglingEpoch(ix.clone()));
+        }
+        for (a, others) in self.conflicts.iter() {
+            for b in others {
+                let (a, b) = (a.clone(), b.clone());
+                if missing(&a) || missing(&b) {
+                    violations.push(GraphViolation::DanglingConflict { a, b });
+                } else if !self.conflicts.get(&b).is_some_and(|c| c.contains(&a)) {
+                    violations.push(GraphViolation::AsymmetricConflict { a, b });
+                }
+            }
+        }
+
+        let dangling = |edge: &Edge<Ix>| !self.edges.contains(edge);
+        for (edge, _) in self.edge_kinds.iter().filter(|(e, _)| dangling(e)) {
+            violations.push(GraphViolation::DanglingEdgeKind(edge));
+        }
+        for (edge, _) in self.edge_times.iter().filter(|(e, _)| dangling(e)) {
+            violations.push(GraphViolation::DanglingEdgeTime(edge));
+        }
+        for (edge, _) in self.edge_weights.iter().filter(|(e, _)| dangling(e)) {
+            violations.push(GraphViolation::DanglingEdgeWeight(edge));
+        }
+        let keys = self.edge_keys.iter().flat_map(|keys| keys.iter());
+        for (edge, _) in keys.filter(|(e, _)| dangling(e)) {
+            violations.push(GraphViolation::DanglingEdgeKeys(edge));
+        }
+
+        violations
+    }
+
+    /// Kahn's algorithm over the edge set alone, so that it can't be thrown
+    /// off by vertices whose adjacency disagrees with the edges. Returns the
+    /// vertices that are never ordered.
+    fn cyclic_vertices(&self) -> Vec<Ix> {
+        let mut in_degree: HashMap<&Ix, usize> = self.vertices.keys().map(|ix| (ix, 0)).collect();
+        let mut references: HashMap<&Ix, Vec<&Ix>> = HashMap::new();
+        for edge in self.edges.iter() {
+            let (Some((source, _)), Some((reference, _))) = (
+                self.vertices.get_key_value(&edge.get_source()),
+                self.vertices.get_key_value(&edge.get_reference()),
+            ) else {
+                continue;
+            };
+            references.entry(source).or_default().push(reference);
+            *in_degree.entry(reference).or_default() += 1;
+        }
+
+        let mut queue: VecDeque<&Ix> = in_degree
+            .iter()
+            .filter(|(_, d)| **d == 0)
+            .map(|(ix, _)| *ix)
+            .collect();
+        while let Some(ix) = queue.pop_front() {
+            in_degree.remove(ix);
+            for r in references.get(ix).into_iter().flatten() {
+                if let Some(d) = in_degree.get_mut(r) {
+                    *d -= 1;
+                    if *d == 0 {
+                        queue.push_back(r);
+                    }
+                }
+            }
+        }
+
+        in_degree.into_keys().cloned().collect()
+    }
+}
+
+impl<'a, T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + Deserialize<'a>,
+    Ix: Index + Debug + Deserialize<'a>,
+{
+    /// Deserializes a graph from JSON produced by its serde derive, then
+    /// [validates](BullDag::validate) it, since the derive alone accepts
+    /// payloads that break the graph's invariants
+    pub fn from_json_checked(json: &'a str) -> Result<BullDag<T, Ix>, CheckedError<Ix>> {
+        let graph: BullDag<T, Ix> = serde_json::from_str(json).map_err(CheckedError::Malformed)?;
+        graph.validate().map_err(CheckedError::Invalid)?;
+        Ok(graph)
+    }
+
+    /// Deserializes a graph from a JSON byte slice, see
+    /// [`BullDag::from_json_checked`]
+    pub fn from_slice_checked(bytes: &'a [u8]) -> Result<BullDag<T, Ix>, CheckedError<Ix>> {
+        let graph: BullDag<T, Ix> =
+            serde_json::from_slice(bytes).map_err(CheckedError::Malformed)?;
+        graph.validate().map_err(CheckedError::Invalid)?;
+        Ok(graph)
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug + for<'de> Deserialize<'de>,
+    Ix: Index + Debug + for<'de> Deserialize<'de>,
+{
+    /// Deserializes a graph from a JSON reader, see
+    /// [`BullDag::from_json_checked`]
+    pub fn from_reader_checked<R: Read>(reader: R) -> Result<BullDag<T, Ix>, CheckedError<Ix>> {
+        let graph: BullDag<T, Ix> =
+            serde_json::from_reader(reader).map_err(CheckedError::Malformed)?;
+        graph.validate().map_err(CheckedError::Invalid)?;
+        Ok(graph)
+    }
+}
@@ -1,10 +1,12 @@
 use crate::edge::Edge;
 use crate::index::Index;
+use crate::memory::table_bytes;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fmt::Debug;
 
 pub type Edges<T, Ix> = Vec<(Vertex<T, Ix>, Vertex<T, Ix>)>;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Source,
     Reference,
@@ -73,6 +75,26 @@ where
         self.references.insert(reference);
     }
 
+    /// Remove a source from the vertex
+    pub(crate) fn remove_source(&mut self, source: &Ix) {
+        self.sources.remove(source);
+    }
+
+    /// Remove a reference from the vertex
+    pub(crate) fn remove_reference(&mut self, reference: &Ix) {
+        self.references.remove(reference);
+    }
+
+    /// The bytes allocated for the sources and references
+    pub(crate) fn adjacency_bytes(&self) -> usize {
+        table_bytes::<Ix>(self.sources.capacity()) + table_bytes::<Ix>(self.references.capacity())
+    }
+
+    /// Turns every source of the vertex into a reference and vice versa
+    pub(crate) fn reverse(&mut self) {
+        std::mem::swap(&mut self.sources, &mut self.references);
+    }
+
     /// Add an edge (source or reference) to the vertex.
     /// Checks whether or not the edge source index matches
     /// the local index or if the edge reference index
@@ -111,6 +133,37 @@ where
         self.data.clone()
     }
 
+    /// Borrows the data, for when a clone isn't needed
+    pub(crate) fn data(&self) -> &T {
+        &self.data
+    }
+
+    /// Borrows the data mutably, to change it in place
+    pub(crate) fn data_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+
+    /// A copy of the vertex, with the same index and edges, holding `data`
+    pub(crate) fn with_data<U: Clone + Debug>(&self, data: U) -> Vertex<U, Ix> {
+        Vertex {
+            data,
+            sources: self.sources.clone(),
+            references: self.references.clone(),
+            index: self.index.clone(),
+        }
+    }
+
+    /// Replace the data in the Vertex, leaving its edges untouched
+    /// ```
+    /// use bulldag::vertex::Vertex;
+    /// let mut vertex: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// vertex.set_data(6);
+    /// assert!(vertex.get_data() == 6usize);
+    /// ```
+    pub fn set_data(&mut self, data: T) {
+        self.data = data;
+    }
+
     /// Get the index from the Vertex
     /// ```
     /// use bulldag::vertex::Vertex;
@@ -150,6 +203,14 @@ where
         self.references.iter().collect()
     }
 
+    /// Get the sources or the references for the current vertex
+    pub(crate) fn neighbors(&self, direction: Direction) -> Vec<&Ix> {
+        match direction {
+            Direction::Source => self.get_sources(),
+            Direction::Reference => self.get_references(),
+        }
+    }
+
     pub fn is_reference(&self, target: &Ix) -> bool {
         self.references.contains(target)
     }
@@ -187,6 +248,33 @@ where
     }
 }
 
+#[cfg(feature = "hash")]
+impl<T> Vertex<T, [u8; 32]>
+where
+    T: Clone + Debug + Serialize,
+{
+    /// Creates a new Vertex whose index is the SHA-256 hash of its JSON
+    /// serialized data, so the index can always be recomputed from, and
+    /// checked against, the data it identifies.
+    pub fn content_addressed(data: T) -> Result<Vertex<T, [u8; 32]>, serde_json::Error> {
+        let index = Vertex::content_hash(&data)?;
+        Ok(Vertex::new(data, index))
+    }
+
+    /// Computes the index a content addressed vertex holding `data` would have
+    pub fn content_hash(data: &T) -> Result<[u8; 32], serde_json::Error> {
+        use sha2::{Digest, Sha256};
+
+        let bytes = serde_json::to_vec(data)?;
+        Ok(Sha256::digest(bytes).into())
+    }
+
+    /// Returns true if the index matches the hash of the vertex's data
+    pub fn verify_content(&self) -> bool {
+        Vertex::content_hash(&self.data).is_ok_and(|index| index == self.index)
+    }
+}
+
 /// Convert a tuple of two [`Vertex`]s into an [`Edge`].
 /// Source is the first item, reference the second item.
 impl<T, Ix> From<(Vertex<T, Ix>, Vertex<T, Ix>)> for Edge<Ix>
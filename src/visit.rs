@@ -0,0 +1,124 @@
+use crate::edge::Edge;
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Vertex;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// Callbacks for the events of a depth first traversal from sources to
+/// references, see [`BullDag::visit`]. Every method has an empty default
+/// so a visitor only needs to implement the events it cares about.
+pub trait Visitor<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Called the first time the traversal reaches a vertex
+    fn discover_vertex(&mut self, _vertex: &Vertex<T, Ix>) {}
+
+    /// Called once all of a vertex's references have been traversed
+    fn finish_vertex(&mut self, _vertex: &Vertex<T, Ix>) {}
+
+    /// Called for every edge out of a discovered vertex, before the
+    /// reference is visited
+    fn examine_edge(&mut self, _edge: &Edge<Ix>) {}
+
+    /// Called for an edge to a vertex that is still being traversed, i.e.
+    /// an edge that closes a cycle. This can't happen in a graph built
+    /// through `add_edge`, but it can in one that was deserialized.
+    fn back_edge(&mut self, _edge: &Edge<Ix>) {}
+}
+
+#[derive(PartialEq)]
+enum Color {
+    Discovered,
+    Finished,
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Depth first traversal following references from `start`, reporting
+    /// each step to `visitor`. Does nothing if `start` is not in the graph.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    /// use bulldag::visit::Visitor;
+    ///
+    /// struct PostOrder(Vec<&'static str>);
+    ///
+    /// impl Visitor<usize, &'static str> for PostOrder {
+    ///     fn finish_vertex(&mut self, vertex: &Vertex<usize, &'static str>) {
+    ///         self.0.push(vertex.get_index());
+    ///     }
+    /// }
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v2, &v3)]);
+    ///
+    /// let mut visitor = PostOrder(vec![]);
+    /// graph.visit(&"source", &mut visitor);
+    /// assert!(visitor.0 == vec!["ref_reference", "reference", "source"]);
+    /// ```
+    pub fn visit<V: Visitor<T, Ix>>(&self, start: &Ix, visitor: &mut V) {
+        let mut colors: HashMap<Ix, Color> = HashMap::new();
+        self.visit_from(start, visitor, &mut colors);
+    }
+
+    /// Runs [`BullDag::visit`] from every root, visiting each vertex once
+    pub fn visit_all<V: Visitor<T, Ix>>(&self, visitor: &mut V) {
+        let mut colors: HashMap<Ix, Color> = HashMap::new();
+        for root in self.roots.iter() {
+            if !colors.contains_key(root) {
+                self.visit_from(root, visitor, &mut colors);
+            }
+        }
+    }
+
+    fn visit_from<V: Visitor<T, Ix>>(
+        &self,
+        start: &Ix,
+        visitor: &mut V,
+        colors: &mut HashMap<Ix, Color>,
+    ) {
+        let mut stack: Vec<(&Vertex<T, Ix>, Vec<&Ix>)> = vec![];
+        if let Some(vtx) = self.vertices.get(start) {
+            colors.insert(start.clone(), Color::Discovered);
+            visitor.discover_vertex(vtx);
+            stack.push((vtx, vtx.get_references()));
+        }
+
+        while let Some((vtx, remaining)) = stack.last_mut() {
+            let vtx: &Vertex<T, Ix> = vtx;
+            match remaining.pop() {
+                Some(next) => {
+                    let edge = Edge::new(vtx.get_index(), next.clone());
+                    visitor.examine_edge(&edge);
+                    match colors.get(next) {
+                        None => {
+                            if let Some(next_vtx) = self.vertices.get(next) {
+                                colors.insert(next.clone(), Color::Discovered);
+                                visitor.discover_vertex(next_vtx);
+                                stack.push((next_vtx, next_vtx.get_references()));
+                            }
+                        }
+                        Some(Color::Discovered) => visitor.back_edge(&edge),
+                        Some(Color::Finished) => {}
+                    }
+                }
+                None => {
+                    colors.insert(vtx.get_index(), Color::Finished);
+                    visitor.finish_vertex(vtx);
+                    stack.pop();
+                }
+            }
+        }
+    }
+}
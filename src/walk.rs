@@ -0,0 +1,228 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::vertex::Direction;
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Debug;
+
+/// A traversal cursor that keeps its own state and only borrows the graph
+/// for the duration of each step, so the caller is free to mutate the
+/// graph (e.g. vertex data through [`BullDag::get_vertex_mut`]) between
+/// steps.
+pub trait Walker<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Advances the walk, returning the index of the next vertex or
+    /// `None` once the walk is complete
+    fn walk_next(&mut self, graph: &BullDag<T, Ix>) -> Option<Ix>;
+}
+
+/// A depth first (preorder) walk from a starting vertex following either
+/// sources or references.
+///
+/// Example
+///
+/// ```
+/// use bulldag::graph::BullDag;
+/// use bulldag::vertex::{Direction, Vertex};
+/// use bulldag::walk::{Dfs, Walker};
+///
+/// let mut graph: BullDag<usize, &str> = BullDag::new();
+/// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+/// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+/// let v3: Vertex<usize, &str> = Vertex::new(3, "ref_reference");
+/// graph.extend_from_edges(&[(&v1, &v2), (&v2, &v3)]);
+///
+/// let mut dfs = Dfs::new("source", Direction::Reference);
+/// while let Some(ix) = dfs.walk_next(&graph) {
+///     let vtx = graph.get_vertex_mut(ix).unwrap();
+///     vtx.set_data(vtx.get_data() * 10);
+/// }
+/// assert!(graph.get_vertex("ref_reference").unwrap().get_data() == 30);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Dfs<Ix: Index + Debug> {
+    stack: Vec<Ix>,
+    discovered: HashSet<Ix>,
+    direction: Direction,
+}
+
+impl<Ix: Index + Debug> Dfs<Ix> {
+    pub fn new(start: Ix, direction: Direction) -> Dfs<Ix> {
+        Dfs {
+            stack: vec![start],
+            discovered: HashSet::new(),
+            direction,
+        }
+    }
+}
+
+impl<T, Ix> Walker<T, Ix> for Dfs<Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn walk_next(&mut self, graph: &BullDag<T, Ix>) -> Option<Ix> {
+        while let Some(ix) = self.stack.pop() {
+            let vtx = match graph.get_vertex(ix.clone()) {
+                Some(vtx) => vtx,
+                None => continue,
+            };
+
+            if !self.discovered.insert(ix.clone()) {
+                continue;
+            }
+
+            for next in vtx.neighbors(self.direction) {
+                if !self.discovered.contains(next) {
+                    self.stack.push(next.clone());
+                }
+            }
+
+            return Some(ix);
+        }
+
+        None
+    }
+}
+
+/// A breadth first walk from a starting vertex following either sources
+/// or references.
+#[derive(Debug, Clone)]
+pub struct Bfs<Ix: Index + Debug> {
+    queue: VecDeque<Ix>,
+    discovered: HashSet<Ix>,
+    direction: Direction,
+}
+
+impl<Ix: Index + Debug> Bfs<Ix> {
+    pub fn new(start: Ix, direction: Direction) -> Bfs<Ix> {
+        let mut discovered = HashSet::new();
+        discovered.insert(start.clone());
+        Bfs {
+            queue: VecDeque::from(vec![start]),
+            discovered,
+            direction,
+        }
+    }
+}
+
+impl<T, Ix> Walker<T, Ix> for Bfs<Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    fn walk_next(&mut self, graph: &BullDag<T, Ix>) -> Option<Ix> {
+        while let Some(ix) = self.queue.pop_front() {
+            let vtx = match graph.get_vertex(ix.clone()) {
+                Some(vtx) => vtx,
+                None => continue,
+            };
+
+            for next in vtx.neighbors(self.direction) {
+                if self.discovered.insert(next.clone()) {
+                    self.queue.push_back(next.clone());
+                }
+            }
+
+            return Some(ix);
+        }
+
+        None
+    }
+}
+
+/// The vertices reachable from a starting vertex by following references,
+/// each after everything reachable from it, returned by
+/// [`BullDag::post_order`]. The depth first search is only carried as far
+/// as the vertices asked for.
+#[derive(Debug, Clone)]
+pub struct PostOrder<'a, T: Clone + Debug, Ix: Index + Debug> {
+    graph: &'a BullDag<T, Ix>,
+    /// Vertices still to visit, and whether their references have been
+    /// pushed above them
+    pending: Vec<(&'a Ix, bool)>,
+    discovered: HashSet<&'a Ix>,
+}
+
+impl<T, Ix> Iterator for PostOrder<'_, T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    type Item = Ix;
+
+    fn next(&mut self) -> Option<Ix> {
+        while let Some((ix, expanded)) = self.pending.pop() {
+            if expanded {
+                return Some(ix.clone());
+            }
+            if !self.discovered.insert(ix) {
+                continue;
+            }
+            self.pending.push((ix, true));
+            for next in self.graph.vertices[ix].get_references() {
+                if !self.discovered.contains(next) {
+                    self.pending.push((next, false));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns a [`PostOrder`] iterator over the vertices reachable from
+    /// `start` by following references, `start` included, each after
+    /// everything reachable from it. Nothing is returned if there is no
+    /// vertex at `start`.
+    ///
+    /// Example
+    ///
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v2, &v3), (&v1, &v3)]);
+    ///
+    /// let order: Vec<&str> = graph.post_order(&"source").collect();
+    /// assert!(order == vec!["ref_reference", "reference", "source"]);
+    ///
+    /// let order: Vec<&str> = graph.reverse_post_order(&"source").collect();
+    /// assert!(order == vec!["source", "reference", "ref_reference"]);
+    /// ```
+    pub fn post_order(&self, start: &Ix) -> PostOrder<'_, T, Ix> {
+        PostOrder {
+            graph: self,
+            pending: self
+                .vertices
+                .get_key_value(start)
+                .map(|(ix, _)| (ix, false))
+                .into_iter()
+                .collect(),
+            discovered: HashSet::new(),
+        }
+    }
+
+    /// Returns the vertices reachable from `start` by following
+    /// references in the reverse of [`BullDag::post_order`], which puts
+    /// each vertex before everything reachable from it, like a topological
+    /// order of just those vertices. Unlike the post order the whole search
+    /// is done up front.
+    pub fn reverse_post_order(&self, start: &Ix) -> impl Iterator<Item = Ix> {
+        self.post_order(start)
+            .collect::<Vec<Ix>>()
+            .into_iter()
+            .rev()
+    }
+}
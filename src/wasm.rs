@@ -0,0 +1,158 @@
+use crate::graph::{BullDag, GraphError};
+use crate::vertex::{Direction, Vertex};
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+/// A JavaScript friendly wrapper around a [`BullDag`] with string indices
+/// and JSON vertex data, so that graphs can be built and queried in the
+/// browser with the same cycle detection as everywhere else. Errors are
+/// returned as strings, which become exceptions on the JS side.
+#[wasm_bindgen(js_name = BullDag)]
+#[derive(Debug, Clone, Default)]
+pub struct JsBullDag {
+    graph: BullDag<Value, String>,
+}
+
+fn error_message(e: GraphError) -> String {
+    e.to_string()
+}
+
+#[wasm_bindgen(js_class = BullDag)]
+impl JsBullDag {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsBullDag {
+        JsBullDag {
+            graph: BullDag::new(),
+        }
+    }
+
+    /// Parses a graph from the adjacency-list JSON format, see
+    /// [`BullDag::from_adjacency_json`]
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<JsBullDag, String> {
+        let graph = BullDag::from_adjacency_json(json).map_err(error_message)?;
+        Ok(JsBullDag { graph })
+    }
+
+    /// Serializes the graph in the adjacency-list JSON format
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, String> {
+        self.graph.to_adjacency_json().map_err(|e| e.to_string())
+    }
+
+    /// Adds a vertex, or replaces the data of an existing one. `data` must
+    /// be valid JSON.
+    #[wasm_bindgen(js_name = addVertex)]
+    pub fn add_vertex(&mut self, index: String, data: &str) -> Result<(), String> {
+        let data: Value = serde_json::from_str(data).map_err(|e| e.to_string())?;
+        self.graph.add_vertex(&Vertex::new(data, index));
+        Ok(())
+    }
+
+    /// Adds an edge between two existing vertices, failing if either is
+    /// missing or if the edge would create a cycle
+    #[wasm_bindgen(js_name = addEdge)]
+    pub fn add_edge(&mut self, source: String, reference: String) -> Result<(), String> {
+        self.graph
+            .add_edge_ix(&source, &reference)
+            .map(|_| ())
+            .map_err(error_message)
+    }
+
+    #[wasm_bindgen(js_name = hasVertex)]
+    pub fn has_vertex(&self, index: String) -> bool {
+        self.graph.contains_vertex(&index)
+    }
+
+    /// Returns the vertex's data as JSON
+    #[wasm_bindgen(js_name = getData)]
+    pub fn get_data(&self, index: String) -> Option<String> {
+        self.graph
+            .get_vertex(index)
+            .map(|vtx| vtx.get_data().to_string())
+    }
+
+    pub fn len(&self) -> usize {
+        self.graph.len()
+    }
+
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.graph.is_empty()
+    }
+
+    #[wasm_bindgen(js_name = edgeCount)]
+    pub fn n_edges(&self) -> usize {
+        self.graph.n_edges()
+    }
+
+    pub fn roots(&self) -> Vec<String> {
+        sorted(self.graph.get_roots())
+    }
+
+    pub fn leaves(&self) -> Vec<String> {
+        sorted(self.graph.get_leaves())
+    }
+
+    /// The vertex's direct sources
+    pub fn sources(&self, index: String) -> Vec<String> {
+        self.neighbors(index, Direction::Source)
+    }
+
+    /// The vertex's direct references
+    pub fn references(&self, index: String) -> Vec<String> {
+        self.neighbors(index, Direction::Reference)
+    }
+
+    /// Every vertex the given vertex can be reached from, not including
+    /// itself
+    pub fn ancestors(&self, index: String) -> Vec<String> {
+        self.trace(index, Direction::Source)
+    }
+
+    /// Every vertex reachable from the given vertex, not including itself
+    pub fn descendants(&self, index: String) -> Vec<String> {
+        self.trace(index, Direction::Reference)
+    }
+}
+
+impl JsBullDag {
+    fn neighbors(&self, index: String, direction: Direction) -> Vec<String> {
+        self.graph
+            .get_vertex(index)
+            .map(|vtx| sorted(vtx.neighbors(direction).into_iter().cloned()))
+            .unwrap_or_default()
+    }
+
+    fn trace(&self, index: String, direction: Direction) -> Vec<String> {
+        self.graph
+            .get_vertex(index.clone())
+            .map(|vtx| {
+                sorted(
+                    self.graph
+                        .trace(vtx, direction)
+                        .into_iter()
+                        .filter(|ix| ix != &index),
+                )
+            })
+            .unwrap_or_default()
+    }
+}
+
+fn sorted(indices: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut indices: Vec<String> = indices.into_iter().collect();
+    indices.sort();
+    indices
+}
+
+impl From<BullDag<Value, String>> for JsBullDag {
+    fn from(graph: BullDag<Value, String>) -> Self {
+        JsBullDag { graph }
+    }
+}
+
+impl From<JsBullDag> for BullDag<Value, String> {
+    fn from(graph: JsBullDag) -> Self {
+        graph.graph
+    }
+}
@@ -0,0 +1,196 @@
+use crate::edge::{Edge, IntoEdgeSpec};
+use crate::graph::{BullDag, GraphResult};
+use crate::index::Index;
+use crate::observe::Event;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// The lightest paths reaching a vertex, lightest first, each as its
+/// weight and the source and rank of the path it extends
+type Paths<'a, Ix> = Vec<(u64, Option<(&'a Ix, usize)>)>;
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Adds an edge with the given weight, like [`BullDag::try_add_edge`].
+    /// If the vertices are already connected the edge's weight is replaced.
+    /// Edges added without a weight weigh 1.
+    pub fn add_edge_weighted(
+        &mut self,
+        edge: impl IntoEdgeSpec<T, Ix>,
+        weight: u64,
+    ) -> GraphResult {
+        let spec = edge.into_edge_spec();
+        let e = spec.edge();
+        self.try_add_edge(spec)?;
+        self.set_edge_weight(&e, weight);
+        Ok(())
+    }
+
+    /// Changes the weight of an edge, returning false if there is no such
+    /// edge
+    pub fn set_edge_weight(&mut self, edge: &Edge<Ix>, weight: u64) -> bool {
+        if !self.edges.contains(edge) {
+            return false;
+        }
+        self.edge_weights.insert(edge, weight);
+        self.notify(Event::EdgeWeightSet(edge, weight));
+        true
+    }
+
+    /// Returns the weight of an edge, or `None` if there is no such edge
+    pub fn edge_weight(&self, edge: &Edge<Ix>) -> Option<u64> {
+        if !self.edges.contains(edge) {
+            return None;
+        }
+        Some(self.edge_weights.get(edge).copied().unwrap_or(1))
+    }
+
+    /// Returns the weight of the edge from `source` to `reference`, taking
+    /// the edge to exist
+    fn weight_between(&self, source: &Ix, reference: &Ix) -> u64 {
+        self.edge_weights
+            .between(source, reference)
+            .copied()
+            .unwrap_or(1)
+    }
+
+    /// Returns the lightest path from `from` to `to` following references,
+    /// both ends included, and its total weight, or `None` if `to` can't be
+    /// reached from `from`. As the graph has no cycles the vertices can be
+    /// relaxed once each in topological order, without a priority queue.
+    /// Ties go to whichever path is found first. Weights add up saturating
+    /// at `u64::MAX`.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// graph.add_edge_weighted((&v1, &v2), 10).unwrap();
+    /// graph.add_edge_weighted((&v2, &v4), 10).unwrap();
+    /// graph.add_edge_weighted((&v1, &v3), 1).unwrap();
+    /// graph.add_edge_weighted((&v3, &v4), 2).unwrap();
+    ///
+    /// let (path, weight) = graph.shortest_path_weighted(&"source", &"ref_reference").unwrap();
+    /// assert!(path == vec!["source", "reference_2", "ref_reference"]);
+    /// assert!(weight == 3);
+    /// assert!(graph.shortest_path_weighted(&"ref_reference", &"source").is_none());
+    /// ```
+    pub fn shortest_path_weighted(&self, from: &Ix, to: &Ix) -> Option<(Vec<Ix>, u64)> {
+        if !self.contains_vertex(from) || !self.contains_vertex(to) {
+            return None;
+        }
+
+        let order = self.topological_order();
+        // The lightest known weight of each vertex reached, and the vertex
+        // it was reached from
+        let mut best: HashMap<&Ix, (u64, Option<&Ix>)> = HashMap::from([(from, (0, None))]);
+        for ix in &order {
+            if ix == to {
+                break;
+            }
+            let Some((weight, _)) = best.get(ix).copied() else {
+                continue;
+            };
+            for r in self.vertices[ix].get_references() {
+                let through = weight.saturating_add(self.weight_between(ix, r));
+                if best.get(r).is_none_or(|(known, _)| through < *known) {
+                    best.insert(r, (through, Some(ix)));
+                }
+            }
+        }
+
+        let (weight, _) = best.get(to).copied()?;
+        let mut path = vec![to.clone()];
+        let mut at = to;
+        while let Some((_, Some(previous))) = best.get(at) {
+            path.push((*previous).clone());
+            at = previous;
+        }
+        path.reverse();
+        Some((path, weight))
+    }
+
+    /// Returns up to `k` different paths from `from` to `to` following
+    /// references, both ends included, with their total weights, the
+    /// lightest first. Paths of equal weight come in no particular order.
+    /// Any path in the graph is loop-free, so instead of Yen's algorithm
+    /// each vertex keeps the `k` lightest paths reaching it, built from
+    /// those of its sources in topological order.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// graph.add_edge_weighted((&v1, &v2), 10).unwrap();
+    /// graph.add_edge_weighted((&v2, &v4), 10).unwrap();
+    /// graph.add_edge_weighted((&v1, &v3), 1).unwrap();
+    /// graph.add_edge_weighted((&v3, &v4), 2).unwrap();
+    /// graph.add_edge_weighted((&v1, &v4), 5).unwrap();
+    ///
+    /// let paths = graph.k_shortest_paths(&"source", &"ref_reference", 2);
+    /// assert!(paths.len() == 2);
+    /// assert!(paths[0] == (vec!["source", "reference_2", "ref_reference"], 3));
+    /// assert!(paths[1] == (vec!["source", "ref_reference"], 5));
+    /// assert!(graph.k_shortest_paths(&"source", &"ref_reference", 10).len() == 3);
+    /// ```
+    pub fn k_shortest_paths(&self, from: &Ix, to: &Ix, k: usize) -> Vec<(Vec<Ix>, u64)> {
+        if k == 0 || !self.contains_vertex(from) || !self.contains_vertex(to) {
+            return vec![];
+        }
+
+        let order = self.topological_order();
+        let mut best: HashMap<&Ix, Paths<Ix>> = HashMap::from([(from, vec![(0, None)])]);
+        for ix in &order {
+            if ix == to {
+                break;
+            }
+            let Some(paths) = best.get(ix) else {
+                continue;
+            };
+            let weights: Vec<u64> = paths.iter().map(|(weight, _)| *weight).collect();
+            for r in self.vertices[ix].get_references() {
+                let edge = self.weight_between(ix, r);
+                let paths = best.entry(r).or_default();
+                paths.extend(
+                    weights
+                        .iter()
+                        .enumerate()
+                        .map(|(rank, weight)| (weight.saturating_add(edge), Some((ix, rank)))),
+                );
+                paths.sort_by_key(|(weight, _)| *weight);
+                paths.truncate(k);
+            }
+        }
+
+        let Some(paths) = best.get(to) else {
+            return vec![];
+        };
+        paths
+            .iter()
+            .map(|(weight, mut previous)| {
+                let mut path = vec![to.clone()];
+                while let Some((ix, rank)) = previous {
+                    path.push(ix.clone());
+                    previous = best[ix][rank].1;
+                }
+                path.reverse();
+                (path, *weight)
+            })
+            .collect()
+    }
+}
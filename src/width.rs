@@ -0,0 +1,165 @@
+use crate::graph::BullDag;
+use crate::index::Index;
+use crate::reachability::ReachabilityIndex;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+const UNMATCHED: usize = usize::MAX;
+
+impl<T, Ix> BullDag<T, Ix>
+where
+    T: Clone + Debug,
+    Ix: Index + Debug,
+{
+    /// Returns a lower bound on the width of the graph, the most vertices
+    /// that can run at once because none of them can be reached from
+    /// another. The bound is the size of the largest of the
+    /// [`BullDag::generations`], each of which is such a set, so it takes
+    /// a single topological pass. See [`BullDag::exact_width`] for the
+    /// exact width.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4)]);
+    ///
+    /// assert!(graph.width() == 2);
+    /// assert!(graph.exact_width() == 2);
+    /// ```
+    pub fn width(&self) -> usize {
+        self.generations()
+            .iter()
+            .map(|generation| generation.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the width of the graph, the most vertices none of which can
+    /// be reached from another. By Dilworth's theorem this is the number
+    /// of vertices minus a maximum matching between each vertex and the
+    /// vertices reachable from it, which is found with augmenting paths
+    /// over the transitive closure. That takes a bit per pair of vertices
+    /// and time cubic in the number of vertices in the worst case, so
+    /// prefer [`BullDag::width`] on large graphs.
+    pub fn exact_width(&self) -> usize {
+        let (_, matched_to) = self.closure_matching();
+        matched_to.iter().filter(|to| **to == UNMATCHED).count()
+    }
+
+    /// Splits the graph into as few chains as possible, where every vertex
+    /// is in exactly one chain and can be reached from each vertex before
+    /// it in its chain, though not necessarily by a direct edge. Running
+    /// each chain in order on its own worker respects every dependency
+    /// within the chain. There are as many chains as the
+    /// [`BullDag::exact_width`], and finding them costs as much.
+    ///
+    /// Example:
+    /// ```
+    /// use bulldag::graph::BullDag;
+    /// use bulldag::vertex::Vertex;
+    ///
+    /// let mut graph: BullDag<usize, &str> = BullDag::new();
+    /// let v1: Vertex<usize, &str> = Vertex::new(5, "source");
+    /// let v2: Vertex<usize, &str> = Vertex::new(4, "reference_1");
+    /// let v3: Vertex<usize, &str> = Vertex::new(3, "reference_2");
+    /// let v4: Vertex<usize, &str> = Vertex::new(2, "ref_reference");
+    /// graph.extend_from_edges(&[(&v1, &v2), (&v1, &v3), (&v2, &v4)]);
+    ///
+    /// // One chain runs from "source" through either reference, the other
+    /// // holds whatever is left
+    /// let chains = graph.chain_decomposition();
+    /// assert!(chains.len() == 2);
+    /// assert!(chains.iter().map(|chain| chain.len()).sum::<usize>() == 4);
+    /// assert!(chains.iter().any(|chain| chain[0] == "source"));
+    /// ```
+    pub fn chain_decomposition(&self) -> Vec<Vec<Ix>> {
+        let (indices, matched_to) = self.closure_matching();
+        let mut is_matched = vec![false; indices.len()];
+        matched_to
+            .iter()
+            .filter(|to| **to != UNMATCHED)
+            .for_each(|to| is_matched[*to] = true);
+
+        (0..indices.len())
+            .filter(|id| !is_matched[*id])
+            .map(|start| {
+                let mut chain = vec![indices[start].clone()];
+                let mut id = start;
+                while matched_to[id] != UNMATCHED {
+                    id = matched_to[id];
+                    chain.push(indices[id].clone());
+                }
+                chain
+            })
+            .collect()
+    }
+
+    /// Finds a maximum matching between each vertex and the vertices
+    /// reachable from it, returning the vertices by id along with the id
+    /// each one is matched to
+    fn closure_matching(&self) -> (Vec<Ix>, Vec<usize>) {
+        let built;
+        let closure = match self.reachability_index() {
+            Some(index) => index,
+            None => {
+                built = ReachabilityIndex::new(self);
+                &built
+            }
+        };
+        let n = closure.len();
+        let mut reachable: Vec<Vec<usize>> = vec![vec![]; n];
+        for id in 0..n {
+            closure
+                .ancestor_ids(id)
+                .for_each(|ancestor| reachable[ancestor].push(id));
+        }
+
+        // Kuhn's algorithm, with a breadth first search for each augmenting
+        // path so that long chains can't overflow the stack.
+        let mut matched_to = vec![UNMATCHED; n];
+        let mut matched_from = vec![UNMATCHED; n];
+        for start in 0..n {
+            let mut parent = vec![UNMATCHED; n];
+            let mut queue = VecDeque::from([start]);
+            'search: while let Some(from) = queue.pop_front() {
+                for &to in &reachable[from] {
+                    if parent[to] != UNMATCHED {
+                        continue;
+                    }
+                    parent[to] = from;
+                    if matched_from[to] != UNMATCHED {
+                        queue.push_back(matched_from[to]);
+                        continue;
+                    }
+                    // Flip the path back to `start`
+                    let mut to = to;
+                    loop {
+                        let from = parent[to];
+                        let next = matched_to[from];
+                        matched_to[from] = to;
+                        matched_from[to] = from;
+                        if from == start {
+                            break;
+                        }
+                        to = next;
+                    }
+                    break 'search;
+                }
+            }
+        }
+
+        let mut indices: Vec<Option<Ix>> = vec![None; n];
+        closure
+            .ids()
+            .iter()
+            .for_each(|(ix, id)| indices[*id] = Some(ix.clone()));
+        (indices.into_iter().flatten().collect(), matched_to)
+    }
+}